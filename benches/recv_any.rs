@@ -0,0 +1,79 @@
+//! Benchmark of `InChannels::recv_any` on a node with many input channels.
+//!
+//! 1000 source nodes send one packet each to a sink node, which receives all of them with
+//! `recv_any`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use dagrs::{
+    Action, Content, DefaultNode, EnvVar, Graph, InChannels, Node, NodeName, NodeTable,
+    OutChannels, Output,
+};
+
+const SOURCES: usize = 1000;
+
+struct SendAction;
+
+#[async_trait]
+impl Action for SendAction {
+    async fn run(
+        &self,
+        _: &mut InChannels,
+        out_channels: &mut OutChannels,
+        _: Arc<EnvVar>,
+    ) -> Output {
+        out_channels.broadcast(Content::new(1usize)).await;
+        Output::empty()
+    }
+}
+
+struct RecvAnyAction;
+
+#[async_trait]
+impl Action for RecvAnyAction {
+    async fn run(
+        &self,
+        in_channels: &mut InChannels,
+        _: &mut OutChannels,
+        _: Arc<EnvVar>,
+    ) -> Output {
+        let mut sum = 0;
+        while let Ok((_, content)) = in_channels.recv_any().await {
+            sum += content.get::<usize>().unwrap();
+            if sum == SOURCES {
+                break;
+            }
+        }
+        Output::new(sum)
+    }
+}
+
+fn build_graph() -> Graph {
+    let mut graph = Graph::new();
+    let mut node_table = NodeTable::new();
+    let sink = DefaultNode::with_action(NodeName::from("sink"), RecvAnyAction, &mut node_table);
+    let sink_id = sink.id();
+    graph.add_node(sink);
+    for i in 0..SOURCES {
+        let source = DefaultNode::with_action(format!("source {}", i), SendAction, &mut node_table);
+        let source_id = source.id();
+        graph.add_node(source);
+        graph.connect(source_id, sink_id).unwrap();
+    }
+    graph
+}
+
+fn recv_any(c: &mut Criterion) {
+    c.bench_function("recv_any 1000 channels", |b| {
+        b.iter_batched(
+            build_graph,
+            |mut graph| graph.start().unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, recv_any);
+criterion_main!(benches);
@@ -0,0 +1,103 @@
+//! Benchmark of `OutChannels::send_batch` against individual sends.
+//!
+//! A source node sends 10000 packets to a sink node, which receives all of them, either with
+//! one `send_to` per packet, or with `send_batch` in batches of 100 packets.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use dagrs::{
+    Action, Content, DefaultNode, EnvVar, Graph, InChannels, Node, NodeName, NodeTable,
+    OutChannels, Output,
+};
+
+const PACKETS: usize = 10000;
+const BATCH: usize = 100;
+
+struct SendAction {
+    batched: bool,
+}
+
+#[async_trait]
+impl Action for SendAction {
+    async fn run(
+        &self,
+        _: &mut InChannels,
+        out_channels: &mut OutChannels,
+        _: Arc<EnvVar>,
+    ) -> Output {
+        let id = out_channels.get_receiver_ids()[0];
+        if self.batched {
+            for _ in 0..PACKETS / BATCH {
+                let batch = (0..BATCH).map(|_| Content::new(1usize)).collect();
+                out_channels.send_batch(&id, batch).await.unwrap();
+            }
+        } else {
+            for _ in 0..PACKETS {
+                out_channels
+                    .send_to(&id, Content::new(1usize))
+                    .await
+                    .unwrap();
+            }
+        }
+        Output::empty()
+    }
+}
+
+struct RecvAction;
+
+#[async_trait]
+impl Action for RecvAction {
+    async fn run(
+        &self,
+        in_channels: &mut InChannels,
+        _: &mut OutChannels,
+        _: Arc<EnvVar>,
+    ) -> Output {
+        let mut sum = 0;
+        while let Ok((_, content)) = in_channels.recv_any().await {
+            sum += content.get::<usize>().unwrap();
+            if sum == PACKETS {
+                break;
+            }
+        }
+        Output::new(sum)
+    }
+}
+
+fn build_graph(batched: bool) -> Graph {
+    let mut graph = Graph::new();
+    let mut node_table = NodeTable::new();
+    let source = DefaultNode::with_action(
+        NodeName::from("source"),
+        SendAction { batched },
+        &mut node_table,
+    );
+    let sink = DefaultNode::with_action(NodeName::from("sink"), RecvAction, &mut node_table);
+    let (source_id, sink_id) = (source.id(), sink.id());
+    graph.add_node(source);
+    graph.add_node(sink);
+    graph.connect(source_id, sink_id).unwrap();
+    graph
+}
+
+fn send_batch(c: &mut Criterion) {
+    c.bench_function("send_to 10000 packets", |b| {
+        b.iter_batched(
+            || build_graph(false),
+            |mut graph| graph.start().unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("send_batch 10000 packets", |b| {
+        b.iter_batched(
+            || build_graph(true),
+            |mut graph| graph.start().unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, send_batch);
+criterion_main!(benches);
@@ -90,11 +90,11 @@ pub(crate) fn auto_node(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
-    return quote! {
+    quote! {
         #item_struct
         #auto_impl
     }
-    .into();
+    .into()
 }
 
 fn auto_impl_node(
@@ -113,6 +113,7 @@ fn auto_impl_node(
         impl_in_channels(field_in_channels),
         impl_out_channels(field_out_channels),
         impl_run(field_action, field_in_channels, field_out_channels),
+        impl_priority(field_action),
     ]);
 
     quote::quote!(
@@ -177,3 +178,12 @@ fn impl_run(
         }
     )
 }
+
+fn impl_priority(field: &Field) -> proc_macro2::TokenStream {
+    let ident = &field.ident;
+    quote::quote!(
+        fn priority(&self) -> u8 {
+            self.#ident.priority()
+        }
+    )
+}
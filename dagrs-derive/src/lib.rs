@@ -1,6 +1,8 @@
 use proc_macro::TokenStream;
 #[cfg(feature = "derive")]
 mod auto_node;
+#[cfg(feature = "derive")]
+mod node;
 mod relay;
 
 /// [`auto_node`] is a macro that may be used when customizing nodes. It can only be
@@ -36,7 +38,38 @@ mod relay;
 #[proc_macro_attribute]
 pub fn auto_node(args: TokenStream, input: TokenStream) -> TokenStream {
     use crate::auto_node::auto_node;
-    auto_node(args, input).into()
+    auto_node(args, input)
+}
+
+/// [`node`] is a macro turning a plain `async fn` into an action, without writing a struct
+/// and its `dagrs::Action` implementation by hand.
+///
+/// The function must take the arguments of `dagrs::Action::run`, that is
+/// `(&mut InChannels, &mut OutChannels, Arc<EnvVar>)`, and return a `dagrs::Output`. The macro
+/// replaces it with a unit struct of the same name and visibility, implementing
+/// `dagrs::Action`, which is given to a node like any other action.
+///
+/// A function has no state: the state of an action, or its priority, calls for implementing
+/// `dagrs::Action` on a struct instead. Generic functions, methods and non-async functions
+/// are rejected with a compile error.
+///
+/// ## Example
+/// ```ignore
+/// use std::sync::Arc;
+/// use dagrs::{node, Content, DefaultNode, EnvVar, InChannels, NodeTable, OutChannels, Output};
+///
+/// #[node]
+/// async fn hello(_: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+///     Output::new("Hello Dagrs".to_string())
+/// }
+///
+/// let mut node_table = NodeTable::new();
+/// let node = DefaultNode::with_action("Hello".to_string(), hello, &mut node_table);
+/// ```
+#[cfg(feature = "derive")]
+#[proc_macro_attribute]
+pub fn node(args: TokenStream, input: TokenStream) -> TokenStream {
+    node::node(args, input)
 }
 
 /// The [`dependencies!`] macro allows users to specify all task dependencies in an easy-to-understand
@@ -0,0 +1,73 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse, parse_macro_input, FnArg, ItemFn};
+
+/// Turn an `async fn` into a unit struct implementing `Action`.
+///
+/// Step 1: check the function is async, not generic, and takes the three arguments of
+/// `Action::run`, without receiver.
+///
+/// Step 2: generate a unit struct named after the function, with its visibility and attributes.
+///
+/// Step 3: implement `Action` for the struct, running the body of the function.
+pub(crate) fn node(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item_fn = parse_macro_input!(input as ItemFn);
+    let _ = parse_macro_input!(args as parse::Nothing);
+
+    let sig = &item_fn.sig;
+    if sig.asyncness.is_none() {
+        return error(
+            sig.fn_token,
+            "`node` macro can only be annotated on async functions.",
+        );
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return error(
+            &sig.generics,
+            "`node` macro does not support generic functions, implement `dagrs::Action` on a struct instead.",
+        );
+    }
+    if let Some(receiver) = sig
+        .inputs
+        .iter()
+        .find(|arg| matches!(arg, FnArg::Receiver(_)))
+    {
+        return error(
+            receiver,
+            "`node` macro can only be annotated on free functions.",
+        );
+    }
+    if sig.inputs.len() != 3 {
+        return error(
+            &sig.inputs,
+            "`node` macro expects the arguments `(&mut InChannels, &mut OutChannels, Arc<EnvVar>)`.",
+        );
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item_fn;
+    let ident = &sig.ident;
+    let inputs = &sig.inputs;
+    let output = &sig.output;
+    quote! {
+        #(#attrs)*
+        #[allow(non_camel_case_types)]
+        #vis struct #ident;
+
+        #[dagrs::async_trait::async_trait]
+        impl dagrs::Action for #ident {
+            async fn run(&self, #inputs) #output #block
+        }
+    }
+    .into()
+}
+
+fn error(tokens: impl quote::ToTokens, message: &str) -> TokenStream {
+    syn::Error::new_spanned(tokens, message)
+        .into_compile_error()
+        .into()
+}
@@ -80,12 +80,12 @@ fn main() {
         .for_each(|node| graph.add_node(node));
 
     // Set up task dependencies.
-    graph.add_edge(a_id, vec![b_id, c_id, d_id]);
-    graph.add_edge(b_id, vec![e_id, g_id]);
-    graph.add_edge(c_id, vec![e_id, f_id]);
-    graph.add_edge(d_id, vec![f_id]);
-    graph.add_edge(e_id, vec![g_id]);
-    graph.add_edge(f_id, vec![g_id]);
+    graph.add_edge(a_id, vec![b_id, c_id, d_id]).unwrap();
+    graph.add_edge(b_id, vec![e_id, g_id]).unwrap();
+    graph.add_edge(c_id, vec![e_id, f_id]).unwrap();
+    graph.add_edge(d_id, vec![f_id]).unwrap();
+    graph.add_edge(e_id, vec![g_id]).unwrap();
+    graph.add_edge(f_id, vec![g_id]).unwrap();
 
     // Set a global environment variable for this dag.
     let mut env = EnvVar::new(node_table);
@@ -121,13 +121,13 @@ fn main() {
     graph.add_node(x);
 
     // Set up task dependencies.
-    graph.add_edge(a_id, vec![b_id, c_id, d_id]);
-    graph.add_edge(b_id, vec![e_id, x_id]);
-    graph.add_edge(c_id, vec![e_id, f_id]);
-    graph.add_edge(d_id, vec![f_id]);
-    graph.add_edge(e_id, vec![x_id]);
-    graph.add_edge(x_id, vec![g_id]);
-    graph.add_edge(f_id, vec![g_id]);
+    graph.add_edge(a_id, vec![b_id, c_id, d_id]).unwrap();
+    graph.add_edge(b_id, vec![e_id, x_id]).unwrap();
+    graph.add_edge(c_id, vec![e_id, f_id]).unwrap();
+    graph.add_edge(d_id, vec![f_id]).unwrap();
+    graph.add_edge(e_id, vec![x_id]).unwrap();
+    graph.add_edge(x_id, vec![g_id]).unwrap();
+    graph.add_edge(f_id, vec![g_id]).unwrap();
 
     // Set a global environment variable for this dag.
     let mut env = EnvVar::new(node_table);
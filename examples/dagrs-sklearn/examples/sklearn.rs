@@ -35,7 +35,7 @@ impl Action for NodeAction {
                 let content: Arc<(Vec<String>, Vec<String>)> =
                     content.unwrap().into_inner().unwrap();
                 let (stdout, _) = (&content.0, &content.1);
-                let theta = stdout.get(0).unwrap().clone();
+                let theta = stdout.first().unwrap().clone();
                 out_channels.broadcast(Content::new(theta)).await
             }
             Output::Err(e) => panic!("{}", e),
@@ -148,7 +148,7 @@ fn main() {
     let acc = if cfg!(target_os = "windows") {
         stdout.get(1).unwrap()
     } else {
-        stdout.get(0).unwrap()
+        stdout.first().unwrap()
     };
     assert_eq!("Accuracy: 94.46%", acc);
 
@@ -38,5 +38,5 @@ fn yaml_task_failed_execute() {
         .parse_tasks("tests/config/script_run_failed.yaml", HashMap::new())
         .unwrap();
     let res = res.start();
-    assert!(!res.is_ok())
+    assert!(res.is_err())
 }
@@ -1,21 +1,24 @@
 //! # Example: hello_dagrs
-//! Creates a `DefaultNode` that returns with "Hello Dagrs",
+//! Creates a `DefaultNode` named "Hello Dagrs" that returns with its own name,
 //! then create a new `Graph` with this node and run.
 
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use dagrs::{
-    Action, Content, DefaultNode, EnvVar, Graph, InChannels, Node, NodeTable, OutChannels, Output,
+    Action, Content, DefaultNode, EnvVar, Graph, InChannels, Node, NodeContext, NodeTable,
+    OutChannels, Output,
 };
 
-/// An implementation of [`Action`] that returns [`Output::Out`] containing a String "Hello world".
+/// An implementation of [`Action`] that returns [`Output::Out`] containing the name of the node
+/// it runs in, read from the [`NodeContext`].
 #[derive(Default)]
 pub struct HelloAction;
 #[async_trait]
 impl Action for HelloAction {
     async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
-        Output::Out(Some(Content::new("Hello Dagrs".to_string())))
+        let ctx = NodeContext::current();
+        Output::Out(Some(Content::new(ctx.name().to_string())))
     }
 }
 
@@ -23,11 +26,8 @@ fn main() {
     // create an empty `NodeTable`
     let mut node_table = NodeTable::new();
     // create a `DefaultNode` with action `HelloAction`
-    let hello_node = DefaultNode::with_action(
-        "Hello Dagrs".to_string(),
-        HelloAction::default(),
-        &mut node_table,
-    );
+    let hello_node =
+        DefaultNode::with_action("Hello Dagrs".to_string(), HelloAction, &mut node_table);
     let id: &dagrs::NodeId = &hello_node.id();
 
     // create a graph with this node and run
@@ -40,9 +40,8 @@ fn main() {
             let outputs = graph.get_outputs();
             assert_eq!(outputs.len(), 1);
 
-            let content = outputs.get(id).unwrap().get_out().unwrap();
-            let node_output = content.get::<String>().unwrap();
-            assert_eq!(node_output, "Hello Dagrs")
+            let node_output = graph.get_output_as::<String>(id).unwrap();
+            assert_eq!(*node_output, "Hello Dagrs")
         }
         Err(e) => {
             eprintln!("Graph execution failed: {:?}", e);
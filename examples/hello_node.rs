@@ -0,0 +1,36 @@
+//! # Example: hello_node
+//! Same as `hello_dagrs`, with the action written as a plain `async fn` marked with the
+//! procedural macro `node`, rather than a struct implementing `Action`.
+
+use std::sync::Arc;
+
+use dagrs::{node, DefaultNode, EnvVar, Graph, InChannels, Node, NodeTable, OutChannels, Output};
+
+/// An action that returns [`Output::Out`] containing a String "Hello Dagrs".
+#[node]
+async fn hello(_: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+    Output::new("Hello Dagrs".to_string())
+}
+
+fn main() {
+    // create an empty `NodeTable`
+    let mut node_table = NodeTable::new();
+    // create a `DefaultNode` with the action `hello` generated by the macro
+    let hello_node = DefaultNode::with_action("Hello Dagrs".to_string(), hello, &mut node_table);
+    let id: &dagrs::NodeId = &hello_node.id();
+
+    // create a graph with this node and run
+    let mut graph = Graph::new();
+    graph.add_node(hello_node);
+
+    match graph.start() {
+        Ok(_) => {
+            // verify the output of this node
+            let node_output = graph.get_output_as::<String>(id).unwrap();
+            assert_eq!(*node_output, "Hello Dagrs")
+        }
+        Err(e) => {
+            eprintln!("Graph execution failed: {:?}", e);
+        }
+    }
+}
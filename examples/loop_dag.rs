@@ -93,7 +93,7 @@ impl Action for ProcAction {
         _env: Arc<EnvVar>,
     ) -> Output {
         let mut times = 0usize;
-        while let Ok(_) = in_channels.recv_from(&self.inter_node).await {
+        while in_channels.recv_from(&self.inter_node).await.is_ok() {
             log::info!("`Proc` send {} to INTER node", times);
             out_channels
                 .send_to(
@@ -145,9 +145,9 @@ fn main() {
     graph.add_node(inter_proc);
 
     // Set up dependencies to create the loop
-    graph.add_edge(in_id, vec![inter_id]);
-    graph.add_edge(inter_id, vec![proc_id]);
-    graph.add_edge(proc_id, vec![inter_id]);
+    graph.add_edge(in_id, vec![inter_id]).unwrap();
+    graph.add_edge(inter_id, vec![proc_id]).unwrap();
+    graph.add_edge(proc_id, vec![inter_id]).unwrap();
 
     // Execute graph
     match graph.start() {
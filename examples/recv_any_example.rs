@@ -154,11 +154,8 @@ fn main() {
     );
 
     // Create receiver node
-    let receiver = DefaultNode::with_action(
-        "Receiver".to_string(),
-        ReceiverAction::default(),
-        &mut node_table,
-    );
+    let receiver =
+        DefaultNode::with_action("Receiver".to_string(), ReceiverAction, &mut node_table);
 
     // Get node IDs before adding nodes to the graph
     let sender1_id = sender1.id();
@@ -174,8 +171,8 @@ fn main() {
     graph.add_node(receiver);
 
     // Add edges: both senders connect to the receiver
-    graph.add_edge(sender1_id, vec![receiver_id]);
-    graph.add_edge(sender2_id, vec![receiver_id]);
+    graph.add_edge(sender1_id, vec![receiver_id]).unwrap();
+    graph.add_edge(sender2_id, vec![receiver_id]).unwrap();
 
     // Run the graph
     match graph.start() {
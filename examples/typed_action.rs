@@ -93,12 +93,12 @@ fn main() {
         .into_iter()
         .for_each(|node| graph.add_node(node));
 
-    graph.add_edge(a_id, vec![b_id, c_id, d_id]);
-    graph.add_edge(b_id, vec![e_id, g_id]);
-    graph.add_edge(c_id, vec![e_id, f_id]);
-    graph.add_edge(d_id, vec![f_id]);
-    graph.add_edge(e_id, vec![g_id]);
-    graph.add_edge(f_id, vec![g_id]);
+    graph.add_edge(a_id, vec![b_id, c_id, d_id]).unwrap();
+    graph.add_edge(b_id, vec![e_id, g_id]).unwrap();
+    graph.add_edge(c_id, vec![e_id, f_id]).unwrap();
+    graph.add_edge(d_id, vec![f_id]).unwrap();
+    graph.add_edge(e_id, vec![g_id]).unwrap();
+    graph.add_edge(f_id, vec![g_id]).unwrap();
 
     let mut env = EnvVar::new(node_table);
     env.set("base", 2usize);
@@ -0,0 +1,111 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU8, Ordering},
+    Arc, Mutex,
+};
+
+use tokio::sync::{futures::Notified, Notify};
+
+/// The default fill ratio, in percent, above which a channel signals backpressure.
+pub(crate) const DEFAULT_BACKPRESSURE_THRESHOLD: u8 = 80;
+
+/// # Backpressure
+/// A signal shared by the [`InChannels`](super::in_channel::InChannels) of a node and the
+/// [`OutChannels`](super::out_channel::OutChannels) of its predecessors, telling the senders to
+/// slow down.
+///
+/// Each edge towards the node has its own flag, see [`EdgeBackpressure`], which its sender
+/// refreshes before each send with the fill level of the channel. The flag of an edge is set
+/// once the buffer of its channel holds more than `threshold` percent of its capacity, and
+/// cleared as soon as it is drained below. The signal is set while the flag of any edge is.
+/// Only bounded `mpsc` channels have a buffer to measure, the other channels never set it.
+#[derive(Debug)]
+pub(crate) struct Backpressure {
+    signal: Arc<AtomicBool>,
+    threshold: AtomicU8,
+    /// The number of edges whose flag is set.
+    pressed_edges: Mutex<usize>,
+    /// Wakes the senders waiting for the receiver to drain its channels.
+    drained: Notify,
+}
+
+impl Default for Backpressure {
+    fn default() -> Self {
+        Self {
+            signal: Arc::default(),
+            threshold: AtomicU8::new(DEFAULT_BACKPRESSURE_THRESHOLD),
+            pressed_edges: Mutex::new(0),
+            drained: Notify::new(),
+        }
+    }
+}
+
+impl Backpressure {
+    /// Returns the shared flag, which is `true` while the receiver is under backpressure.
+    pub(crate) fn signal(&self) -> Arc<AtomicBool> {
+        self.signal.clone()
+    }
+
+    /// Set the fill ratio above which the flag of an edge is set, in percent.
+    pub(crate) fn set_threshold(&self, percent: u8) {
+        self.threshold.store(percent, Ordering::Relaxed);
+    }
+
+    /// Wake the senders waiting for the receiver, after it took a packet from one of its
+    /// channels or closed one.
+    pub(crate) fn notify_drained(&self) {
+        self.drained.notify_waiters();
+    }
+
+    /// Record that the flag of an edge is set, or cleared, and refresh the signal.
+    fn set_pressed(&self, pressed: bool) {
+        let mut pressed_edges = self.pressed_edges.lock().unwrap();
+        if pressed {
+            *pressed_edges += 1;
+        } else {
+            *pressed_edges -= 1;
+        }
+        self.signal.store(*pressed_edges > 0, Ordering::Relaxed);
+    }
+}
+
+/// The backpressure of a single edge towards a receiver, see [`Backpressure`].
+#[derive(Debug)]
+pub(crate) struct EdgeBackpressure {
+    receiver: Arc<Backpressure>,
+    pressed: AtomicBool,
+}
+
+impl EdgeBackpressure {
+    pub(crate) fn new(receiver: Arc<Backpressure>) -> Self {
+        Self {
+            receiver,
+            pressed: AtomicBool::new(false),
+        }
+    }
+
+    /// Refresh the flag of the edge with a channel holding `len` packets out of `capacity`,
+    /// and returns it.
+    pub(crate) fn update(&self, len: usize, capacity: usize) -> bool {
+        let threshold = self.receiver.threshold.load(Ordering::Relaxed) as usize;
+        let pressed = len * 100 > capacity * threshold;
+        if self.pressed.swap(pressed, Ordering::Relaxed) != pressed {
+            self.receiver.set_pressed(pressed);
+        }
+        pressed
+    }
+
+    /// Returns a future completing once the receiver drains one of its channels. It must be
+    /// enabled before checking the fill level of the channel, so that no drain is missed.
+    pub(crate) fn drained(&self) -> Notified<'_> {
+        self.receiver.drained.notified()
+    }
+}
+
+impl Drop for EdgeBackpressure {
+    fn drop(&mut self) {
+        // A removed edge no longer holds the receiver under backpressure.
+        if *self.pressed.get_mut() {
+            self.receiver.set_pressed(false);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use tokio::sync::{futures::Notified, Notify};
+
+/// # Cutoff
+/// Closes a `broadcast` channel shared by several receivers for one of them only, see
+/// [`OutChannels::close_to`](super::out_channel::OutChannels::close_to).
+///
+/// A packet is sent once through the channel for all its receivers, so the channel itself
+/// cannot be closed for one of them. Instead, the sender counts the packets sent through the
+/// channel, and closing it for a receiver records that count: the receiver sees the channel
+/// closed once it received, or lagged behind, that many packets. It then leaves the channel,
+/// dropping the packets sent to the other receivers since.
+#[derive(Debug)]
+pub(crate) struct Cutoff {
+    /// The number of packets sent through the channel when it was closed for the receiver,
+    /// `u64::MAX` while it is open.
+    at: AtomicU64,
+    /// The number of packets the receiver received or lagged behind so far.
+    seen: AtomicU64,
+    /// The number of packets dropped by the receiver leaving the channel, not yet released
+    /// from the in-flight work.
+    dropped: AtomicUsize,
+    /// Wakes the receiver waiting for a packet when the channel is closed.
+    closed: Notify,
+}
+
+impl Default for Cutoff {
+    fn default() -> Self {
+        Self {
+            at: AtomicU64::new(u64::MAX),
+            seen: AtomicU64::default(),
+            dropped: AtomicUsize::default(),
+            closed: Notify::new(),
+        }
+    }
+}
+
+impl Cutoff {
+    /// Close the channel for the receiver after the first `sent` packets, unless it is closed
+    /// already.
+    pub(crate) fn close(&self, sent: u64) {
+        let _ = self
+            .at
+            .compare_exchange(u64::MAX, sent, Ordering::Relaxed, Ordering::Relaxed);
+        self.closed.notify_waiters();
+    }
+
+    /// Returns true if the channel is closed for the receiver, even if packets sent before
+    /// are left to receive.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.at.load(Ordering::Relaxed) != u64::MAX
+    }
+
+    /// Returns true once the receiver got all the packets sent before the channel was closed
+    /// for it.
+    pub(crate) fn is_reached(&self) -> bool {
+        self.seen.load(Ordering::Relaxed) >= self.at.load(Ordering::Relaxed)
+    }
+
+    /// Record that the receiver received, or lagged behind, `packets` packets.
+    pub(crate) fn advance(&self, packets: u64) {
+        self.seen.fetch_add(packets, Ordering::Relaxed);
+    }
+
+    /// Returns a future completing once the channel is closed for the receiver. It must be
+    /// enabled before checking [`Cutoff::is_reached`], so that no closing is missed.
+    pub(crate) fn closed(&self) -> Notified<'_> {
+        self.closed.notified()
+    }
+
+    /// Record the packets dropped by the receiver leaving the channel.
+    pub(crate) fn add_dropped(&self, dropped: usize) {
+        self.dropped.fetch_add(dropped, Ordering::Relaxed);
+    }
+
+    /// Take the packets dropped by the receiver leaving the channel, to release them.
+    pub(crate) fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// The sender side of a receiver of a shared `broadcast` channel, see [`Cutoff`].
+#[derive(Debug, Clone)]
+pub(crate) struct SharedReceiver {
+    /// The number of packets sent through the channel, shared by all its receivers.
+    pub(crate) sent: Arc<AtomicU64>,
+    pub(crate) cutoff: Arc<Cutoff>,
+}
@@ -1,57 +1,198 @@
-use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+use std::{
+    any::{self, Any},
+    collections::HashMap,
+    marker::PhantomData,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
 
-use futures::future::join_all;
-use futures::future::select_ok;
-use tokio::sync::{broadcast, mpsc, Mutex};
+use futures::{
+    future::{self, join_all, BoxFuture, Either},
+    stream::{FuturesUnordered, StreamExt},
+};
+use tokio::{
+    sync::{
+        broadcast, mpsc,
+        oneshot::{self, error::TryRecvError},
+        Mutex,
+    },
+    time,
+};
 
-use crate::node::node::NodeId;
+use crate::{
+    graph::event::GraphEvent,
+    node::node::NodeId,
+    utils::{in_flight::InFlight, watchdog::touch},
+};
+use log::warn;
 
-use super::information_packet::Content;
+use super::{
+    backpressure::Backpressure,
+    cutoff::Cutoff,
+    information_packet::{Content, MessageMeta},
+};
 
 /// # Input Channels
 /// A hash-table mapping `NodeId` to `InChannel`. In **Dagrs**, each `Node` stores input
 /// channels in this map, enabling `Node` to receive information packets from other `Node`s.
+///
+/// Every channel counts the packets dropped because the receiver lagged behind a broadcast
+/// channel, see [`InChannels::messages_dropped`], and with the `metrics` feature, the packets
+/// successfully received from it, see [`InChannels::messages_received`].
+///
+/// The senders are told to slow down once the buffer of a channel is nearly full, see
+/// [`InChannels::backpressure_signal`].
+///
+/// A closed channel is drained before it is reported closed: the packets sent before the
+/// senders closed are all received, in order, and only then does a receive return
+/// [`RecvErr::Closed`]. This holds for the `mpsc`, `broadcast` and `oneshot` channels alike,
+/// except that a receiver lagging behind a `broadcast` channel gets [`RecvErr::Lagged`] for the
+/// packets overwritten before it received them. A `broadcast` channel is closed once all of
+/// its senders are, or once its sender closed it for this receiver only, see
+/// [`OutChannels::close_to`](crate::OutChannels::close_to).
 #[derive(Default)]
-pub struct InChannels(pub(crate) HashMap<NodeId, Arc<Mutex<InChannel>>>);
+pub struct InChannels {
+    /// The channels from the predecessors, shared with the typed view of these channels.
+    pub(crate) receivers: Receivers,
+    /// The pending receives of [`InChannels::recv_any`].
+    pub(crate) selector: Selector,
+}
 
 impl InChannels {
     /// Perform a blocking receive on the incoming channel from `NodeId`.
     pub fn blocking_recv_from(&mut self, id: &NodeId) -> Result<Content, RecvErr> {
-        match self.get(id) {
-            Some(channel) => channel.blocking_lock().blocking_recv(),
-            None => Err(RecvErr::NoSuchChannel),
-        }
+        self.selector.clear();
+        self.receivers.blocking_recv(id)
     }
     /// Perform a asynchronous receive on the incoming channel from `NodeId`.
     pub async fn recv_from(&mut self, id: &NodeId) -> Result<Content, RecvErr> {
-        match self.get(id) {
-            Some(channel) => channel.lock().await.recv().await,
-            None => Err(RecvErr::NoSuchChannel),
+        self.selector.clear();
+        self.receivers.recv(id).await
+    }
+
+    /// Receive from the channel of `NodeId` without waiting, e.g. to poll several channels.
+    ///
+    /// Returns [`RecvErr::Empty`] if no packet is buffered in the channel, or if the channel
+    /// is being received from by another pending receive.
+    pub fn try_recv_from(&mut self, id: &NodeId) -> Result<Content, RecvErr> {
+        self.selector.clear();
+        self.receivers.try_recv(id)
+    }
+
+    /// Receive from the channel of `NodeId` like [`InChannels::recv_from`], and detach the
+    /// metadata of the packet, if it was sent with
+    /// [`OutChannels::send_with_metadata`](crate::OutChannels::send_with_metadata).
+    pub async fn recv_with_metadata(
+        &mut self,
+        id: &NodeId,
+    ) -> Result<(Content, Option<MessageMeta>), RecvErr> {
+        let mut content = self.recv_from(id).await?;
+        let meta = content.take_meta();
+        Ok((content, meta))
+    }
+
+    /// Returns the number of packets successfully received from the channel of `NodeId`
+    /// so far, or 0 if there is no such channel.
+    #[cfg(feature = "metrics")]
+    pub fn messages_received(&self, id: &NodeId) -> u64 {
+        self.receivers.messages_received(id)
+    }
+
+    /// Returns the number of packets dropped because this receiver lagged behind the broadcast
+    /// channel of `NodeId` so far, or 0 if there is no such channel.
+    pub fn messages_dropped(&self, id: &NodeId) -> u64 {
+        self.receivers.messages_dropped(id)
+    }
+
+    /// Returns the kind of the channel from `NodeId`, or `None` if there is no such channel.
+    ///
+    /// Only a `broadcast` channel can report [`RecvErr::Lagged`].
+    pub fn channel_type(&self, id: &NodeId) -> Option<ChannelType> {
+        self.receivers
+            .stats
+            .get(id)
+            .and_then(|stats| stats.channel_type)
+    }
+
+    /// Returns the flag shared with the senders of these channels, which is `true` while the
+    /// buffer of any channel is more than the threshold full, 80% by default.
+    ///
+    /// Each sender refreshes the state of its own channel before each send, and waits for this
+    /// receiver to drain the channel below the threshold, see
+    /// [`OutChannels`](crate::OutChannels). Only `mpsc` channels have a buffer to fill.
+    pub fn backpressure_signal(&self) -> Arc<AtomicBool> {
+        self.receivers.backpressure.signal()
+    }
+
+    /// Set the fill ratio of a channel buffer, in percent, above which the senders are under
+    /// backpressure. Defaults to 80.
+    ///
+    /// # Panics
+    /// Panics if `percent` is greater than 100.
+    pub fn set_backpressure_threshold(&mut self, percent: u8) {
+        assert!(
+            percent <= 100,
+            "backpressure threshold must be at most 100%"
+        );
+        self.receivers.backpressure.set_threshold(percent);
+    }
+
+    /// Returns the backpressure to share with the senders of these channels.
+    pub(crate) fn backpressure(&self) -> Arc<Backpressure> {
+        self.receivers.backpressure.clone()
+    }
+
+    /// Share the [`Cutoff`] of the `broadcast` channel from `NodeId` with its sender, which
+    /// shares the channel with other receivers.
+    pub(crate) fn set_cutoff(&mut self, id: &NodeId, cutoff: Arc<Cutoff>) {
+        if let Some(stats) = self.receivers.stats.get(id) {
+            let _ = stats.cutoff.set(cutoff);
         }
     }
 
     /// Receives data from any available channel and returns both the sender's ID and the content.
     /// This method will wait until any channel has data available.
+    ///
+    /// The pending receives are kept across calls, so that receiving from many channels in a
+    /// loop only polls the channels that are ready.
+    ///
+    /// A channel closed while being received from, e.g. through another handle sharing it like
+    /// the [`TypedInChannels`] of a typed action, is skipped and excluded from the next receives
+    /// rather than reported. [`RecvErr::Closed`] is returned only once every channel is closed.
     pub async fn recv_any(&mut self) -> Result<(NodeId, Content), RecvErr> {
-        let mut futures = Vec::new();
-        let ids: Vec<NodeId> = self.keys();
-
-        for id in ids {
-            let channel = self.get(&id).ok_or(RecvErr::NoSuchChannel)?;
-            let fut = Box::pin(async move {
-                let content = channel.lock().await.recv().await?;
-                Ok::<_, RecvErr>((id, content))
-            });
-            futures.push(fut);
-        }
+        self.selector.recv_any(&self.receivers).await
+    }
 
-        if futures.is_empty() {
+    /// Waits until a channel has a packet to receive, or is closed, and returns its sender's
+    /// ID without receiving anything, so that the caller can decide what to receive, e.g.
+    /// with [`InChannels::recv_from`], unlike [`InChannels::recv_any`].
+    ///
+    /// The channels are polled, checking again after a wait doubling from 1ms up to 16ms. If
+    /// several channels are ready, the one with the lowest ID is returned. Returns
+    /// [`RecvErr::NoSuchChannel`] if there is no channel.
+    pub async fn select_ready(&mut self) -> Result<NodeId, RecvErr> {
+        self.selector.clear();
+        if self.receivers.channels.is_empty() {
             return Err(RecvErr::NoSuchChannel);
         }
-
-        match select_ok(futures).await {
-            Ok((result, _)) => Ok(result),
-            Err(_) => Err(RecvErr::Closed),
+        let mut ids = self.keys();
+        ids.sort();
+        let mut wait = Duration::from_millis(1);
+        loop {
+            let ready = ids.iter().find(|id| {
+                self.receivers.channels[id]
+                    .try_lock()
+                    .is_ok_and(|channel| channel.is_ready(self.receivers.stats.get(id)))
+            });
+            if let Some(id) = ready {
+                return Ok(*id);
+            }
+            time::sleep(wait).await;
+            wait = (wait * 2).min(Duration::from_millis(16));
         }
     }
 
@@ -69,53 +210,323 @@ impl InChannels {
 
     /// Calls `recv` for all the [`InChannel`]s, and applies transformation `f` to
     /// the return values of the call asynchronously.
-    pub async fn map<F, T>(&mut self, mut f: F) -> Vec<T>
+    pub async fn map<F, T>(&mut self, f: F) -> Vec<T>
     where
         F: FnMut(Result<Content, RecvErr>) -> T,
     {
-        let futures = self
-            .0
-            .iter_mut()
-            .map(|(_, c)| async { c.lock().await.recv().await });
-        join_all(futures).await.into_iter().map(|x| f(x)).collect()
+        self.selector.clear();
+        self.receivers
+            .recv_all()
+            .await
+            .into_iter()
+            .map(|(_, result)| result)
+            .map(f)
+            .collect()
+    }
+
+    /// Calls `recv` for all the [`InChannel`]s asynchronously, and applies transformation `f`
+    /// to the received packets only.
+    ///
+    /// Channels failing to receive, e.g. closed or lagged, are silently skipped.
+    pub async fn map_ok<F, T>(&mut self, mut f: F) -> Vec<T>
+    where
+        F: FnMut(Content) -> T,
+    {
+        self.map(|result| result.ok().map(&mut f))
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Receives a packet from every [`InChannel`] asynchronously.
+    ///
+    /// Channels failing to receive, e.g. closed or lagged, are silently skipped.
+    pub async fn recv_values(&mut self) -> Vec<Content> {
+        self.map_ok(|content| content).await
+    }
+
+    /// Receives a packet from every [`InChannel`] asynchronously, and reduces them with `f`,
+    /// starting from `init`, e.g. to sum or concatenate the values of all the predecessors.
+    ///
+    /// The packets are folded in no particular order, along with the id of their sender.
+    /// Channels failing to receive, e.g. closed or lagged, are silently skipped.
+    pub async fn recv_fold<A, F>(&mut self, init: A, f: F) -> A
+    where
+        F: FnMut(A, (NodeId, Content)) -> A,
+    {
+        self.selector.clear();
+        self.receivers
+            .recv_all()
+            .await
+            .into_iter()
+            .filter_map(|(id, result)| result.ok().map(|content| (id, content)))
+            .fold(init, f)
+    }
+
+    /// Receives exactly one packet from every [`InChannel`] asynchronously, keyed by the id of
+    /// their sender, for barrier-style join nodes which must not wait forever for a failed or
+    /// slow predecessor.
+    ///
+    /// Returns [`RecvErr::TimedOut`] if some channel has not delivered its packet within
+    /// `timeout`. The whole receive is then cancelled: the packets received from the other
+    /// channels are dropped. Fails with the error of a channel failing to receive, e.g.
+    /// [`RecvErr::Closed`], or [`RecvErr::NoSuchChannel`] if there is no channel.
+    pub async fn recv_all_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<HashMap<NodeId, Content>, RecvErr> {
+        if self.receivers.channels.is_empty() {
+            return Err(RecvErr::NoSuchChannel);
+        }
+        self.selector.clear();
+        time::timeout(timeout, self.receivers.recv_all())
+            .await
+            .map_err(|_| RecvErr::TimedOut)?
+            .into_iter()
+            .map(|(id, result)| result.map(|content| (id, content)))
+            .collect()
     }
 
     /// Close the channel by the given `NodeId` asynchronously, and remove the channel in this map.
     pub async fn close_async(&mut self, id: &NodeId) {
-        if let Some(c) = self.get(id) {
-            c.lock().await.close();
-            self.0.remove(id);
-        }
+        self.selector.clear();
+        self.receivers.close_async(id).await;
     }
 
     /// Close the channel by the given `NodeId`, and remove the channel in this map.
     pub fn close(&mut self, id: &NodeId) {
+        self.selector.clear();
+        self.receivers.close(id);
+    }
+
+    pub(crate) fn insert(&mut self, node_id: NodeId, channel: Arc<Mutex<InChannel>>) {
+        self.selector.clear();
+        self.receivers.insert(node_id, channel);
+    }
+
+    pub(crate) async fn close_all(&mut self) {
+        self.selector.clear();
+        for c in self.receivers.channels.values() {
+            let dropped = c.lock().await.close();
+            self.receivers.release(dropped);
+        }
+    }
+
+    /// Returns true if every channel has a packet to receive, or is closed.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.receivers.channels.iter().all(|(id, c)| {
+            c.try_lock()
+                .is_ok_and(|c| c.is_ready(self.receivers.stats.get(id)))
+        })
+    }
+
+    /// Returns true if there is no channel.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.receivers.channels.is_empty()
+    }
+
+    /// Returns true if there is a channel from `NodeId`.
+    pub(crate) fn contains(&self, id: &NodeId) -> bool {
+        self.receivers.channels.contains_key(id)
+    }
+
+    /// Track the packets received through these channels in `in_flight`.
+    pub(crate) fn set_in_flight(&mut self, in_flight: Arc<InFlight>) {
+        self.receivers.in_flight = Some(in_flight);
+    }
+
+    /// Log a warning for each lag of the channels once more than `threshold` packets from the
+    /// channel are dropped in total, naming `receiver` as the slow consumer.
+    pub(crate) fn set_lag_warning(&mut self, receiver: NodeId, threshold: u64) {
+        for (sender, stats) in &self.receivers.stats {
+            *stats.lag_warning.lock().unwrap() = Some(LagWarning {
+                sender: *sender,
+                receiver,
+                threshold,
+            });
+        }
+    }
+
+    /// Fast-forward the broadcast channel from `sender` to its newest packet once `receiver`
+    /// lags behind it `after_lags` times without catching up, reporting it to `events`. The
+    /// count of lags starts over.
+    pub(crate) fn set_fast_forward(
+        &mut self,
+        sender: NodeId,
+        receiver: NodeId,
+        after_lags: u32,
+        events: Arc<broadcast::Sender<GraphEvent>>,
+    ) {
+        if let Some(stats) = self.receivers.stats.get(&sender) {
+            *stats.fast_forward.lock().unwrap() = Some(FastForward {
+                sender,
+                receiver,
+                after: after_lags,
+                lags: 0,
+                events,
+            });
+        }
+    }
+
+    /// Record every receive from these channels as an activity in `activity`. A channel keeps
+    /// the first `activity` it is given.
+    pub(crate) fn set_activity(&mut self, activity: Arc<AtomicU64>) {
+        for stats in self.receivers.stats.values() {
+            let _ = stats.activity.set(activity.clone());
+        }
+    }
+
+    fn keys(&self) -> Vec<NodeId> {
+        self.receivers.keys()
+    }
+
+    /// Returns a list of all the sender node IDs.
+    pub fn get_sender_ids(&self) -> Vec<NodeId> {
+        self.keys()
+    }
+}
+
+/// The channels from the predecessors of a node, keyed by the sender's id, along with their
+/// counters.
+///
+/// It is shared by [`InChannels`] and [`TypedInChannels`], so that a packet is counted and
+/// released from the in-flight work the same way whichever is used to receive it.
+#[derive(Default, Clone)]
+pub(crate) struct Receivers {
+    pub(crate) channels: HashMap<NodeId, Arc<Mutex<InChannel>>>,
+    /// The counters of each channel.
+    pub(crate) stats: HashMap<NodeId, Arc<ChannelStats>>,
+    /// See [`InChannels::set_in_flight`].
+    pub(crate) in_flight: Option<Arc<InFlight>>,
+    /// See [`InChannels::backpressure_signal`].
+    pub(crate) backpressure: Arc<Backpressure>,
+}
+
+impl Receivers {
+    /// Perform a blocking receive from `NodeId`.
+    fn blocking_recv(&self, id: &NodeId) -> Result<Content, RecvErr> {
+        match self.get(id) {
+            Some(channel) => self.count(
+                id,
+                channel.blocking_lock().blocking_recv(self.stats.get(id)),
+            ),
+            None => Err(RecvErr::NoSuchChannel),
+        }
+    }
+
+    /// Perform a asynchronous receive from `NodeId`.
+    async fn recv(&self, id: &NodeId) -> Result<Content, RecvErr> {
+        match self.get(id) {
+            Some(channel) => self.count(id, channel.lock().await.recv(self.stats.get(id)).await),
+            None => Err(RecvErr::NoSuchChannel),
+        }
+    }
+
+    /// Receive from `NodeId` without waiting, see [`InChannels::try_recv_from`].
+    fn try_recv(&self, id: &NodeId) -> Result<Content, RecvErr> {
+        match self.get(id) {
+            Some(channel) => self.count(id, try_recv(&channel, self.stats.get(id))),
+            None => Err(RecvErr::NoSuchChannel),
+        }
+    }
+
+    /// Receive a packet from every channel concurrently, along with the id of its sender.
+    async fn recv_all(&self) -> Vec<(NodeId, Result<Content, RecvErr>)> {
+        let futures = self.channels.iter().map(|(id, c)| async {
+            let result = c.lock().await.recv(self.stats.get(id)).await;
+            (*id, self.count(id, result))
+        });
+        join_all(futures).await
+    }
+
+    /// Close the channel from `NodeId` asynchronously, and remove it.
+    async fn close_async(&mut self, id: &NodeId) {
         if let Some(c) = self.get(id) {
-            c.blocking_lock().close();
-            self.0.remove(id);
+            let dropped = c.lock().await.close();
+            self.release(dropped);
+            self.channels.remove(id);
+            self.stats.remove(id);
         }
     }
 
-    pub(crate) fn insert(&mut self, node_id: NodeId, channel: Arc<Mutex<InChannel>>) {
-        self.0.insert(node_id, channel);
+    /// Close the channel from `NodeId`, and remove it.
+    fn close(&mut self, id: &NodeId) {
+        if let Some(c) = self.get(id) {
+            let dropped = c.blocking_lock().close();
+            self.release(dropped);
+            self.channels.remove(id);
+            self.stats.remove(id);
+        }
     }
 
-    pub(crate) fn close_all(&mut self) {
-        self.0.values_mut().for_each(|c| c.blocking_lock().close());
+    fn insert(&mut self, node_id: NodeId, channel: Arc<Mutex<InChannel>>) {
+        // The channel is not shared yet, so it is not locked.
+        let channel_type = channel
+            .try_lock()
+            .ok()
+            .map(|channel| channel.channel_type());
+        self.channels.insert(node_id, channel);
+        self.stats.insert(
+            node_id,
+            Arc::new(ChannelStats {
+                channel_type,
+                ..Default::default()
+            }),
+        );
     }
 
-    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<InChannel>>> {
-        match self.0.get(id) {
-            Some(c) => Some(c.clone()),
-            None => None,
+    #[cfg(feature = "metrics")]
+    fn messages_received(&self, id: &NodeId) -> u64 {
+        self.stats
+            .get(id)
+            .map_or(0, |stats| stats.received.load(Ordering::Relaxed))
+    }
+
+    fn messages_dropped(&self, id: &NodeId) -> u64 {
+        self.stats
+            .get(id)
+            .map_or(0, |stats| stats.dropped.load(Ordering::Relaxed))
+    }
+
+    /// Update the counters of the channel from `NodeId` with the `result` of a receive.
+    /// The senders waiting for the channel to drain are woken.
+    fn count(&self, id: &NodeId, result: Result<Content, RecvErr>) -> Result<Content, RecvErr> {
+        let result = count(self.stats.get(id), self.in_flight.as_ref(), result);
+        self.backpressure.notify_drained();
+        result
+    }
+
+    /// Release the packets dropped by closing a channel from the in-flight work, and wake the
+    /// senders waiting for the channel to drain.
+    fn release(&self, dropped: usize) {
+        if let Some(in_flight) = &self.in_flight {
+            in_flight.sub(dropped);
         }
+        self.backpressure.notify_drained();
+    }
+
+    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<InChannel>>> {
+        self.channels.get(id).cloned()
     }
 
     fn keys(&self) -> Vec<NodeId> {
-        self.0.keys().map(|x| *x).collect()
+        self.channels.keys().copied().collect()
     }
 }
 
+/// The kind of channel of an edge, see [`InChannel`] and
+/// [`OutChannel`](crate::connection::out_channel::OutChannel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelType {
+    /// A `tokio::sync::mpsc` channel.
+    Mpsc,
+    /// A `tokio::sync::broadcast` channel.
+    Broadcast,
+    /// A `tokio::sync::oneshot` channel.
+    Once,
+}
+
 /// # Input Channel
 /// Wrapper of receivers of `tokio::sync::mpsc` and `tokio::sync::broadcast`. **Dagrs** will
 /// decide the inner type of channel when building the graph.
@@ -125,114 +536,265 @@ pub enum InChannel {
     Mpsc(mpsc::Receiver<Content>),
     /// Receiver of a `tokio::sync::broadcast` channel.
     Bcst(broadcast::Receiver<Content>),
+    /// Receiver of a `tokio::sync::oneshot` channel, which yields exactly one packet and
+    /// then [`RecvErr::Closed`].
+    Once(oneshot::Receiver<Content>),
 }
 
 impl InChannel {
-    /// Perform a blocking receive on this channel.
-    fn blocking_recv(&mut self) -> Result<Content, RecvErr> {
+    /// Returns the kind of this channel.
+    pub fn channel_type(&self) -> ChannelType {
         match self {
-            InChannel::Mpsc(receiver) => {
-                if let Some(content) = receiver.blocking_recv() {
-                    Ok(content)
-                } else {
-                    Err(RecvErr::Closed)
+            InChannel::Mpsc(_) => ChannelType::Mpsc,
+            InChannel::Bcst(_) => ChannelType::Broadcast,
+            InChannel::Once(_) => ChannelType::Once,
+        }
+    }
+
+    /// Perform a blocking receive on this channel, whose counters are `stats`.
+    fn blocking_recv(&mut self, stats: Option<&Arc<ChannelStats>>) -> Result<Content, RecvErr> {
+        loop {
+            if self.leave_if_cut_off(stats) {
+                return Err(RecvErr::Closed);
+            }
+            let content = match self {
+                InChannel::Mpsc(receiver) => {
+                    if let Some(content) = receiver.blocking_recv() {
+                        Ok(content)
+                    } else {
+                        Err(RecvErr::Closed)
+                    }
+                }
+                InChannel::Bcst(receiver) => {
+                    match futures::executor::block_on(recv_bcst(receiver, stats)) {
+                        Some(result) => result,
+                        None => continue,
+                    }
                 }
+                // A oneshot receiver must not be polled again once completed, so check it first.
+                InChannel::Once(receiver) => match receiver.try_recv() {
+                    Ok(v) => Ok(v),
+                    Err(TryRecvError::Closed) => Err(RecvErr::Closed),
+                    Err(TryRecvError::Empty) => {
+                        futures::executor::block_on(receiver).map_err(|_| RecvErr::Closed)
+                    }
+                },
+            }?;
+            return Self::accept(content);
+        }
+    }
+
+    /// Perform a asynchronous receive on this channel, whose counters are `stats`.
+    async fn recv(&mut self, stats: Option<&Arc<ChannelStats>>) -> Result<Content, RecvErr> {
+        loop {
+            if self.leave_if_cut_off(stats) {
+                return Err(RecvErr::Closed);
             }
-            InChannel::Bcst(receiver) => match receiver.blocking_recv() {
-                Ok(v) => Ok(v),
-                Err(e) => match e {
-                    broadcast::error::RecvError::Closed => Err(RecvErr::Closed),
-                    broadcast::error::RecvError::Lagged(x) => Err(RecvErr::Lagged(x)),
+            let content = match self {
+                InChannel::Mpsc(receiver) => {
+                    if let Some(content) = receiver.recv().await {
+                        Ok(content)
+                    } else {
+                        Err(RecvErr::Closed)
+                    }
+                }
+                InChannel::Bcst(receiver) => match recv_bcst(receiver, stats).await {
+                    Some(result) => result,
+                    None => continue,
                 },
-            },
+                // A oneshot receiver must not be polled again once completed, so check it first.
+                InChannel::Once(receiver) => match receiver.try_recv() {
+                    Ok(v) => Ok(v),
+                    Err(TryRecvError::Closed) => Err(RecvErr::Closed),
+                    Err(TryRecvError::Empty) => receiver.await.map_err(|_| RecvErr::Closed),
+                },
+            }?;
+            return Self::accept(content);
         }
     }
 
-    /// Perform a asynchronous receive on this channel.
-    async fn recv(&mut self) -> Result<Content, RecvErr> {
+    /// Receive from this channel, whose counters are `stats`, without waiting.
+    fn try_recv(&mut self, stats: Option<&Arc<ChannelStats>>) -> Result<Content, RecvErr> {
+        if self.leave_if_cut_off(stats) {
+            return Err(RecvErr::Closed);
+        }
+        let content = match self {
+            InChannel::Mpsc(receiver) => receiver.try_recv().map_err(|e| match e {
+                mpsc::error::TryRecvError::Empty => RecvErr::Empty,
+                mpsc::error::TryRecvError::Disconnected => RecvErr::Closed,
+            }),
+            InChannel::Bcst(receiver) => {
+                let result = receiver.try_recv().map_err(|e| match e {
+                    broadcast::error::TryRecvError::Empty => RecvErr::Empty,
+                    broadcast::error::TryRecvError::Closed => RecvErr::Closed,
+                    broadcast::error::TryRecvError::Lagged(x) => RecvErr::Lagged(x),
+                });
+                advance(stats, fast_forward(receiver, stats, result))
+            }
+            InChannel::Once(receiver) => receiver.try_recv().map_err(|e| match e {
+                TryRecvError::Empty => RecvErr::Empty,
+                TryRecvError::Closed => RecvErr::Closed,
+            }),
+        }?;
+        Self::accept(content)
+    }
+
+    /// Reject the packet if its time-to-live has elapsed.
+    fn accept(content: Content) -> Result<Content, RecvErr> {
+        if content.is_expired() {
+            return Err(RecvErr::Expired);
+        }
+        Ok(content)
+    }
+    /// Returns true if a receive on this channel, whose counters are `stats`, will not wait,
+    /// i.e. there's a packet in it, or it is closed.
+    fn is_ready(&self, stats: Option<&Arc<ChannelStats>>) -> bool {
         match self {
-            InChannel::Mpsc(receiver) => {
-                if let Some(content) = receiver.recv().await {
-                    Ok(content)
-                } else {
-                    Err(RecvErr::Closed)
-                }
+            InChannel::Mpsc(receiver) => !receiver.is_empty() || receiver.is_closed(),
+            InChannel::Bcst(receiver) => {
+                !receiver.is_empty()
+                    || receiver.is_closed()
+                    || cutoff(stats).is_some_and(|cutoff| cutoff.is_reached())
             }
-            InChannel::Bcst(receiver) => match receiver.recv().await {
-                Ok(v) => Ok(v),
-                Err(e) => match e {
-                    broadcast::error::RecvError::Closed => Err(RecvErr::Closed),
-                    broadcast::error::RecvError::Lagged(x) => Err(RecvErr::Lagged(x)),
-                },
-            },
+            // A oneshot receiver cannot tell whether its sender is dropped without receiving.
+            InChannel::Once(receiver) => !receiver.is_empty() || receiver.is_terminated(),
         }
     }
-    /// Close the channel and drop the messages inside.
-    fn close(&mut self) {
+
+    /// Leave a `broadcast` channel, whose counters are `stats`, closed for this receiver once
+    /// it received the packets sent before, see [`Cutoff`]. Returns true if it left it.
+    fn leave_if_cut_off(&mut self, stats: Option<&Arc<ChannelStats>>) -> bool {
+        match cutoff(stats) {
+            Some(cutoff) if matches!(self, InChannel::Bcst(_)) && cutoff.is_reached() => {
+                cutoff.add_dropped(self.close());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Close the channel and drop the messages inside. Returns the number of dropped messages.
+    fn close(&mut self) -> usize {
+        let mut dropped = 0;
         match self {
-            InChannel::Mpsc(receiver) => receiver.close(),
-            // Broadcast channel will be closed after `self` is dropped.
-            InChannel::Bcst(_) => (),
+            InChannel::Mpsc(receiver) => {
+                receiver.close();
+                while receiver.try_recv().is_ok() {
+                    dropped += 1;
+                }
+            }
+            // A broadcast channel cannot be closed by a receiver: drop the receiver, so that it
+            // is no longer counted by the senders, and leave a closed channel instead.
+            InChannel::Bcst(receiver) => {
+                loop {
+                    match receiver.try_recv() {
+                        Ok(_) => dropped += 1,
+                        Err(broadcast::error::TryRecvError::Lagged(x)) => dropped += x as usize,
+                        Err(_) => break,
+                    }
+                }
+                *self = InChannel::Once(oneshot::channel().1);
+            }
+            InChannel::Once(receiver) => {
+                receiver.close();
+                if receiver.try_recv().is_ok() {
+                    dropped += 1;
+                }
+            }
         }
+        dropped
     }
 }
 
 /// # Typed Input Channels
 /// A hash-table mapping `NodeId` to `InChannel`. This provides type-safe channel communication
 /// between nodes.
+///
+/// A channel can carry another type than `T` if it is registered with
+/// [`TypedInChannels::register_type`], and received with [`TypedInChannels::recv_typed`].
 #[derive(Default)]
-pub struct TypedInChannels<T: Send + Sync + 'static>(
-    pub(crate) HashMap<NodeId, Arc<Mutex<InChannel>>>,
+pub struct TypedInChannels<T: Send + Sync + 'static> {
+    /// The channels of the [`InChannels`] this is made from.
+    pub(crate) receivers: Receivers,
+    /// The pending receives of [`TypedInChannels::recv_any`].
+    pub(crate) selector: Selector,
+    /// The types registered with [`TypedInChannels::register_type`].
+    pub(crate) downcasts: HashMap<NodeId, Downcast>,
     // maker for type T
-    pub(crate) PhantomData<T>,
-);
+    pub(crate) _marker: PhantomData<T>,
+}
 
 impl<T: Send + Sync + 'static> TypedInChannels<T> {
+    /// Typed view of the channels `receivers`.
+    pub(crate) fn new(receivers: Receivers) -> Self {
+        Self {
+            receivers,
+            selector: Selector::default(),
+            downcasts: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+
     /// Perform a blocking receive on the incoming channel from `NodeId`.
     pub fn blocking_recv_from(&mut self, id: &NodeId) -> Result<Option<Arc<T>>, RecvErr> {
-        match self.get(id) {
-            Some(channel) => {
-                let content: Content = channel.blocking_lock().blocking_recv()?;
-                Ok(content.into_inner())
-            }
-            None => Err(RecvErr::NoSuchChannel),
-        }
+        self.selector.clear();
+        Ok(self.receivers.blocking_recv(id)?.into_inner())
     }
 
     /// Perform a asynchronous receive on the incoming channel from `NodeId`.
     pub async fn recv_from(&mut self, id: &NodeId) -> Result<Option<Arc<T>>, RecvErr> {
-        match self.get(id) {
-            Some(channel) => {
-                let content: Content = channel.lock().await.recv().await?;
-                Ok(content.into_inner())
-            }
-            None => Err(RecvErr::NoSuchChannel),
-        }
+        self.selector.clear();
+        Ok(self.receivers.recv(id).await?.into_inner())
+    }
+
+    /// Receive from the channel of `NodeId` without waiting, like
+    /// [`InChannels::try_recv_from`].
+    pub fn try_recv_from(&mut self, id: &NodeId) -> Result<Option<Arc<T>>, RecvErr> {
+        self.selector.clear();
+        Ok(self.receivers.try_recv(id)?.into_inner())
+    }
+
+    /// Registers `U` as the type of the packets from `NodeId`, received by
+    /// [`TypedInChannels::recv_typed`]. It replaces the type registered before, if any.
+    pub fn register_type<U: Send + Sync + 'static>(&mut self, id: NodeId) -> &mut Self {
+        self.downcasts.insert(id, Downcast::of::<U>());
+        self
+    }
+
+    /// Perform a asynchronous receive on the incoming channel from `NodeId`, and check the
+    /// packet is of the type registered for the channel, or `T` if none is registered.
+    ///
+    /// The value is returned as a `Box` of `Arc<U>`, where `U` is the registered type, so that
+    /// a join node can receive a different type from each channel. Returns
+    /// [`RecvErr::TypeMismatch`] if the packet is of another type.
+    ///
+    /// # Example
+    /// ```ignore
+    /// in_channels.register_type::<String>(name_id).register_type::<u32>(age_id);
+    /// let name = in_channels.recv_typed(&name_id).await?.downcast::<Arc<String>>();
+    /// ```
+    pub async fn recv_typed(&mut self, id: &NodeId) -> Result<Box<dyn Any + Send + Sync>, RecvErr> {
+        self.selector.clear();
+        let content = self.receivers.recv(id).await?;
+        let downcast = self
+            .downcasts
+            .get(id)
+            .copied()
+            .unwrap_or(Downcast::of::<T>());
+        (downcast.cast)(content).ok_or(RecvErr::TypeMismatch {
+            expected: downcast.type_name,
+        })
     }
 
     /// Receives typed data from any available channel and returns both the sender's ID and the typed content.
     /// This method will wait until any channel has data available.
+    ///
+    /// The pending receives are kept across calls, so that receiving from many channels in a
+    /// loop only polls the channels that are ready. A channel closed concurrently is skipped,
+    /// same as [`InChannels::recv_any`].
     pub async fn recv_any(&mut self) -> Result<(NodeId, Option<Arc<T>>), RecvErr> {
-        let mut futures = Vec::new();
-        let ids: Vec<NodeId> = self.keys();
-
-        for id in ids {
-            let channel = self.get(&id).ok_or(RecvErr::NoSuchChannel)?;
-            let fut = Box::pin(async move {
-                let content: Content = channel.lock().await.recv().await?;
-                Ok::<_, RecvErr>((id, content.into_inner()))
-            });
-            futures.push(fut);
-        }
-
-        if futures.is_empty() {
-            return Err(RecvErr::NoSuchChannel);
-        }
-
-        match select_ok(futures).await {
-            Ok((result, _)) => Ok(result),
-            Err(_) => Err(RecvErr::Closed),
-        }
+        let (id, content) = self.selector.recv_any(&self.receivers).await?;
+        Ok((id, content.into_inner()))
     }
 
     /// Calls `blocking_recv` for all the [`InChannel`]s, and applies transformation `f` to
@@ -249,53 +811,791 @@ impl<T: Send + Sync + 'static> TypedInChannels<T> {
 
     /// Calls `recv` for all the [`InChannel`]s, and applies transformation `f` to
     /// the return values of the call asynchronously.
-    pub async fn map<F, U>(&mut self, mut f: F) -> Vec<U>
+    pub async fn map<F, U>(&mut self, f: F) -> Vec<U>
     where
         F: FnMut(Result<Option<Arc<T>>, RecvErr>) -> U,
     {
-        let futures = self.0.iter_mut().map(|(_, c)| async {
-            let content: Content = c.lock().await.recv().await?;
-            Ok(content.into_inner())
-        });
-        join_all(futures).await.into_iter().map(|x| f(x)).collect()
+        self.selector.clear();
+        self.receivers
+            .recv_all()
+            .await
+            .into_iter()
+            .map(|(_, result)| result.map(|content| content.into_inner()))
+            .map(f)
+            .collect()
     }
 
     /// Close the channel by the given `NodeId` asynchronously, and remove the channel in this map.
     pub async fn close_async(&mut self, id: &NodeId) {
-        if let Some(c) = self.get(id) {
-            c.lock().await.close();
-            self.0.remove(id);
-        }
+        self.selector.clear();
+        self.receivers.close_async(id).await;
     }
 
     /// Close the channel by the given `NodeId`, and remove the channel in this map.
     pub fn close(&mut self, id: &NodeId) {
-        if let Some(c) = self.get(id) {
-            c.blocking_lock().close();
-            self.0.remove(id);
+        self.selector.clear();
+        self.receivers.close(id);
+    }
+
+    /// Returns the number of packets successfully received from the channel of `NodeId`
+    /// so far, or 0 if there is no such channel.
+    #[cfg(feature = "metrics")]
+    pub fn messages_received(&self, id: &NodeId) -> u64 {
+        self.receivers.messages_received(id)
+    }
+
+    /// Returns the number of packets dropped because this receiver lagged behind the broadcast
+    /// channel of `NodeId` so far, or 0 if there is no such channel.
+    pub fn messages_dropped(&self, id: &NodeId) -> u64 {
+        self.receivers.messages_dropped(id)
+    }
+
+    fn keys(&self) -> Vec<NodeId> {
+        self.receivers.keys()
+    }
+}
+
+/// Converts the value of a packet into a `Box` of `Arc` of the type registered by
+/// [`TypedInChannels::register_type`], or `None` if the value is of another type.
+#[derive(Clone, Copy)]
+pub(crate) struct Downcast {
+    type_name: &'static str,
+    cast: fn(Content) -> Option<Box<dyn Any + Send + Sync>>,
+}
+
+impl Downcast {
+    fn of<U: Send + Sync + 'static>() -> Self {
+        Self {
+            type_name: any::type_name::<U>(),
+            cast: |content| {
+                content
+                    .into_inner::<U>()
+                    .map(|value| Box::new(value) as Box<dyn Any + Send + Sync>)
+            },
         }
     }
+}
 
-    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<InChannel>>> {
-        match self.0.get(id) {
-            Some(c) => Some(c.clone()),
-            None => None,
+type RecvFuture = BoxFuture<'static, (NodeId, Result<Content, RecvErr>)>;
+
+/// Pending receives of `recv_any`, one for each open channel.
+///
+/// The receives are kept across calls, and a channel is received again only after its last
+/// receive completes, so each call costs O(ready channels) rather than O(all channels). A
+/// pending receive holds the lock of its channel, so the selector must be cleared before the
+/// channels are accessed in any other way. Receiving is cancel safe, so no packet is lost.
+#[derive(Default)]
+pub(crate) struct Selector(std::sync::Mutex<Option<FuturesUnordered<RecvFuture>>>);
+
+impl Selector {
+    async fn recv_any(&mut self, receivers: &Receivers) -> Result<(NodeId, Content), RecvErr> {
+        let channels = &receivers.channels;
+        if channels.is_empty() {
+            return Err(RecvErr::NoSuchChannel);
         }
+        let stats = &receivers.stats;
+        let pending = self.0.get_mut().unwrap().get_or_insert_with(|| {
+            channels
+                .iter()
+                .map(|(id, channel)| Self::recv(*id, channel.clone(), stats.get(id).cloned()))
+                .collect()
+        });
+        while let Some((id, result)) = pending.next().await {
+            // A closed channel is excluded from the next receives, and so is a channel no
+            // longer in the map, rather than failing the whole call.
+            match (&result, channels.get(&id)) {
+                (Err(RecvErr::Closed), _) | (_, None) => {}
+                (_, Some(channel)) => {
+                    pending.push(Self::recv(id, channel.clone(), stats.get(&id).cloned()))
+                }
+            }
+            if let Ok(content) = receivers.count(&id, result) {
+                return Ok((id, content));
+            }
+        }
+        Err(RecvErr::Closed)
     }
 
-    fn keys(&self) -> Vec<NodeId> {
-        self.0.keys().map(|x| *x).collect()
+    fn recv(
+        id: NodeId,
+        channel: Arc<Mutex<InChannel>>,
+        stats: Option<Arc<ChannelStats>>,
+    ) -> RecvFuture {
+        Box::pin(async move { (id, channel.lock().await.recv(stats.as_ref()).await) })
+    }
+
+    /// Cancel the pending receives, releasing the locks of the channels.
+    fn clear(&mut self) {
+        *self.0.get_mut().unwrap() = None;
+    }
+}
+
+/// Receive from the broadcast `receiver`, whose counters are `stats`. Returns `None` if the
+/// channel is closed for this receiver while it waits, see [`Cutoff`].
+async fn recv_bcst(
+    receiver: &mut broadcast::Receiver<Content>,
+    stats: Option<&Arc<ChannelStats>>,
+) -> Option<Result<Content, RecvErr>> {
+    let result = match cutoff(stats) {
+        Some(cutoff) => {
+            let mut closed = pin!(cutoff.closed());
+            closed.as_mut().enable();
+            if cutoff.is_reached() {
+                return None;
+            }
+            match future::select(pin!(receiver.recv()), closed).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => return None,
+            }
+        }
+        None => receiver.recv().await,
+    };
+    let result = result.map_err(|e| match e {
+        broadcast::error::RecvError::Closed => RecvErr::Closed,
+        broadcast::error::RecvError::Lagged(x) => RecvErr::Lagged(x),
+    });
+    Some(advance(stats, fast_forward(receiver, stats, result)))
+}
+
+/// Returns the [`Cutoff`] of the channel whose counters are `stats`, if its sender shares it
+/// with other receivers.
+fn cutoff(stats: Option<&Arc<ChannelStats>>) -> Option<&Arc<Cutoff>> {
+    stats.and_then(|stats| stats.cutoff.get())
+}
+
+/// Count the packets received or lagged behind by `result` towards the [`Cutoff`] of the
+/// channel whose counters are `stats`, if any.
+fn advance(
+    stats: Option<&Arc<ChannelStats>>,
+    result: Result<Content, RecvErr>,
+) -> Result<Content, RecvErr> {
+    if let Some(cutoff) = cutoff(stats) {
+        match &result {
+            Ok(_) => cutoff.advance(1),
+            Err(RecvErr::Lagged(x)) => cutoff.advance(*x),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Counters of the packets from a channel.
+#[derive(Default)]
+pub(crate) struct ChannelStats {
+    /// The kind of the channel, see [`InChannels::channel_type`].
+    channel_type: Option<ChannelType>,
+    #[cfg(feature = "metrics")]
+    received: AtomicU64,
+    dropped: AtomicU64,
+    lag_warning: std::sync::Mutex<Option<LagWarning>>,
+    /// The last activity of the receiving node, see [`InChannels::set_activity`].
+    activity: OnceLock<Arc<AtomicU64>>,
+    /// See [`InChannels::set_fast_forward`].
+    fast_forward: std::sync::Mutex<Option<FastForward>>,
+    /// See [`InChannels::set_cutoff`].
+    cutoff: OnceLock<Arc<Cutoff>>,
+}
+
+/// See [`InChannels::set_lag_warning`].
+#[derive(Clone, Copy)]
+struct LagWarning {
+    sender: NodeId,
+    receiver: NodeId,
+    threshold: u64,
+}
+
+/// See [`InChannels::set_fast_forward`].
+struct FastForward {
+    sender: NodeId,
+    receiver: NodeId,
+    /// The number of lags after which the receiver is fast-forwarded.
+    after: u32,
+    /// The number of lags since the receiver last caught up.
+    lags: u32,
+    events: Arc<broadcast::Sender<GraphEvent>>,
+}
+
+/// Fast-forward the broadcast `receiver` to the newest packet if `result` is its `after`th lag
+/// since it last caught up, i.e. received the last packet in the channel, see
+/// [`Graph::set_fast_forward`](crate::Graph::set_fast_forward). The packets dropped by the lag
+/// and skipped by the fast-forward are reported together as a single lag.
+fn fast_forward(
+    receiver: &mut broadcast::Receiver<Content>,
+    stats: Option<&Arc<ChannelStats>>,
+    result: Result<Content, RecvErr>,
+) -> Result<Content, RecvErr> {
+    let Some(stats) = stats else {
+        return result;
+    };
+    let mut policy = stats.fast_forward.lock().unwrap();
+    let Some(policy) = policy.as_mut() else {
+        return result;
+    };
+    match &result {
+        Ok(_) if receiver.is_empty() => policy.lags = 0,
+        Err(RecvErr::Lagged(x)) => {
+            policy.lags += 1;
+            if policy.lags >= policy.after {
+                policy.lags = 0;
+                let dropped = x + receiver.len() as u64;
+                *receiver = receiver.resubscribe();
+                warn!(
+                    "Slow consumer [id: {}] fast-forwarded on the broadcast channel of node {}, {} packets dropped",
+                    policy.receiver.0, policy.sender.0, dropped
+                );
+                let _ = policy.events.send(GraphEvent::FastForwarded {
+                    from: policy.sender,
+                    to: policy.receiver,
+                    dropped,
+                });
+                return Err(RecvErr::Lagged(dropped));
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+/// Receive from `channel` without waiting, see [`InChannels::try_recv_from`].
+fn try_recv(
+    channel: &Mutex<InChannel>,
+    stats: Option<&Arc<ChannelStats>>,
+) -> Result<Content, RecvErr> {
+    match channel.try_lock() {
+        Ok(mut channel) => channel.try_recv(stats),
+        Err(_) => Err(RecvErr::Empty),
+    }
+}
+
+/// Update the counters in `stats` with the result of a receive, and release the packet from
+/// `in_flight` if it is taken from the channel, even expired, as well as the packets a lagging
+/// receiver missed.
+fn count(
+    stats: Option<&Arc<ChannelStats>>,
+    in_flight: Option<&Arc<InFlight>>,
+    result: Result<Content, RecvErr>,
+) -> Result<Content, RecvErr> {
+    if let Some(activity) = stats.and_then(|stats| stats.activity.get()) {
+        touch(activity);
+    }
+    if let Ok(content) = &result {
+        content.acknowledge();
+    }
+    match (&result, stats) {
+        #[cfg(feature = "metrics")]
+        (Ok(_), Some(stats)) => {
+            stats.received.fetch_add(1, Ordering::Relaxed);
+        }
+        (Err(RecvErr::Lagged(x)), Some(stats)) => {
+            let dropped = stats.dropped.fetch_add(*x, Ordering::Relaxed) + x;
+            if let Some(warning) = *stats.lag_warning.lock().unwrap() {
+                if dropped > warning.threshold {
+                    warn!(
+                        "Slow consumer [id: {}] lagged behind node {}, {} packets dropped in total",
+                        warning.receiver.0, warning.sender.0, dropped
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+    match (&result, in_flight) {
+        (Ok(_) | Err(RecvErr::Expired), Some(in_flight)) => in_flight.sub(1),
+        // The packets overwritten before being received are never received.
+        (Err(RecvErr::Lagged(x)), Some(in_flight)) => in_flight.sub(*x as usize),
+        // The packets dropped by leaving a channel closed for this receiver only.
+        (Err(RecvErr::Closed), Some(in_flight)) => {
+            if let Some(cutoff) = cutoff(stats) {
+                in_flight.sub(cutoff.take_dropped());
+            }
+        }
+        _ => {}
     }
+    result
 }
 
 /// # Input Channel Error Types
 /// - NoSuchChannel: try to get a channel with an invalid `NodeId`.
-/// - Closed: the channel to receive messages from is closed and empty already.
+/// - Closed: the channel to receive messages from is closed, and all the packets sent before
+///   closing were received already.
 /// - Lagged(x): the channel encounters a cache overflow and `x` information
-/// pakages are dropped on this receiver's side.
+///   pakages are dropped on this receiver's side.
+/// - Expired: the received packet was created by [`Content::with_ttl`], and its time-to-live
+///   elapsed before it was received. The packet is dropped.
+/// - TypeMismatch: the received packet is not of the type registered for the channel, see
+///   [`TypedInChannels::recv_typed`]. The packet is dropped.
+/// - TimedOut: not every channel delivered a packet before the deadline, see
+///   [`InChannels::recv_all_timeout`].
+/// - Empty: no packet is buffered in the channel yet, see [`InChannels::try_recv_from`].
 #[derive(Debug)]
 pub enum RecvErr {
     NoSuchChannel,
     Closed,
     Lagged(u64),
+    Expired,
+    TypeMismatch { expected: &'static str },
+    TimedOut,
+    Empty,
+}
+
+impl std::fmt::Display for RecvErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RecvErr::NoSuchChannel => write!(f, "no such input channel"),
+            RecvErr::Closed => write!(f, "input channel is closed"),
+            RecvErr::Lagged(x) => write!(f, "receiver lagged behind, {} packets dropped", x),
+            RecvErr::Expired => write!(f, "packet expired before being received"),
+            RecvErr::TypeMismatch { expected } => {
+                write!(f, "received a packet not of type {}", expected)
+            }
+            RecvErr::TimedOut => write!(f, "timed out waiting for the input channels"),
+            RecvErr::Empty => write!(f, "no packet in the input channel yet"),
+        }
+    }
+}
+
+impl std::error::Error for RecvErr {}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::out_channel::{OutChannel, OutChannels};
+
+    use super::*;
+
+    /// Test for draining the closed channels before reporting them closed.
+    ///
+    /// Send 5 packets through a `mpsc` and a `broadcast` channel, and close the senders: all 5
+    /// packets are received from each channel, in order, then [`RecvErr::Closed`].
+    #[test]
+    fn test_drain_on_close() {
+        let (mpsc_tx, mpsc_rx) = mpsc::channel(8);
+        let (bcst_tx, bcst_rx) = broadcast::channel(8);
+        let (sender, receiver) = (NodeId(1), NodeId(2));
+        let mut out_channels = OutChannels::default();
+        out_channels.insert(receiver, Arc::new(Mutex::new(OutChannel::Mpsc(mpsc_tx))));
+        let mut in_channels = InChannels::default();
+        in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Mpsc(mpsc_rx))));
+        in_channels.insert(receiver, Arc::new(Mutex::new(InChannel::Bcst(bcst_rx))));
+
+        for i in 0..5 {
+            out_channels
+                .blocking_send_to(&receiver, Content::new(i))
+                .unwrap();
+            bcst_tx.send(Content::new(i)).unwrap();
+        }
+        out_channels.close_all();
+        drop(bcst_tx);
+
+        for id in [sender, receiver] {
+            for i in 0..5 {
+                let content = in_channels.blocking_recv_from(&id).unwrap();
+                assert_eq!(content.get::<i32>(), Some(&i));
+            }
+            assert!(matches!(
+                in_channels.blocking_recv_from(&id),
+                Err(RecvErr::Closed)
+            ));
+        }
+    }
+
+    /// Test for waiting for a ready channel without receiving from it.
+    ///
+    /// A packet is sent to the second of two channels after a delay: the channel is reported
+    /// ready, and the packet is still received from it afterwards.
+    #[test]
+    fn test_select_ready() {
+        let (_tx1, rx1) = mpsc::channel::<Content>(8);
+        let (tx2, rx2) = mpsc::channel::<Content>(8);
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(rx1))));
+        in_channels.insert(NodeId(2), Arc::new(Mutex::new(InChannel::Mpsc(rx2))));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            tokio::spawn(async move {
+                time::sleep(Duration::from_millis(20)).await;
+                tx2.send(Content::new(7)).await.unwrap();
+            });
+            assert_eq!(in_channels.select_ready().await.unwrap(), NodeId(2));
+            #[cfg(feature = "metrics")]
+            assert_eq!(in_channels.messages_received(&NodeId(2)), 0);
+            let content = in_channels.recv_from(&NodeId(2)).await.unwrap();
+            assert_eq!(content.get::<i32>(), Some(&7));
+        });
+        assert!(matches!(
+            runtime.block_on(InChannels::default().select_ready()),
+            Err(RecvErr::NoSuchChannel)
+        ));
+    }
+
+    /// Test for telling the kind of each input channel.
+    #[test]
+    fn test_channel_type() {
+        let (_mpsc_tx, mpsc_rx) = mpsc::channel::<Content>(8);
+        let (_bcst_tx, bcst_rx) = broadcast::channel::<Content>(8);
+        let (_once_tx, once_rx) = oneshot::channel::<Content>();
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(mpsc_rx))));
+        in_channels.insert(NodeId(2), Arc::new(Mutex::new(InChannel::Bcst(bcst_rx))));
+        in_channels.insert(NodeId(3), Arc::new(Mutex::new(InChannel::Once(once_rx))));
+
+        assert_eq!(
+            in_channels.channel_type(&NodeId(1)),
+            Some(ChannelType::Mpsc)
+        );
+        assert_eq!(
+            in_channels.channel_type(&NodeId(2)),
+            Some(ChannelType::Broadcast)
+        );
+        assert_eq!(
+            in_channels.channel_type(&NodeId(3)),
+            Some(ChannelType::Once)
+        );
+        assert_eq!(in_channels.channel_type(&NodeId(4)), None);
+    }
+
+    /// Test for the message counters of both ends of a channel.
+    ///
+    /// Send 3 packets and receive them until the channel is closed: both counters reach 3,
+    /// the failed receive is not counted, and an unknown channel reports 0.
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_message_counters() {
+        let (tx, rx) = mpsc::channel(8);
+        let (sender, receiver) = (NodeId(1), NodeId(2));
+        let mut out_channels = OutChannels::default();
+        out_channels.insert(receiver, Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        let mut in_channels = InChannels::default();
+        in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+
+        for i in 0..3 {
+            out_channels
+                .blocking_send_to(&receiver, Content::new(i))
+                .unwrap();
+        }
+        assert_eq!(out_channels.messages_sent(&receiver), 3);
+        out_channels.close_all();
+
+        while in_channels.blocking_recv_from(&sender).is_ok() {}
+        assert_eq!(in_channels.messages_received(&sender), 3);
+        assert_eq!(in_channels.messages_received(&receiver), 0);
+    }
+
+    /// Test for folding a packet from each channel.
+    ///
+    /// Of three channels, two have a packet and the third is closed: the sum only adds the
+    /// packets received.
+    #[test]
+    fn test_recv_fold() {
+        let mut in_channels = InChannels::default();
+        for i in 1..=3 {
+            let (tx, rx) = mpsc::channel(8);
+            in_channels.insert(NodeId(i), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+            if i < 3 {
+                tx.blocking_send(Content::new(i * 10)).unwrap();
+            }
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let sum = runtime.block_on(in_channels.recv_fold(0, |sum, (id, content)| {
+            assert_eq!(*content.get::<usize>().unwrap(), id.as_usize() * 10);
+            sum + content.get::<usize>().unwrap()
+        }));
+        assert_eq!(sum, 30);
+    }
+
+    /// Test for receiving a packet from every channel within a deadline.
+    ///
+    /// Step 1: with a packet in both channels, verify both are received.
+    ///
+    /// Step 2: with a packet in the first channel only, verify the receive times out.
+    #[test]
+    fn test_recv_all_timeout() {
+        let mut in_channels = InChannels::default();
+        let mut senders = vec![];
+        for i in 1..=2 {
+            let (tx, rx) = mpsc::channel(8);
+            in_channels.insert(NodeId(i), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+            tx.blocking_send(Content::new(i)).unwrap();
+            senders.push(tx);
+        }
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let timeout = Duration::from_millis(50);
+
+        let received = runtime
+            .block_on(in_channels.recv_all_timeout(timeout))
+            .unwrap();
+        assert_eq!(received.len(), 2);
+        for (id, content) in received {
+            assert_eq!(content.get::<usize>(), Some(&id.as_usize()));
+        }
+
+        senders[0].blocking_send(Content::new(1usize)).unwrap();
+        let result = runtime.block_on(in_channels.recv_all_timeout(timeout));
+        assert!(matches!(result, Err(RecvErr::TimedOut)));
+    }
+
+    /// Test for receiving from all the channels while skipping the failed ones.
+    ///
+    /// Of two channels, one has a packet and the other is closed: only the packet is received.
+    #[test]
+    fn test_map_ok() {
+        let (tx, rx) = mpsc::channel(8);
+        let (closed_tx, closed_rx) = mpsc::channel::<Content>(8);
+        drop(closed_tx);
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        in_channels.insert(NodeId(2), Arc::new(Mutex::new(InChannel::Mpsc(closed_rx))));
+
+        tx.blocking_send(Content::new(1)).unwrap();
+        tx.blocking_send(Content::new(2)).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let values = runtime.block_on(in_channels.map_ok(|c| *c.get::<i32>().unwrap()));
+        assert_eq!(values, vec![1]);
+        let values = runtime.block_on(in_channels.recv_values());
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].get::<i32>(), Some(&2));
+    }
+
+    /// Test for packets with time-to-live.
+    ///
+    /// A packet received after its ttl is rejected, while a packet within its ttl is delivered.
+    #[test]
+    fn test_content_ttl() {
+        let (tx, rx) = mpsc::channel(8);
+        let (sender, receiver) = (NodeId(1), NodeId(2));
+        let mut out_channels = OutChannels::default();
+        out_channels.insert(receiver, Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        let mut in_channels = InChannels::default();
+        in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+
+        let ttl = std::time::Duration::from_millis(10);
+        out_channels
+            .blocking_send_to(&receiver, Content::with_ttl(1, ttl))
+            .unwrap();
+        std::thread::sleep(ttl * 2);
+        assert!(matches!(
+            in_channels.blocking_recv_from(&sender),
+            Err(RecvErr::Expired)
+        ));
+
+        out_channels
+            .blocking_send_to(&receiver, Content::with_ttl(2, ttl * 100))
+            .unwrap();
+        let content = in_channels.blocking_recv_from(&sender).unwrap();
+        assert_eq!(content.get::<i32>(), Some(&2));
+    }
+
+    /// Test for receiving a different type from each channel.
+    ///
+    /// Register `String` for one channel and leave the other one with the default `i32`: both
+    /// values are received with their types, and a packet of another type is rejected.
+    #[test]
+    fn test_recv_typed() {
+        let (name_tx, name_rx) = mpsc::channel(8);
+        let (age_tx, age_rx) = mpsc::channel(8);
+        let (name_id, age_id) = (NodeId(1), NodeId(2));
+        let mut in_channels = TypedInChannels::<i32>::default();
+        in_channels
+            .receivers
+            .insert(name_id, Arc::new(Mutex::new(InChannel::Mpsc(name_rx))));
+        in_channels
+            .receivers
+            .insert(age_id, Arc::new(Mutex::new(InChannel::Mpsc(age_rx))));
+        in_channels.register_type::<String>(name_id);
+
+        name_tx
+            .blocking_send(Content::new("dagrs".to_string()))
+            .unwrap();
+        name_tx.blocking_send(Content::new(1)).unwrap();
+        age_tx.blocking_send(Content::new(3)).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let name = in_channels.recv_typed(&name_id).await.unwrap();
+            assert_eq!(name.downcast::<Arc<String>>().unwrap().as_str(), "dagrs");
+            let age = in_channels.recv_typed(&age_id).await.unwrap();
+            assert_eq!(**age.downcast::<Arc<i32>>().unwrap(), 3);
+            assert!(matches!(
+                in_channels.recv_typed(&name_id).await,
+                Err(RecvErr::TypeMismatch { expected }) if expected == any::type_name::<String>()
+            ));
+        });
+    }
+
+    /// Test for receiving without waiting, from untyped and typed channels.
+    ///
+    /// An empty channel reports [`RecvErr::Empty`], a buffered packet is received and counted,
+    /// a typed channel yields `None` for a packet of another type, and a closed channel
+    /// reports [`RecvErr::Closed`].
+    #[test]
+    fn test_try_recv_from() {
+        let (tx, rx) = mpsc::channel::<Content>(8);
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        assert!(matches!(
+            in_channels.try_recv_from(&NodeId(1)),
+            Err(RecvErr::Empty)
+        ));
+        tx.try_send(Content::new(1)).unwrap();
+        let content = in_channels.try_recv_from(&NodeId(1)).unwrap();
+        assert_eq!(content.get::<i32>(), Some(&1));
+        #[cfg(feature = "metrics")]
+        assert_eq!(in_channels.messages_received(&NodeId(1)), 1);
+        drop(tx);
+        assert!(matches!(
+            in_channels.try_recv_from(&NodeId(1)),
+            Err(RecvErr::Closed)
+        ));
+        assert!(matches!(
+            in_channels.try_recv_from(&NodeId(2)),
+            Err(RecvErr::NoSuchChannel)
+        ));
+
+        let (tx, rx) = broadcast::channel::<Content>(8);
+        let mut typed = TypedInChannels::<i32>::default();
+        typed
+            .receivers
+            .insert(NodeId(1), Arc::new(Mutex::new(InChannel::Bcst(rx))));
+        assert!(matches!(
+            typed.try_recv_from(&NodeId(1)),
+            Err(RecvErr::Empty)
+        ));
+        tx.send(Content::new(2)).unwrap();
+        tx.send(Content::new("two")).unwrap();
+        assert_eq!(*typed.try_recv_from(&NodeId(1)).unwrap().unwrap(), 2);
+        assert!(typed.try_recv_from(&NodeId(1)).unwrap().is_none());
+        drop(tx);
+        assert!(matches!(
+            typed.try_recv_from(&NodeId(1)),
+            Err(RecvErr::Closed)
+        ));
+    }
+
+    /// Test for counting the packets dropped by a lagging receiver.
+    ///
+    /// Send 5 packets through a broadcast channel of capacity 2: the receiver lags behind by 3
+    /// packets, which are counted as dropped, and then receives the last 2 packets.
+    #[test]
+    fn test_messages_dropped() {
+        let (tx, rx) = broadcast::channel(2);
+        let sender = NodeId(1);
+        let mut in_channels = InChannels::default();
+        in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Bcst(rx))));
+        in_channels.set_lag_warning(NodeId(2), 0);
+
+        for i in 0..5 {
+            tx.send(Content::new(i)).unwrap();
+        }
+        drop(tx);
+        assert!(matches!(
+            in_channels.blocking_recv_from(&sender),
+            Err(RecvErr::Lagged(3))
+        ));
+        while in_channels.blocking_recv_from(&sender).is_ok() {}
+        assert_eq!(in_channels.messages_dropped(&sender), 3);
+        #[cfg(feature = "metrics")]
+        assert_eq!(in_channels.messages_received(&sender), 2);
+    }
+
+    /// Test for fast-forwarding a persistently lagging receiver.
+    ///
+    /// Step 1: Send 5 packets through a broadcast channel of capacity 2, fast-forwarded after 2
+    /// lags: the first lag is reported as usual, and the next packet is received.
+    ///
+    /// Step 2: Send 5 more packets: the second lag skips all the buffered packets, reported
+    /// once as dropped and by a `FastForwarded` event, and the receiver gets the next packet sent.
+    #[test]
+    fn test_fast_forward() {
+        let (tx, rx) = broadcast::channel(2);
+        let (sender, receiver) = (NodeId(1), NodeId(2));
+        let events = Arc::new(broadcast::channel(8).0);
+        let mut event_rx = events.subscribe();
+        let mut in_channels = InChannels::default();
+        in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Bcst(rx))));
+        in_channels.set_fast_forward(sender, receiver, 2, events);
+
+        for i in 0..5 {
+            tx.send(Content::new(i)).unwrap();
+        }
+        assert!(matches!(
+            in_channels.blocking_recv_from(&sender),
+            Err(RecvErr::Lagged(3))
+        ));
+        let content = in_channels.blocking_recv_from(&sender).unwrap();
+        assert_eq!(content.get::<i32>(), Some(&3));
+        assert!(event_rx.try_recv().is_err());
+
+        for i in 5..10 {
+            tx.send(Content::new(i)).unwrap();
+        }
+        assert!(matches!(
+            in_channels.blocking_recv_from(&sender),
+            Err(RecvErr::Lagged(6))
+        ));
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(GraphEvent::FastForwarded { from, to, dropped: 6 }) if from == sender && to == receiver
+        ));
+        tx.send(Content::new(10)).unwrap();
+        let content = in_channels.blocking_recv_from(&sender).unwrap();
+        assert_eq!(content.get::<i32>(), Some(&10));
+        assert_eq!(in_channels.messages_dropped(&sender), 9);
+    }
+
+    /// Test for closing channels concurrently with `recv_any`.
+    ///
+    /// 8 senders send 100 packets each, while another handle sharing the channels closes 4 of
+    /// them. `recv_any` never panics nor fails before all the channels are closed: it receives a
+    /// prefix of the packets from the closed channels, and all the packets from the others.
+    #[test]
+    fn test_recv_any_concurrent_close() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut in_channels = InChannels::default();
+            let mut senders = vec![];
+            for i in 0..8 {
+                let (tx, rx) = mpsc::channel(4);
+                in_channels.insert(NodeId(i), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+                senders.push(tx);
+            }
+            let mut other = InChannels {
+                receivers: Receivers {
+                    in_flight: None,
+                    ..in_channels.receivers.clone()
+                },
+                ..Default::default()
+            };
+
+            for tx in senders {
+                tokio::spawn(async move {
+                    for i in 0..100 {
+                        if tx.send(Content::new(i)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            let closing = tokio::spawn(async move {
+                for i in 0..4 {
+                    other.close_async(&NodeId(i)).await;
+                    tokio::task::yield_now().await;
+                }
+            });
+
+            let mut received = vec![vec![]; 8];
+            loop {
+                match in_channels.recv_any().await {
+                    Ok((id, content)) => received[id.0].push(*content.get::<i32>().unwrap()),
+                    Err(RecvErr::Closed) => break,
+                    Err(e) => panic!("unexpected error: {:?}", e),
+                }
+            }
+            closing.await.unwrap();
+
+            for (i, values) in received.iter().enumerate() {
+                let expected = if i < 4 { values.len() as i32 } else { 100 };
+                assert!(values.iter().copied().eq(0..expected));
+            }
+        });
+    }
 }
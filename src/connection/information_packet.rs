@@ -1,28 +1,470 @@
-use std::{any::Any, sync::Arc};
+use std::{
+    any::{self, Any},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use tokio::sync::oneshot;
 
 /// Container type to store task output.
+///
+/// With the `serde` feature, a [`Content`] holding a value of type `()`, `bool`, `i32`, `i64`,
+/// `u32`, `u64`, `usize`, `f32`, `f64`, `String`, `Vec<u8>` or
+/// [`NodeTable`](crate::NodeTable) can be serialized, and is deserialized into a [`Content`]
+/// holding the same type. Serializing any other type fails,
+/// and neither the time-to-live, the trace id nor the [`MessageMeta`] is serialized. With the
+/// `compression` feature, a `String` or a `Vec<u8>` can be serialized compressed, see
+/// [`Content::with_compression`].
 #[derive(Debug, Clone)]
 pub struct Content {
-    pub inner: Arc<dyn Any + Send + Sync>,
+    /// The value, or a [`Lazy`] computing it on first access, see [`Content::get`].
+    inner: Arc<dyn Any + Send + Sync>,
+    /// Name of the type of `inner`, captured at construction.
+    type_name: &'static str,
+    /// How long the packet stays valid after being sent. Never expires if `None`.
+    ttl: Option<Duration>,
+    /// When the packet was sent, only recorded if it has a `ttl`.
+    sent_at: Option<Instant>,
+    /// Identifies the logical packet across the nodes it flows through, see
+    /// [`Content::with_trace_id`].
+    trace_id: Option<u64>,
+    /// Notified when the packet is received, see
+    /// [`OutChannels::send_with_ack`](crate::OutChannels::send_with_ack).
+    ack: Option<Arc<Mutex<Option<oneshot::Sender<()>>>>>,
+    /// The metadata carried along the value, see
+    /// [`OutChannels::send_with_metadata`](crate::OutChannels::send_with_metadata).
+    /// Boxed, since it is seldom set, to keep the packets small.
+    meta: Option<Box<MessageMeta>>,
+    /// Whether the value is compressed when serialized, see [`Content::with_compression`].
+    #[cfg(feature = "compression")]
+    compress: bool,
+}
+
+/// # Message metadata
+///
+/// The metadata of a packet, for end-to-end tracing and latency measurement, carried along
+/// the value rather than inside it, so that the channels stay typed. Sent with
+/// [`OutChannels::send_with_metadata`](crate::OutChannels::send_with_metadata), and received
+/// with [`InChannels::recv_with_metadata`](crate::InChannels::recv_with_metadata).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageMeta {
+    /// When the packet was created, from which its latency is measured.
+    pub timestamp: SystemTime,
+    /// Identifies the logical packet across the nodes it flows through.
+    pub trace_id: u64,
+    /// The priority given by the sender, for the receivers to interpret.
+    pub priority: u8,
+    /// The number of times the packet was sent, incremented by each send.
+    pub hop_count: u32,
+}
+
+impl MessageMeta {
+    /// Create the metadata of a new packet traced as `trace_id`, timestamped now, with priority
+    /// 0 and no hop yet.
+    pub fn new(trace_id: u64) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            trace_id,
+            priority: 0,
+            hop_count: 0,
+        }
+    }
 }
 
 impl Content {
     /// Construct a new [`Content`].
     pub fn new<H: Send + Sync + 'static>(val: H) -> Self {
+        Self::from_arc(Arc::new(val))
+    }
+
+    pub fn from_arc<H: Send + Sync + 'static>(val: Arc<H>) -> Self {
         Self {
-            inner: Arc::new(val),
+            inner: val,
+            type_name: any::type_name::<H>(),
+            ttl: None,
+            sent_at: None,
+            trace_id: None,
+            ack: None,
+            meta: None,
+            #[cfg(feature = "compression")]
+            compress: false,
         }
     }
 
-    pub fn from_arc<H: Send + Sync + 'static>(val: Arc<H>) -> Self {
-        Self { inner: val }
+    /// Construct a new [`Content`] holding a binary payload, see [`Content::as_bytes`].
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+
+    /// Construct a new [`Content`] whose value is computed by `producer` on first access, e.g.
+    /// by [`Content::get`], rather than upfront.
+    ///
+    /// This defers expensive work, such as decoding a large payload, which is wasted if the
+    /// receiving node drops the packet without looking at its value. The value is computed at
+    /// most once, and shared by the clones of the packet.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let computed = Arc::new(AtomicBool::new(false));
+    /// let flag = computed.clone();
+    /// let content = Content::lazy(Box::new(move || {
+    ///     flag.store(true, Ordering::SeqCst);
+    ///     vec![0u8; 1024]
+    /// }));
+    /// assert!(content.is::<Vec<u8>>());
+    /// assert!(!computed.load(Ordering::SeqCst));
+    /// assert_eq!(content.get::<Vec<u8>>().unwrap().len(), 1024);
+    /// assert!(computed.load(Ordering::SeqCst));
+    /// ```
+    pub fn lazy<T: Send + Sync + 'static>(
+        producer: Box<dyn FnOnce() -> T + Send + 'static>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Lazy {
+                value: OnceLock::new(),
+                producer: Mutex::new(Some(producer)),
+            }),
+            type_name: any::type_name::<T>(),
+            ttl: None,
+            sent_at: None,
+            trace_id: None,
+            ack: None,
+            meta: None,
+            #[cfg(feature = "compression")]
+            compress: false,
+        }
+    }
+
+    /// Construct a new [`Content`] that expires `ttl` after being sent.
+    ///
+    /// Receiving an expired packet yields [`RecvErr::Expired`](crate::RecvErr::Expired) instead
+    /// of the stale value.
+    pub fn with_ttl<H: Send + Sync + 'static>(val: H, ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..Self::new(val)
+        }
     }
 
+    /// Tag this [`Content`] with a trace id, identifying the logical packet it carries.
+    ///
+    /// The clones of the packet share the trace id, so a packet reaching a node through several
+    /// paths can be recognized, e.g. by a [`DedupNode`](crate::DedupNode).
+    pub fn with_trace_id(mut self, trace_id: u64) -> Self {
+        self.trace_id = Some(trace_id);
+        self
+    }
+
+    /// Compress the value of this [`Content`] with LZ4 when it is serialized, e.g. to send it
+    /// to another process, if it is a `String` or a `Vec<u8>`. The compressed form is
+    /// deserialized into the original value. Other values are serialized as usual.
+    ///
+    /// The value held in memory is left as is, so that it costs nothing until serialized. Then,
+    /// compressing trades CPU time on both ends for fewer bytes to transfer, which pays off for
+    /// large, compressible payloads, while small or already compressed ones only cost CPU time.
+    /// The edges connected by [`Graph::connect_compressed`](crate::Graph::connect_compressed)
+    /// apply it to every packet sent through them.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self) -> Self {
+        self.compress = true;
+        self
+    }
+
+    /// Returns the trace id given by [`Content::with_trace_id`], if any.
+    pub fn trace_id(&self) -> Option<u64> {
+        self.trace_id
+    }
+
+    /// Returns a reference to the inner value if it is of type `H`, computing it first if the
+    /// [`Content`] is [lazy](Content::lazy).
     pub fn get<H: 'static>(&self) -> Option<&H> {
-        self.inner.downcast_ref::<H>()
+        match self.inner.downcast_ref::<Lazy<H>>() {
+            Some(lazy) => Some(lazy.force()),
+            None => self.inner.downcast_ref::<H>(),
+        }
     }
 
     pub fn into_inner<H: Send + Sync + 'static>(self) -> Option<Arc<H>> {
-        self.inner.downcast::<H>().ok()
+        match self.inner.downcast_ref::<Lazy<H>>() {
+            Some(lazy) => {
+                lazy.force();
+                lazy.value.get().cloned()
+            }
+            None => self.inner.downcast::<H>().ok(),
+        }
+    }
+
+    /// Clone the inner value of type `H` by value, returning an owned copy that can be
+    /// mutated without affecting the other holders of this [`Content`].
+    ///
+    /// Returns `None` if the inner value is not of type `H`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// let content = Content::new(vec![1, 2, 3]);
+    /// let mut owned: Vec<i32> = content.try_clone_owned().unwrap();
+    /// owned.push(4);
+    /// assert_eq!(content.get::<Vec<i32>>().unwrap().len(), 3);
+    /// ```
+    pub fn try_clone_owned<H: Clone + 'static>(&self) -> Option<H> {
+        self.get::<H>().cloned()
+    }
+
+    /// Returns true if the two [`Content`]s share the same allocation, e.g. they are clones of
+    /// the same packet, rather than just holding equal values.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// let content = Content::new(1);
+    /// assert!(content.ptr_eq(&content.clone()));
+    /// assert!(!content.ptr_eq(&Content::new(1)));
+    /// ```
+    pub fn ptr_eq(&self, other: &Content) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Transform the inner value of type `T` with `f`, into a [`Content`] holding the result
+    /// and carrying the trace id and the time-to-live of this one, see
+    /// [`Content::with_trace_id`] and [`Content::with_ttl`].
+    ///
+    /// Returns this [`Content`] unchanged if the inner value is not of type `T`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// let content = Content::new(21).with_trace_id(7);
+    /// let doubled = content.map(|value: &i32| value * 2).unwrap();
+    /// assert_eq!(doubled.get::<i32>(), Some(&42));
+    /// assert_eq!(doubled.trace_id(), Some(7));
+    /// assert!(doubled.map(|value: &String| value.len()).is_err());
+    /// ```
+    pub fn map<T, U, F>(self, f: F) -> Result<Content, Content>
+    where
+        T: 'static,
+        U: Send + Sync + 'static,
+        F: FnOnce(&T) -> U,
+    {
+        let Some(value) = self.get::<T>() else {
+            return Err(self);
+        };
+        Ok(Content {
+            ttl: self.ttl,
+            trace_id: self.trace_id,
+            ..Content::new(f(value))
+        })
+    }
+
+    /// Returns the name of the type of the inner value, as given by [`std::any::type_name`].
+    ///
+    /// The name is meant for diagnostics, such as logging the packets flowing through an edge,
+    /// and its exact format is not guaranteed. Use [`Content::is`] to check the type.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// assert_eq!(Content::new(1u8).type_name(), "u8");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the inner value as a byte slice, without copying, if it is a `Vec<u8>`, or a
+    /// `bytes::Bytes` with the `bytes` feature.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// let content = Content::from_bytes(vec![1, 2, 3]);
+    /// assert_eq!(content.as_bytes(), Some(&[1, 2, 3][..]));
+    /// assert_eq!(Content::new("text").as_bytes(), None);
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Some(bytes) = self.get::<Vec<u8>>() {
+            return Some(bytes);
+        }
+        #[cfg(feature = "bytes")]
+        if let Some(bytes) = self.get::<bytes::Bytes>() {
+            return Some(bytes);
+        }
+        None
+    }
+
+    /// Returns true if the inner value is of type `H`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Content;
+    ///
+    /// let content = Content::new(String::from("dagrs"));
+    /// assert!(content.is::<String>());
+    /// assert!(!content.is::<&str>());
+    /// ```
+    pub fn is<H: 'static>(&self) -> bool {
+        self.inner.is::<H>() || self.inner.is::<Lazy<H>>()
+    }
+
+    /// Record the time of sending, from which the `ttl` counts.
+    pub(crate) fn mark_sent(&mut self) {
+        if self.ttl.is_some() {
+            self.sent_at = Some(Instant::now());
+        }
+    }
+
+    /// Attach `meta` to the packet, replacing any metadata.
+    pub(crate) fn set_meta(&mut self, meta: MessageMeta) {
+        self.meta = Some(Box::new(meta));
+    }
+
+    /// Detach the metadata of the packet, if any.
+    pub(crate) fn take_meta(&mut self) -> Option<MessageMeta> {
+        self.meta.take().map(|meta| *meta)
+    }
+
+    /// Notify `ack` once the packet is received.
+    pub(crate) fn set_ack(&mut self, ack: oneshot::Sender<()>) {
+        self.ack = Some(Arc::new(Mutex::new(Some(ack))));
+    }
+
+    /// Acknowledge the receipt of the packet, if the sender waits for it.
+    pub(crate) fn acknowledge(&self) {
+        if let Some(ack) = self.ack.as_ref().and_then(|ack| ack.lock().unwrap().take()) {
+            let _ = ack.send(());
+        }
+    }
+
+    /// Returns true if the `ttl` has elapsed since the packet was sent.
+    pub(crate) fn is_expired(&self) -> bool {
+        match (self.ttl, self.sent_at) {
+            (Some(ttl), Some(sent_at)) => sent_at.elapsed() > ttl,
+            _ => false,
+        }
+    }
+}
+
+/// The value of a [lazy](Content::lazy) [`Content`], computed by `producer` on first access.
+struct Lazy<T> {
+    value: OnceLock<Arc<T>>,
+    producer: Mutex<Option<Box<dyn FnOnce() -> T + Send>>>,
+}
+
+impl<T> Lazy<T> {
+    fn force(&self) -> &T {
+        self.value.get_or_init(|| {
+            let producer = self.producer.lock().unwrap().take().unwrap();
+            Arc::new(producer())
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Content;
+    use crate::node::node::NodeTable;
+
+    macro_rules! serializable_values {
+        ($($variant:ident($ty:ty)),* $(,)?) => {
+            #[derive(Serialize)]
+            enum ValueRef<'a> {
+                $($variant(&'a $ty),)*
+                #[cfg(feature = "compression")]
+                Lz4 { text: bool, bytes: Vec<u8> },
+            }
+
+            #[derive(Deserialize)]
+            enum Value {
+                $($variant($ty),)*
+                #[cfg(feature = "compression")]
+                Lz4 { text: bool, bytes: Vec<u8> },
+            }
+
+            impl Serialize for Content {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    #[cfg(feature = "compression")]
+                    if let Some(compressed) = self.compressed() {
+                        return compressed.serialize(serializer);
+                    }
+                    $(
+                        if let Some(value) = self.get::<$ty>() {
+                            return ValueRef::$variant(value).serialize(serializer);
+                        }
+                    )*
+                    Err(S::Error::custom("the value in the content is not serializable"))
+                }
+            }
+
+            impl<'de> Deserialize<'de> for Content {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    Ok(match Value::deserialize(deserializer)? {
+                        $(Value::$variant(value) => Content::new(value),)*
+                        #[cfg(feature = "compression")]
+                        Value::Lz4 { text, bytes } => {
+                            decompress(text, &bytes).map_err(serde::de::Error::custom)?
+                        }
+                    })
+                }
+            }
+        };
+    }
+
+    serializable_values! {
+        Unit(()),
+        Bool(bool),
+        I32(i32),
+        I64(i64),
+        U32(u32),
+        U64(u64),
+        Usize(usize),
+        F32(f32),
+        F64(f64),
+        String(String),
+        Bytes(Vec<u8>),
+        NodeTable(NodeTable),
+    }
+
+    impl Content {
+        /// Returns the value compressed with LZ4 if it is to be compressed, see
+        /// [`Content::with_compression`], and can be.
+        #[cfg(feature = "compression")]
+        fn compressed(&self) -> Option<ValueRef<'_>> {
+            if !self.compress {
+                return None;
+            }
+            let (text, bytes) = match (self.get::<String>(), self.get::<Vec<u8>>()) {
+                (Some(text), _) => (true, text.as_bytes()),
+                (_, Some(bytes)) => (false, &bytes[..]),
+                _ => return None,
+            };
+            Some(ValueRef::Lz4 {
+                text,
+                bytes: lz4_flex::compress_prepend_size(bytes),
+            })
+        }
+    }
+
+    /// Decompress a value compressed by [`Content::compressed`], into a `String` if it is
+    /// `text`, otherwise a `Vec<u8>`.
+    #[cfg(feature = "compression")]
+    fn decompress(text: bool, bytes: &[u8]) -> Result<Content, String> {
+        let bytes = lz4_flex::decompress_size_prepended(bytes).map_err(|e| e.to_string())?;
+        if text {
+            String::from_utf8(bytes)
+                .map(Content::new)
+                .map_err(|e| e.to_string())
+        } else {
+            Ok(Content::new(bytes))
+        }
     }
 }
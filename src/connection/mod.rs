@@ -1,3 +1,6 @@
+pub(crate) mod backpressure;
+pub(crate) mod cutoff;
 pub mod in_channel;
 pub mod information_packet;
 pub mod out_channel;
+pub mod rate_limiter;
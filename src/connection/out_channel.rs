@@ -1,78 +1,709 @@
-use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    marker::PhantomData,
+    pin::pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+#[cfg(feature = "compression")]
+use std::collections::HashSet;
 
 use futures::future::join_all;
-use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 
-use crate::node::node::NodeId;
+use crate::{
+    graph::event::GraphEvent,
+    node::node::NodeId,
+    utils::{in_flight::InFlight, watchdog::touch},
+};
 
-use super::information_packet::Content;
+use super::{
+    backpressure::{Backpressure, EdgeBackpressure},
+    cutoff::{Cutoff, SharedReceiver},
+    in_channel::ChannelType,
+    information_packet::{Content, MessageMeta},
+    rate_limiter::RateLimiter,
+};
 
 /// # Output Channels
 /// A hash-table mapping `NodeId` to `OutChannel`. In **Dagrs**, each `Node` stores output
 /// channels in this map, enabling `Node` to send information packets to other `Node`s.
+///
+/// Sending through an edge with a [`RateLimiter`] waits until the limiter allows it.
+/// With the `metrics` feature, every channel also counts the packets successfully sent through
+/// it, see [`OutChannels::messages_sent`].
+///
+/// A set of receivers can be given a name with [`OutChannels::define_group`], and sent to
+/// together with [`OutChannels::send_to_group`].
+///
+/// Sending through a channel filled above the backpressure threshold of its receiver, see
+/// [`InChannels::backpressure_signal`], waits until the receiver drains it below the threshold,
+/// while [`OutChannels::try_send_to`] fails with [`SendErr::Backpressure`] instead.
+///
+/// [`InChannels::backpressure_signal`]: crate::InChannels::backpressure_signal
+///
+/// The successors can also share the work rather than all receive it: see
+/// [`OutChannels::send_balanced`].
+///
+/// # Send patterns
+/// - [`OutChannels::unicast`] sends a packet to a single successor.
+/// - [`OutChannels::broadcast`] sends a packet to all the successors, and reports a result
+///   for each of them. The packet is never deep-copied: each successor gets a clone of the same
+///   [`Content`], see [`Content::ptr_eq`].
+///
+/// Both patterns work on every kind of [`OutChannel`], with the following differences:
+/// - `Mpsc`: the successor receives each packet sent to it.
+/// - `Once`: only the first packet sent through the channel is delivered, any later send,
+///   unicast or broadcast, fails with [`SendErr::ClosedChannel`] for this channel only.
+/// - `Bcst`: the packet is delivered to every receiver subscribed to the channel, even with a
+///   unicast, since the receivers share the sender.
 #[derive(Default)]
-pub struct OutChannels(pub(crate) HashMap<NodeId, Arc<Mutex<OutChannel>>>);
+pub struct OutChannels {
+    /// The channels to the successors, shared with the typed view of these channels.
+    pub(crate) senders: Senders,
+    /// The named sets of receivers, see [`OutChannels::define_group`].
+    pub(crate) groups: HashMap<String, Vec<NodeId>>,
+    /// See [`OutChannels::send_balanced`].
+    pub(crate) balancer: Balancer,
+}
 
 impl OutChannels {
     /// Perform a blocking send on the outcoming channel from `NodeId`.
     pub fn blocking_send_to(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
-        match self.get(id) {
-            Some(channel) => channel.blocking_lock().blocking_send(content),
-            None => Err(SendErr::NoSuchChannel),
-        }
+        self.senders.blocking_send(id, content)
+    }
+
+    /// Try to send the `content` to `NodeId` without waiting.
+    ///
+    /// Returns [`SendErr::Backpressure`] with the `content` if the channel is filled above the
+    /// backpressure threshold of the receiver, the channel is full, or the rate limiter of the
+    /// edge does not allow a send yet. The send can be tried again later.
+    pub fn try_send_to(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
+        self.senders.try_send(id, content)
+    }
+
+    /// Sends the `content` to the successor `NodeId` only, same as [`OutChannels::send_to`].
+    ///
+    /// Returns [`SendErr::NoSuchChannel`] if `NodeId` is not a successor.
+    pub async fn unicast(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
+        self.send_to(id, content).await
     }
 
     /// Perform a asynchronous send on the outcoming channel from `NodeId`.
     pub async fn send_to(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
-        match self.get(id) {
-            Some(channel) => channel.lock().await.send(content).await,
-            None => Err(SendErr::NoSuchChannel),
+        self.senders.send(id, content).await
+    }
+
+    /// Sends all the `contents` to `NodeId` in order, like [`OutChannels::send_to`], locking
+    /// the channel once for the whole batch rather than once per packet.
+    ///
+    /// Stops at the first failed send and returns its error, the remaining packets are dropped.
+    pub async fn send_batch(&self, id: &NodeId, contents: Vec<Content>) -> Result<(), SendErr> {
+        self.senders.send_batch(id, contents).await
+    }
+
+    /// Sends the `content` to `NodeId` like [`OutChannels::send_to`], along with `meta`, whose
+    /// `hop_count` is incremented. The receiving node gets both with
+    /// [`InChannels::recv_with_metadata`](crate::InChannels::recv_with_metadata), and can
+    /// forward the packet with the same metadata to count the hops.
+    pub async fn send_with_metadata(
+        &self,
+        id: &NodeId,
+        mut content: Content,
+        mut meta: MessageMeta,
+    ) -> Result<(), SendErr> {
+        meta.hop_count += 1;
+        content.set_meta(meta);
+        self.send_to(id, content).await
+    }
+
+    /// Sends the `content` to `NodeId` like [`OutChannels::send_to`], and waits until the
+    /// receiving node receives it.
+    ///
+    /// Returns [`AckError::Send`] if the send fails, or [`AckError::Dropped`] if the packet is
+    /// dropped without being received, e.g. the channel is closed or the packet expires. Through
+    /// a broadcast channel, the first receiver to receive the packet acknowledges it.
+    pub async fn send_with_ack(&self, id: &NodeId, mut content: Content) -> Result<(), AckError> {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        content.set_ack(ack_tx);
+        self.send_to(id, content).await.map_err(AckError::Send)?;
+        ack_rx.await.map_err(|_| AckError::Dropped)
+    }
+
+    /// Names the group of receivers `members`, replacing the group of the same name if any.
+    pub fn define_group(&mut self, group_name: &str, members: &[NodeId]) {
+        self.groups.insert(group_name.to_string(), members.to_vec());
+    }
+
+    /// Sends the `content` to every member of the group `group_name`, in the order they are
+    /// given to [`OutChannels::define_group`].
+    ///
+    /// Nothing is sent if the group is not defined ([`SendErr::NoSuchGroup`]) or a member has
+    /// no channel ([`SendErr::NoSuchChannel`]). Otherwise the sends stop at the first failure,
+    /// which is returned, and the rest of the members are not attempted.
+    pub async fn send_to_group(&self, group_name: &str, content: Content) -> Result<(), SendErr> {
+        let members = self.groups.get(group_name).ok_or(SendErr::NoSuchGroup)?;
+        if members
+            .iter()
+            .any(|id| !self.senders.channels.contains_key(id))
+        {
+            return Err(SendErr::NoSuchChannel);
+        }
+        for id in members {
+            self.send_to(id, content.clone()).await?;
         }
+        Ok(())
+    }
+
+    /// Sends the `content` to a single successor, picked by the [`BalancePolicy`] of these
+    /// channels, see [`Graph::set_balance_policy`](crate::Graph::set_balance_policy). Returns
+    /// the successor the packet is sent to.
+    ///
+    /// It distributes the work among identical successors, e.g. a pool of workers, rather
+    /// than sending it to all of them like [`OutChannels::broadcast`]. Returns
+    /// [`SendErr::NoSuchChannel`] if there is no successor. Failing to send to the picked
+    /// successor is returned as is: the packet is not sent to another one.
+    pub async fn send_balanced(&self, content: Content) -> Result<NodeId, SendErr> {
+        let mut ids = self.get_receiver_ids();
+        if ids.is_empty() {
+            return Err(SendErr::NoSuchChannel);
+        }
+        ids.sort_unstable();
+        let turn = self.balancer.cursor.fetch_add(1, Ordering::Relaxed) % ids.len();
+        ids.rotate_left(turn);
+        let id = match self.balancer.policy {
+            BalancePolicy::RoundRobin => ids[0],
+            BalancePolicy::LeastLoaded => {
+                let mut least = (ids[0], usize::MAX);
+                for id in ids {
+                    let queued = match self.senders.get(&id) {
+                        Some(channel) => channel.lock().await.fill().map_or(0, |(len, _)| len),
+                        None => continue,
+                    };
+                    if queued < least.1 {
+                        least = (id, queued);
+                    }
+                }
+                least.0
+            }
+        };
+        self.send_to(&id, content).await.map(|_| id)
     }
 
     /// Broadcasts the `content` to all the [`OutChannel`]s asynchronously.
     pub async fn broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
-        let futures = self
-            .0
-            .iter()
-            .map(|(_, c)| async { c.lock().await.send(content.clone()).await });
-
-        join_all(futures).await
+        self.senders.broadcast(content).await
     }
 
     /// Blocking broadcasts the `content` to all the [`OutChannel`]s.
     pub fn blocking_broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
-        self.0
-            .iter()
-            .map(|(_, c)| c.blocking_lock().blocking_send(content.clone()))
-            .collect()
+        self.senders.blocking_broadcast(content)
+    }
+
+    /// Broadcasts the `content` to all the [`OutChannel`]s without waiting, skipping the full
+    /// ones, see [`Graph::restore`](crate::Graph::restore).
+    pub(crate) fn try_broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
+        self.senders.try_broadcast(content)
     }
 
     /// Close the channel by the given `NodeId`, and remove the channel in this map.
     pub fn close(&mut self, id: &NodeId) {
-        if let Some(_) = self.get(id) {
-            self.0.remove(id);
-        }
+        self.senders.close(id);
+    }
+
+    /// Close the channel to `NodeId`, so that its receiver sees the channel closed once it
+    /// received the packets already sent, while the other channels keep working.
+    ///
+    /// Unlike [`OutChannels::close`], it only needs a shared reference, and the channel stays in
+    /// this map: any later send to `NodeId` fails with [`SendErr::ClosedChannel`]. A `Bcst`
+    /// channel shared by several receivers, see
+    /// [`Graph::connect_broadcast`](crate::Graph::connect_broadcast), is only closed for
+    /// `NodeId`: the other receivers keep receiving the packets sent through it. Otherwise, its
+    /// receiver only sees it closed once all the senders of the channel are closed.
+    pub async fn close_to(&self, id: &NodeId) {
+        self.senders.close_to(id).await;
     }
 
     pub(crate) fn close_all(&mut self) {
-        self.0.clear();
+        self.senders.close_all();
     }
 
-    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<OutChannel>>> {
-        match self.0.get(id) {
-            Some(c) => Some(c.clone()),
-            None => None,
-        }
+    /// Returns the number of packets buffered in the channel to `NodeId`, sent but not received
+    /// yet, so that a producer can pause before flooding a slow receiver.
+    ///
+    /// For a `broadcast` channel, it is the number of packets not received yet by the slowest
+    /// receiver. Returns `None` if there is no such channel, for a `oneshot` channel, or if the
+    /// channel is being sent to concurrently.
+    pub fn pending_count(&self, id: &NodeId) -> Option<usize> {
+        self.senders.get(id)?.try_lock().ok()?.pending()
+    }
+
+    /// Returns the number of packets successfully sent through the channel to `NodeId`
+    /// so far, or 0 if there is no such channel.
+    #[cfg(feature = "metrics")]
+    pub fn messages_sent(&self, id: &NodeId) -> u64 {
+        self.senders.messages_sent(id)
+    }
+
+    /// Track the packets sent through these channels in `in_flight`.
+    pub(crate) fn set_in_flight(&mut self, in_flight: Arc<InFlight>) {
+        self.senders.in_flight = Some(in_flight);
+    }
+
+    /// Record every send through these channels as an activity in `activity`.
+    pub(crate) fn set_activity(&mut self, activity: Arc<AtomicU64>) {
+        self.senders.hooks.activity = Some(activity);
+    }
+
+    /// Count the sends in progress through these channels in `pending_sends`.
+    pub(crate) fn set_pending_sends(&mut self, pending_sends: Arc<AtomicUsize>) {
+        self.senders.hooks.pending_sends = Some(pending_sends);
+    }
+
+    /// Compress the packets sent to `NodeId` when serialized, see
+    /// [`Graph::connect_compressed`](crate::Graph::connect_compressed).
+    #[cfg(feature = "compression")]
+    pub(crate) fn set_compression(&mut self, id: NodeId) {
+        self.senders.hooks.compressed.insert(id);
+    }
+
+    /// Report every packet sent through these channels by node `sender` to `events`.
+    pub(crate) fn set_events(
+        &mut self,
+        sender: NodeId,
+        events: Arc<broadcast::Sender<GraphEvent>>,
+    ) {
+        self.senders.hooks.events = Some((sender, events));
+    }
+
+    /// Pick the successor of [`OutChannels::send_balanced`] with `policy`.
+    pub(crate) fn set_balance_policy(&mut self, policy: BalancePolicy) {
+        self.balancer.policy = policy;
+    }
+
+    /// Limit the rate of sending packets to `NodeId`.
+    pub(crate) fn set_rate_limiter(&mut self, id: NodeId, limiter: RateLimiter) {
+        self.senders.limiters.insert(id, Arc::new(limiter));
+    }
+
+    /// Share the [`Cutoff`] of the receiver `NodeId` of a `Bcst` channel shared with other
+    /// receivers, which all count the packets sent through the channel with `sent`.
+    pub(crate) fn set_cutoff(&mut self, id: NodeId, sent: Arc<AtomicU64>, cutoff: Arc<Cutoff>) {
+        self.senders
+            .shared
+            .insert(id, SharedReceiver { sent, cutoff });
+    }
+
+    /// Share the backpressure of the receiver `NodeId`, through a flag of its own for the
+    /// edge to it.
+    pub(crate) fn set_backpressure(&mut self, id: NodeId, backpressure: Arc<Backpressure>) {
+        self.senders
+            .backpressure
+            .insert(id, Arc::new(EdgeBackpressure::new(backpressure)));
     }
 
     pub(crate) fn insert(&mut self, node_id: NodeId, channel: Arc<Mutex<OutChannel>>) {
-        self.0.insert(node_id, channel);
+        self.senders.insert(node_id, channel);
+    }
+
+    /// Returns true if there is no channel.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.senders.channels.is_empty()
     }
 
     /// Returns a list of all available receiver node IDs.
     pub fn get_receiver_ids(&self) -> Vec<NodeId> {
-        self.0.keys().copied().collect()
+        self.senders.receiver_ids()
+    }
+
+    /// Returns the kind and the capacity of the channel to `NodeId`, see
+    /// [`OutChannel::channel_type`] and [`OutChannel::capacity`]. Returns `None` if the channel
+    /// is busy sending.
+    pub(crate) fn channel_info(&self, id: &NodeId) -> Option<(ChannelType, usize)> {
+        let channel = self.senders.get(id)?;
+        let channel = channel.try_lock().ok()?;
+        Some((channel.channel_type(), channel.capacity()))
+    }
+}
+
+/// The channels to the successors of a node, keyed by the receiver's id, along with the
+/// settings of the edges and the observers of the sends.
+///
+/// It is shared by [`OutChannels`] and [`TypedOutChannels`], so that a packet goes through the
+/// same rate limiting, backpressure, compression and accounting whichever is used to send it.
+#[derive(Default, Clone)]
+pub(crate) struct Senders {
+    pub(crate) channels: HashMap<NodeId, Arc<Mutex<OutChannel>>>,
+    /// The rate limiters of the edges, see [`OutChannels::set_rate_limiter`].
+    limiters: HashMap<NodeId, Arc<RateLimiter>>,
+    /// The packets sent through each channel, see [`OutChannels::messages_sent`].
+    #[cfg(feature = "metrics")]
+    counters: HashMap<NodeId, Arc<AtomicU64>>,
+    /// See [`OutChannels::set_in_flight`].
+    in_flight: Option<Arc<InFlight>>,
+    /// The backpressure of the receivers, see [`OutChannels::set_backpressure`].
+    backpressure: HashMap<NodeId, Arc<EdgeBackpressure>>,
+    /// The receivers sharing a `Bcst` channel with others, see [`OutChannels::set_cutoff`].
+    shared: HashMap<NodeId, SharedReceiver>,
+    hooks: SendHooks,
+}
+
+impl Senders {
+    /// Perform a blocking send to `NodeId`.
+    fn blocking_send(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
+        match self.get(id) {
+            Some(channel) => {
+                self.blocking_throttle(id);
+                let mut channel = channel.blocking_lock();
+                if self.is_cut_off(id) {
+                    return Err(SendErr::ClosedChannel(content));
+                }
+                let _sending = self.hooks.sending();
+                futures::executor::block_on(self.give_way(id, &channel));
+                let entered = self.enter_in_flight(&channel);
+                self.count(
+                    id,
+                    entered,
+                    channel.blocking_send(self.hooks.encode(id, content)),
+                )
+            }
+            None => Err(SendErr::NoSuchChannel),
+        }
+    }
+
+    /// Try to send to `NodeId` without waiting, see [`OutChannels::try_send_to`].
+    fn try_send(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
+        match self.get(id) {
+            Some(channel) => {
+                let Ok(mut channel) = channel.try_lock() else {
+                    return Err(SendErr::Backpressure(content));
+                };
+                if self.is_cut_off(id) {
+                    return Err(SendErr::ClosedChannel(content));
+                }
+                if self.pressed(id, &channel) {
+                    return Err(SendErr::Backpressure(content));
+                }
+                if let Some(limiter) = self.limiters.get(id) {
+                    if limiter.try_acquire().is_some() {
+                        return Err(SendErr::Backpressure(content));
+                    }
+                }
+                let entered = self.enter_in_flight(&channel);
+                self.count(
+                    id,
+                    entered,
+                    channel.try_send(self.hooks.encode(id, content)),
+                )
+            }
+            None => Err(SendErr::NoSuchChannel),
+        }
+    }
+
+    /// Perform a asynchronous send to `NodeId`.
+    async fn send(&self, id: &NodeId, content: Content) -> Result<(), SendErr> {
+        match self.get(id) {
+            Some(channel) => {
+                self.throttle(id).await;
+                let mut channel = channel.lock().await;
+                if self.is_cut_off(id) {
+                    return Err(SendErr::ClosedChannel(content));
+                }
+                let _sending = self.hooks.sending();
+                self.give_way(id, &channel).await;
+                let entered = self.enter_in_flight(&channel);
+                self.count(
+                    id,
+                    entered,
+                    channel.send(self.hooks.encode(id, content)).await,
+                )
+            }
+            None => Err(SendErr::NoSuchChannel),
+        }
+    }
+
+    /// Send all the `contents` to `NodeId` in order, see [`OutChannels::send_batch`].
+    async fn send_batch(&self, id: &NodeId, contents: Vec<Content>) -> Result<(), SendErr> {
+        let Some(channel) = self.get(id) else {
+            return Err(SendErr::NoSuchChannel);
+        };
+        let mut channel = channel.lock().await;
+        for content in contents {
+            if self.is_cut_off(id) {
+                return Err(SendErr::ClosedChannel(content));
+            }
+            self.throttle(id).await;
+            let _sending = self.hooks.sending();
+            self.give_way(id, &channel).await;
+            let entered = self.enter_in_flight(&channel);
+            self.count(
+                id,
+                entered,
+                channel.send(self.hooks.encode(id, content)).await,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Send the `content` to all the channels asynchronously, once through each channel shared
+    /// by several receivers.
+    async fn broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
+        let content = &content;
+        let futures = self.fan_out().into_iter().map(|(ids, c)| async move {
+            let id = &ids[0];
+            self.throttle(id).await;
+            let mut channel = c.lock().await;
+            let _sending = self.hooks.sending();
+            self.give_way(id, &channel).await;
+            let entered = self.enter_in_flight(&channel);
+            let result = channel.send(self.hooks.encode(id, content.clone())).await;
+            self.count_shared(&ids, entered, result, content)
+        });
+
+        join_all(futures).await.into_iter().flatten().collect()
+    }
+
+    /// Send the `content` to all the channels, blocking, once through each channel shared by
+    /// several receivers.
+    fn blocking_broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
+        self.fan_out()
+            .into_iter()
+            .flat_map(|(ids, c)| {
+                let id = &ids[0];
+                self.blocking_throttle(id);
+                let mut channel = c.blocking_lock();
+                let _sending = self.hooks.sending();
+                futures::executor::block_on(self.give_way(id, &channel));
+                let entered = self.enter_in_flight(&channel);
+                let result = channel.blocking_send(self.hooks.encode(id, content.clone()));
+                self.count_shared(&ids, entered, result, &content)
+            })
+            .collect()
+    }
+
+    /// Send the `content` to all the channels without waiting, once through each channel shared
+    /// by several receivers. A channel which is full or in use fails with
+    /// [`SendErr::Backpressure`].
+    fn try_broadcast(&self, content: Content) -> Vec<Result<(), SendErr>> {
+        self.fan_out()
+            .into_iter()
+            .flat_map(|(ids, c)| {
+                let id = &ids[0];
+                let Ok(mut channel) = c.try_lock() else {
+                    return vec![Err(SendErr::Backpressure(content.clone())); ids.len()];
+                };
+                let entered = self.enter_in_flight(&channel);
+                let result = channel.try_send(self.hooks.encode(id, content.clone()));
+                self.count_shared(&ids, entered, result, &content)
+            })
+            .collect()
+    }
+
+    /// Group the receivers by channel: the receivers of a `broadcast` edge, see
+    /// [`Graph::connect_broadcast`](crate::Graph::connect_broadcast), share a single channel.
+    /// The receivers the channel is closed for come last.
+    fn fan_out(&self) -> Vec<(Vec<NodeId>, Arc<Mutex<OutChannel>>)> {
+        let mut groups: Vec<(Vec<NodeId>, Arc<Mutex<OutChannel>>)> = vec![];
+        let mut index: HashMap<_, usize> = HashMap::new();
+        for (id, channel) in &self.channels {
+            match index.entry(Arc::as_ptr(channel)) {
+                Entry::Occupied(entry) => groups[*entry.get()].0.push(*id),
+                Entry::Vacant(entry) => {
+                    entry.insert(groups.len());
+                    groups.push((vec![*id], channel.clone()));
+                }
+            }
+        }
+        for (ids, _) in &mut groups {
+            ids.sort_by_key(|id| self.is_cut_off(id));
+        }
+        groups
+    }
+
+    /// Remove the channel to `NodeId` and the settings of its edge.
+    fn close(&mut self, id: &NodeId) {
+        if let Some(shared) = self.shared.remove(id) {
+            shared.cutoff.close(shared.sent.load(Ordering::Relaxed));
+        }
+        if self.channels.remove(id).is_some() {
+            self.limiters.remove(id);
+            #[cfg(feature = "metrics")]
+            self.counters.remove(id);
+            self.backpressure.remove(id);
+        }
+    }
+
+    /// Close the channel to `NodeId`, keeping it in the map, see [`OutChannels::close_to`].
+    ///
+    /// A channel shared with other receivers is only closed for `NodeId`, until it is closed
+    /// for all of them.
+    async fn close_to(&self, id: &NodeId) {
+        let Some(c) = self.get(id) else {
+            return;
+        };
+        let mut channel = c.lock().await;
+        if let Some(shared) = self.shared.get(id) {
+            shared.cutoff.close(shared.sent.load(Ordering::Relaxed));
+            if self
+                .channels
+                .iter()
+                .any(|(other, channel)| Arc::ptr_eq(channel, &c) && !self.is_cut_off(other))
+            {
+                return;
+            }
+        }
+        channel.close();
+    }
+
+    /// Returns true if the channel to `NodeId` is shared with other receivers, and closed for
+    /// `NodeId`, see [`Cutoff`].
+    fn is_cut_off(&self, id: &NodeId) -> bool {
+        self.shared
+            .get(id)
+            .is_some_and(|shared| shared.cutoff.is_closed())
+    }
+
+    fn close_all(&mut self) {
+        self.channels.clear();
+        self.limiters.clear();
+        #[cfg(feature = "metrics")]
+        self.counters.clear();
+        self.backpressure.clear();
+        self.shared.clear();
+    }
+
+    #[cfg(feature = "metrics")]
+    fn messages_sent(&self, id: &NodeId) -> u64 {
+        self.counters
+            .get(id)
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    /// Refresh the backpressure of the receiver `NodeId` with the fill level of `channel`, and
+    /// returns whether the receiver is under backpressure.
+    fn pressed(&self, id: &NodeId, channel: &OutChannel) -> bool {
+        match (self.backpressure.get(id), channel.fill()) {
+            (Some(backpressure), Some((len, capacity))) => backpressure.update(len, capacity),
+            _ => false,
+        }
+    }
+
+    /// Wait until the receiver `NodeId` drains `channel` below its backpressure threshold.
+    async fn give_way(&self, id: &NodeId, channel: &OutChannel) {
+        let Some(backpressure) = self.backpressure.get(id) else {
+            return;
+        };
+        loop {
+            let mut drained = pin!(backpressure.drained());
+            drained.as_mut().enable();
+            if !self.pressed(id, channel) {
+                return;
+            }
+            drained.await;
+        }
+    }
+
+    /// Wait for the rate limiter of the channel to `NodeId`, if there is one.
+    async fn throttle(&self, id: &NodeId) {
+        if let Some(limiter) = self.limiters.get(id) {
+            limiter.acquire().await;
+        }
+    }
+
+    /// Blocking wait for the rate limiter of the channel to `NodeId`, if there is one.
+    fn blocking_throttle(&self, id: &NodeId) {
+        if let Some(limiter) = self.limiters.get(id) {
+            limiter.blocking_acquire();
+        }
+    }
+
+    /// Count a packet to send through `channel` as in-flight work, once for each of its
+    /// receivers, since each of them releases it when receiving it. It is counted before
+    /// sending, so that it is never received before being counted. Returns the count.
+    fn enter_in_flight(&self, channel: &OutChannel) -> usize {
+        let receivers = channel.receiver_count();
+        if let Some(in_flight) = &self.in_flight {
+            in_flight.add(receivers);
+        }
+        receivers
+    }
+
+    /// Increase the counter of `to` if a packet is sent to it, otherwise release the `entered`
+    /// in-flight packets. The attempt is reported to the hooks either way.
+    fn count(
+        &self,
+        to: &NodeId,
+        entered: usize,
+        result: Result<(), SendErr>,
+    ) -> Result<(), SendErr> {
+        self.hooks.on_send(to, result.is_ok());
+        match (&result, &self.in_flight) {
+            (Ok(_), _) => {
+                if let Some(shared) = self.shared.get(to) {
+                    shared.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                self.count_sent(to)
+            }
+            (Err(_), Some(in_flight)) => in_flight.sub(entered),
+            _ => (),
+        }
+        result
+    }
+
+    /// Count a packet sent to `to`, see [`OutChannels::messages_sent`].
+    #[cfg(feature = "metrics")]
+    fn count_sent(&self, to: &NodeId) {
+        if let Some(counter) = self.counters.get(to) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn count_sent(&self, _: &NodeId) {}
+
+    /// Account for the `content` sent once through the channel shared by the receivers `ids`,
+    /// like [`Senders::count`], with a result for each of them. The receivers the channel is
+    /// closed for, which come last, fail with [`SendErr::ClosedChannel`].
+    fn count_shared(
+        &self,
+        ids: &[NodeId],
+        entered: usize,
+        result: Result<(), SendErr>,
+        content: &Content,
+    ) -> Vec<Result<(), SendErr>> {
+        let result = self.count(&ids[0], entered, result);
+        let mut results = Vec::with_capacity(ids.len());
+        for id in &ids[1..] {
+            if self.is_cut_off(id) {
+                self.hooks.on_send(id, false);
+                results.push(Err(SendErr::ClosedChannel(content.clone())));
+                continue;
+            }
+            self.hooks.on_send(id, result.is_ok());
+            if result.is_ok() {
+                self.count_sent(id);
+            }
+            results.push(result.clone());
+        }
+        results.push(result);
+        results
+    }
+
+    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<OutChannel>>> {
+        self.channels.get(id).cloned()
+    }
+
+    fn insert(&mut self, node_id: NodeId, channel: Arc<Mutex<OutChannel>>) {
+        self.channels.insert(node_id, channel);
+        #[cfg(feature = "metrics")]
+        self.counters.insert(node_id, Arc::default());
+    }
+
+    fn receiver_ids(&self) -> Vec<NodeId> {
+        self.channels.keys().copied().collect()
     }
 }
 
@@ -85,11 +716,87 @@ pub enum OutChannel {
     Mpsc(mpsc::Sender<Content>),
     /// Sender of a `tokio::sync::broadcast` channel.
     Bcst(broadcast::Sender<Content>),
+    /// Sender of a `tokio::sync::oneshot` channel, which can send exactly one packet.
+    /// It is taken by the first send, so any further send fails with [`SendErr::ClosedChannel`].
+    Once(Option<oneshot::Sender<Content>>),
 }
 
 impl OutChannel {
+    /// Returns the kind of this channel.
+    pub(crate) fn channel_type(&self) -> ChannelType {
+        match self {
+            OutChannel::Mpsc(_) => ChannelType::Mpsc,
+            OutChannel::Bcst(_) => ChannelType::Broadcast,
+            OutChannel::Once(_) => ChannelType::Once,
+        }
+    }
+
+    /// Returns the number of packets this channel can buffer: 1 for a `Once` channel, and 0
+    /// for a `Bcst` channel, whose capacity is not exposed by tokio.
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            OutChannel::Mpsc(sender) => sender.max_capacity(),
+            OutChannel::Bcst(_) => 0,
+            OutChannel::Once(_) => 1,
+        }
+    }
+
+    /// Returns the number of receivers of a packet sent through this channel: the receivers
+    /// subscribed to a `Bcst` channel, and 1 for the other channels.
+    fn receiver_count(&self) -> usize {
+        match self {
+            OutChannel::Bcst(sender) => sender.receiver_count(),
+            _ => 1,
+        }
+    }
+
+    /// Drop the sender, leaving an exhausted `Once` channel, which fails every send.
+    fn close(&mut self) {
+        *self = OutChannel::Once(None);
+    }
+
+    /// Returns the number of buffered packets, unknown for a `Once` channel.
+    fn pending(&self) -> Option<usize> {
+        match self {
+            OutChannel::Mpsc(sender) => Some(sender.max_capacity() - sender.capacity()),
+            OutChannel::Bcst(sender) => Some(sender.len()),
+            OutChannel::Once(_) => None,
+        }
+    }
+
+    /// Returns the number of buffered packets and the capacity of an open `mpsc` channel.
+    fn fill(&self) -> Option<(usize, usize)> {
+        match self {
+            OutChannel::Mpsc(sender) if !sender.is_closed() => Some((
+                sender.max_capacity() - sender.capacity(),
+                sender.max_capacity(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Try to send on this channel without waiting.
+    fn try_send(&mut self, mut value: Content) -> Result<(), SendErr> {
+        value.mark_sent();
+        match self {
+            OutChannel::Mpsc(sender) => sender.try_send(value).map_err(|e| match e {
+                mpsc::error::TrySendError::Full(value) => SendErr::Backpressure(value),
+                mpsc::error::TrySendError::Closed(value) => SendErr::ClosedChannel(value),
+            }),
+            OutChannel::Bcst(sender) => match sender.send(value) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(SendErr::ClosedChannel(e.0)),
+            },
+            OutChannel::Once(sender) => match sender.take() {
+                Some(sender) => sender.send(value).map_err(SendErr::ClosedChannel),
+                None => Err(SendErr::ClosedChannel(value)),
+            },
+        }
+    }
+
     /// Perform a blocking send on this channel.
-    fn blocking_send(&self, value: Content) -> Result<(), SendErr> {
+    fn blocking_send(&mut self, mut value: Content) -> Result<(), SendErr> {
+        value.mark_sent();
         match self {
             OutChannel::Mpsc(sender) => match sender.blocking_send(value) {
                 Ok(_) => Ok(()),
@@ -99,11 +806,16 @@ impl OutChannel {
                 Ok(_) => Ok(()),
                 Err(e) => Err(SendErr::ClosedChannel(e.0)),
             },
+            OutChannel::Once(sender) => match sender.take() {
+                Some(sender) => sender.send(value).map_err(SendErr::ClosedChannel),
+                None => Err(SendErr::ClosedChannel(value)),
+            },
         }
     }
 
     /// Perform a asynchronous send on this channel.
-    async fn send(&self, value: Content) -> Result<(), SendErr> {
+    async fn send(&mut self, mut value: Content) -> Result<(), SendErr> {
+        value.mark_sent();
         match self {
             OutChannel::Mpsc(sender) => match sender.send(value).await {
                 Ok(_) => Ok(()),
@@ -113,6 +825,94 @@ impl OutChannel {
                 Ok(_) => Ok(()),
                 Err(e) => Err(SendErr::ClosedChannel(e.0)),
             },
+            OutChannel::Once(sender) => match sender.take() {
+                Some(sender) => sender.send(value).map_err(SendErr::ClosedChannel),
+                None => Err(SendErr::ClosedChannel(value)),
+            },
+        }
+    }
+}
+
+/// How [`OutChannels::send_balanced`] picks the successor of a packet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BalancePolicy {
+    /// Each successor in turn.
+    #[default]
+    RoundRobin,
+    /// The successor with the fewest packets waiting in its channel, each in turn if several
+    /// have as few. Only `mpsc` channels report their waiting packets, the other channels
+    /// count as empty.
+    LeastLoaded,
+}
+
+/// The state of [`OutChannels::send_balanced`].
+#[derive(Debug, Default)]
+pub(crate) struct Balancer {
+    policy: BalancePolicy,
+    /// Rotates the successors, so that they are picked in turn.
+    cursor: AtomicUsize,
+}
+
+/// Observers of the sends through the channels of a node.
+#[derive(Default, Clone)]
+pub(crate) struct SendHooks {
+    /// The last activity of the node, see [`OutChannels::set_activity`].
+    activity: Option<Arc<AtomicU64>>,
+    /// The sending node, and where to report its sends, see [`OutChannels::set_events`].
+    events: Option<(NodeId, Arc<broadcast::Sender<GraphEvent>>)>,
+    /// The sends of the node in progress, see [`OutChannels::set_pending_sends`].
+    pending_sends: Option<Arc<AtomicUsize>>,
+    /// The successors receiving packets compressed when serialized, see
+    /// [`OutChannels::set_compression`].
+    #[cfg(feature = "compression")]
+    compressed: HashSet<NodeId>,
+}
+
+impl SendHooks {
+    /// Prepare the `content` to send to `to`, marking it to be compressed when serialized if
+    /// the edge is compressed, see [`Content::with_compression`].
+    #[cfg(feature = "compression")]
+    fn encode(&self, to: &NodeId, content: Content) -> Content {
+        if self.compressed.contains(to) {
+            content.with_compression()
+        } else {
+            content
+        }
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn encode(&self, _: &NodeId, content: Content) -> Content {
+        content
+    }
+
+    /// Count a send in progress, until the returned guard is dropped.
+    fn sending(&self) -> Sending<'_> {
+        if let Some(pending) = &self.pending_sends {
+            pending.fetch_add(1, Ordering::Relaxed);
+        }
+        Sending(self.pending_sends.as_deref())
+    }
+
+    fn on_send(&self, to: &NodeId, sent: bool) {
+        if let Some(activity) = &self.activity {
+            touch(activity);
+        }
+        if let (true, Some((from, events))) = (sent, &self.events) {
+            let _ = events.send(GraphEvent::MessageSent {
+                from: *from,
+                to: *to,
+            });
+        }
+    }
+}
+
+/// A send in progress, see [`SendHooks::sending`].
+struct Sending<'a>(Option<&'a AtomicUsize>);
+
+impl Drop for Sending<'_> {
+    fn drop(&mut self) {
+        if let Some(pending) = self.0 {
+            pending.fetch_sub(1, Ordering::Relaxed);
         }
     }
 }
@@ -120,79 +920,598 @@ impl OutChannel {
 /// # Output Channel Error Types
 /// - NoSuchChannel: try to get a channel with an invalid `NodeId`.
 /// - ClosedChannel: the channel is closed alredy.
+/// - Backpressure: the receiver cannot take the packet now, see [`OutChannels::try_send_to`].
 ///
 /// In cases of getting errs of type `MpscError` and `BcstError`, the sender
 /// will find there are no active receivers left, so try to send messages is
 /// meaningless for now.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SendErr {
     NoSuchChannel,
     ClosedChannel(Content),
+    /// No group of receivers has the given name, see [`OutChannels::define_group`].
+    NoSuchGroup,
+    /// The receiver is under backpressure, the packet is handed back.
+    Backpressure(Content),
+}
+
+impl std::fmt::Display for SendErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SendErr::NoSuchChannel => write!(f, "no such output channel"),
+            SendErr::ClosedChannel(_) => write!(f, "output channel is closed"),
+            SendErr::NoSuchGroup => write!(f, "no such group of output channels"),
+            SendErr::Backpressure(_) => write!(f, "receiver is under backpressure"),
+        }
+    }
 }
 
+impl std::error::Error for SendErr {}
+
+/// # Acknowledged Send Error Types
+/// - Send: the packet cannot be sent, see [`SendErr`].
+/// - Dropped: the packet is sent, but dropped before the receiver receives it.
+///
+/// See [`OutChannels::send_with_ack`].
+#[derive(Debug)]
+pub enum AckError {
+    Send(SendErr),
+    Dropped,
+}
+
+impl std::fmt::Display for AckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AckError::Send(e) => write!(f, "{}", e),
+            AckError::Dropped => write!(f, "packet dropped before being received"),
+        }
+    }
+}
+
+impl std::error::Error for AckError {}
+
 /// # Typed Output Channels
 /// A hash-table mapping [`NodeId`] to [`OutChannel`]. This provides type-safe channel communication
 /// between nodes.
+///
+/// It shares the channels of the [`OutChannels`] it is made from, along with their settings:
+/// the packets are rate limited, compressed and counted the same way.
 #[derive(Default)]
-pub struct TypedOutChannels<T: Send + Sync + 'static>(
-    pub(crate) HashMap<NodeId, Arc<Mutex<OutChannel>>>,
+pub struct TypedOutChannels<T: Send + Sync + 'static> {
+    pub(crate) senders: Senders,
     // maker for type T
-    pub(crate) PhantomData<T>,
-);
+    pub(crate) _marker: PhantomData<T>,
+}
 
 impl<T: Send + Sync + 'static> TypedOutChannels<T> {
+    /// Typed view of the channels `senders`.
+    pub(crate) fn new(senders: Senders) -> Self {
+        Self {
+            senders,
+            _marker: PhantomData,
+        }
+    }
+
     /// Perform a blocking send on the outcoming channel from `NodeId`.
     pub fn blocking_send_to(&self, id: &NodeId, content: T) -> Result<(), SendErr> {
-        match self.get(id) {
-            Some(channel) => channel.blocking_lock().blocking_send(Content::new(content)),
-            None => Err(SendErr::NoSuchChannel),
-        }
+        self.senders.blocking_send(id, Content::new(content))
     }
 
     /// Perform a asynchronous send on the outcoming channel from `NodeId`.
     pub async fn send_to(&self, id: &NodeId, content: T) -> Result<(), SendErr> {
-        match self.get(id) {
-            Some(channel) => channel.lock().await.send(Content::new(content)).await,
-            None => Err(SendErr::NoSuchChannel),
-        }
+        self.senders.send(id, Content::new(content)).await
     }
 
     /// Broadcasts the `content` to all the [`TypedOutChannel`]s asynchronously.
     pub async fn broadcast(&self, content: T) -> Vec<Result<(), SendErr>> {
-        let content = Content::new(content);
-        let futures = self
-            .0
-            .iter()
-            .map(|(_, c)| async { c.lock().await.send(content.clone()).await });
-
-        join_all(futures).await
+        self.senders.broadcast(Content::new(content)).await
     }
 
     /// Blocking broadcasts the `content` to all the [`TypedOutChannel`]s.
     pub fn blocking_broadcast(&self, content: T) -> Vec<Result<(), SendErr>> {
-        let content = Content::new(content);
-        self.0
-            .iter()
-            .map(|(_, c)| c.blocking_lock().blocking_send(content.clone()))
-            .collect()
+        self.senders.blocking_broadcast(Content::new(content))
     }
 
     /// Close the channel by the given `NodeId`, and remove the channel in this map.
     pub fn close(&mut self, id: &NodeId) {
-        if let Some(_) = self.get(id) {
-            self.0.remove(id);
-        }
+        self.senders.close(id);
     }
 
-    fn get(&self, id: &NodeId) -> Option<Arc<Mutex<OutChannel>>> {
-        match self.0.get(id) {
-            Some(c) => Some(c.clone()),
-            None => None,
-        }
+    /// Close the channel to `NodeId`, keeping the other channels open, see
+    /// [`OutChannels::close_to`].
+    pub async fn close_to(&self, id: &NodeId) {
+        self.senders.close_to(id).await;
+    }
+
+    /// Returns the number of packets successfully sent through the channel to `NodeId`
+    /// so far, or 0 if there is no such channel.
+    #[cfg(feature = "metrics")]
+    pub fn messages_sent(&self, id: &NodeId) -> u64 {
+        self.senders.messages_sent(id)
     }
 
     /// Returns a list of all available receiver node IDs.
     pub fn get_receiver_ids(&self) -> Vec<NodeId> {
-        self.0.keys().copied().collect()
+        self.senders.receiver_ids()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{connection::in_channel::InChannel, InChannels, RecvErr};
+
+    /// Test for sending to a group of receivers.
+    ///
+    /// Of a group of 3 receivers, the second one is closed: the first one receives the packet,
+    /// the error is returned, and the third one is not attempted. An undefined group or a
+    /// member without channel sends nothing.
+    #[test]
+    fn test_send_to_group() {
+        let mut out_channels = OutChannels::default();
+        let mut receivers = vec![];
+        for i in 1..=3 {
+            let (tx, rx) = mpsc::channel::<Content>(8);
+            out_channels.insert(NodeId(i), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+            receivers.push(rx);
+        }
+        receivers[1].close();
+        out_channels.define_group("all", &[NodeId(1), NodeId(2), NodeId(3)]);
+        out_channels.define_group("unknown", &[NodeId(1), NodeId(4)]);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let result = out_channels.send_to_group("all", Content::new(1)).await;
+            assert!(matches!(result, Err(SendErr::ClosedChannel(_))));
+            let result = out_channels.send_to_group("unknown", Content::new(2)).await;
+            assert!(matches!(result, Err(SendErr::NoSuchChannel)));
+            let result = out_channels.send_to_group("none", Content::new(3)).await;
+            assert!(matches!(result, Err(SendErr::NoSuchGroup)));
+        });
+
+        assert_eq!(receivers[0].try_recv().unwrap().get::<i32>(), Some(&1));
+        assert!(receivers[0].try_recv().is_err());
+        assert!(receivers[2].try_recv().is_err());
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(1)), 1);
+    }
+
+    /// Test for counting the packets buffered in the output channels.
+    ///
+    /// Send 3 packets through a `mpsc` and a `broadcast` channel, and receive one from each:
+    /// 2 packets are pending in both, and the count of a `oneshot` channel is unknown.
+    #[test]
+    fn test_pending_count() {
+        let mut out_channels = OutChannels::default();
+        let (mpsc_tx, mut mpsc_rx) = mpsc::channel::<Content>(8);
+        let (bcst_tx, mut bcst_rx) = broadcast::channel::<Content>(8);
+        let (once_tx, _once_rx) = oneshot::channel::<Content>();
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(mpsc_tx))));
+        out_channels.insert(NodeId(2), Arc::new(Mutex::new(OutChannel::Bcst(bcst_tx))));
+        out_channels.insert(
+            NodeId(3),
+            Arc::new(Mutex::new(OutChannel::Once(Some(once_tx)))),
+        );
+        assert_eq!(out_channels.pending_count(&NodeId(1)), Some(0));
+
+        for i in 0..3 {
+            out_channels
+                .blocking_send_to(&NodeId(1), Content::new(i))
+                .unwrap();
+            out_channels
+                .blocking_send_to(&NodeId(2), Content::new(i))
+                .unwrap();
+        }
+        mpsc_rx.try_recv().unwrap();
+        bcst_rx.try_recv().unwrap();
+        assert_eq!(out_channels.pending_count(&NodeId(1)), Some(2));
+        assert_eq!(out_channels.pending_count(&NodeId(2)), Some(2));
+        assert_eq!(out_channels.pending_count(&NodeId(3)), None);
+        assert_eq!(out_channels.pending_count(&NodeId(4)), None);
+    }
+
+    /// Test for sending a batch of packets.
+    ///
+    /// The batch is received in order and counted, while a batch through a used `oneshot`
+    /// channel fails at its first packet.
+    #[test]
+    fn test_send_batch() {
+        let mut out_channels = OutChannels::default();
+        let (mpsc_tx, mut mpsc_rx) = mpsc::channel::<Content>(8);
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(mpsc_tx))));
+        out_channels.insert(NodeId(2), Arc::new(Mutex::new(OutChannel::Once(None))));
+        let batch = |n: i32| (0..n).map(Content::new).collect::<Vec<_>>();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime
+            .block_on(out_channels.send_batch(&NodeId(1), batch(5)))
+            .unwrap();
+        let received: Vec<i32> = (0..5)
+            .map(|_| *mpsc_rx.try_recv().unwrap().get::<i32>().unwrap())
+            .collect();
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(1)), 5);
+
+        assert!(matches!(
+            runtime.block_on(out_channels.send_batch(&NodeId(2), batch(2))),
+            Err(SendErr::ClosedChannel(_))
+        ));
+        assert!(matches!(
+            runtime.block_on(out_channels.send_batch(&NodeId(3), batch(2))),
+            Err(SendErr::NoSuchChannel)
+        ));
+    }
+
+    /// Test for sending the metadata of a packet along with it.
+    ///
+    /// Step 1: the packet is sent with its metadata, and received with one hop counted.
+    ///
+    /// Step 2: the packet is forwarded with the same metadata, and received with two hops,
+    /// while a packet sent without metadata is received without.
+    #[test]
+    fn test_send_with_metadata() {
+        let mut out_channels = OutChannels::default();
+        let mut in_channels = InChannels::default();
+        let (tx, rx) = mpsc::channel::<Content>(8);
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        in_channels.insert(NodeId(0), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let meta = MessageMeta::new(7);
+            out_channels
+                .send_with_metadata(&NodeId(1), Content::new(1), meta)
+                .await
+                .unwrap();
+            let (content, meta) = in_channels.recv_with_metadata(&NodeId(0)).await.unwrap();
+            let meta = meta.unwrap();
+            assert_eq!(content.get::<i32>(), Some(&1));
+            assert_eq!((meta.trace_id, meta.hop_count), (7, 1));
+
+            out_channels
+                .send_with_metadata(&NodeId(1), content, meta)
+                .await
+                .unwrap();
+            let (_, forwarded) = in_channels.recv_with_metadata(&NodeId(0)).await.unwrap();
+            let forwarded = forwarded.unwrap();
+            assert_eq!((forwarded.trace_id, forwarded.hop_count), (7, 2));
+            assert_eq!(forwarded.timestamp, meta.timestamp);
+
+            out_channels
+                .send_to(&NodeId(1), Content::new(2))
+                .await
+                .unwrap();
+            let (_, meta) = in_channels.recv_with_metadata(&NodeId(0)).await.unwrap();
+            assert!(meta.is_none());
+        });
+    }
+
+    /// Test for compressing the packets sent to a successor when serialized.
+    ///
+    /// Step 1: send a byte buffer, a string and an integer through a compressed edge, and verify
+    /// they are received unchanged.
+    ///
+    /// Step 2: verify the buffer and the string are serialized compressed, and deserialized into
+    /// the original values, while the integer is serialized as usual.
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compression() {
+        let mut out_channels = OutChannels::default();
+        let (tx, rx) = mpsc::channel::<Content>(8);
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        out_channels.set_compression(NodeId(1));
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(0), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+
+        let bytes = Content::from_bytes(vec![7u8; 4096]);
+        let text = Content::new("dagrs ".repeat(512));
+        for content in [&bytes, &text, &Content::new(1)] {
+            out_channels
+                .blocking_send_to(&NodeId(1), content.clone())
+                .unwrap();
+        }
+        let received: Vec<Content> = (0..3)
+            .map(|_| in_channels.blocking_recv_from(&NodeId(0)).unwrap())
+            .collect();
+        assert!(received[0].ptr_eq(&bytes));
+        assert!(received[1].ptr_eq(&text));
+        assert_eq!(received[2].get::<i32>(), Some(&1));
+
+        for (received, sent) in received[..2].iter().zip([&bytes, &text]) {
+            let json = serde_json::to_string(received).unwrap();
+            assert!(json.len() < serde_json::to_string(sent).unwrap().len() / 10);
+            let content: Content = serde_json::from_str(&json).unwrap();
+            assert_eq!(content.get::<Vec<u8>>(), sent.get::<Vec<u8>>());
+            assert_eq!(content.get::<String>(), sent.get::<String>());
+        }
+        assert_eq!(serde_json::to_string(&received[2]).unwrap(), r#"{"I32":1}"#);
+    }
+
+    /// Test for closing a single output channel.
+    ///
+    /// After closing the channel to the first receiver, it receives the packet sent before and
+    /// sees the channel closed, sending to it fails, and the second receiver still receives.
+    #[test]
+    fn test_close_to() {
+        let mut out_channels = OutChannels::default();
+        let mut receivers = vec![];
+        for i in 1..=2 {
+            let (tx, rx) = mpsc::channel::<Content>(8);
+            out_channels.insert(NodeId(i), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+            receivers.push(rx);
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            out_channels.broadcast(Content::new(1)).await;
+            out_channels.close_to(&NodeId(1)).await;
+            let result = out_channels.send_to(&NodeId(1), Content::new(2)).await;
+            assert!(matches!(result, Err(SendErr::ClosedChannel(_))));
+            assert!(out_channels
+                .send_to(&NodeId(2), Content::new(2))
+                .await
+                .is_ok());
+        });
+
+        assert_eq!(receivers[0].blocking_recv().unwrap().get::<i32>(), Some(&1));
+        assert!(receivers[0].blocking_recv().is_none());
+        assert_eq!(receivers[1].try_recv().unwrap().get::<i32>(), Some(&1));
+        assert_eq!(receivers[1].try_recv().unwrap().get::<i32>(), Some(&2));
+    }
+
+    /// Test for distributing packets among identical successors.
+    ///
+    /// Step 1: send 9 packets round-robin to 3 receivers, and verify each receives 3.
+    ///
+    /// Step 2: with the least-loaded policy, verify the receivers not holding the 3 packets
+    /// left in the first channel are picked.
+    #[test]
+    fn test_send_balanced() {
+        let mut out_channels = OutChannels::default();
+        let mut receivers = vec![];
+        for i in 1..=3 {
+            let (tx, rx) = mpsc::channel::<Content>(8);
+            out_channels.insert(NodeId(i), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+            receivers.push(rx);
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            for i in 0..9 {
+                out_channels.send_balanced(Content::new(i)).await.unwrap();
+            }
+        });
+        for (i, rx) in receivers.iter_mut().enumerate() {
+            let mut received = vec![];
+            while let Ok(content) = rx.try_recv() {
+                received.push(*content.get::<i32>().unwrap());
+            }
+            let i = i as i32;
+            assert_eq!(received, vec![i, i + 3, i + 6]);
+        }
+
+        out_channels.set_balance_policy(BalancePolicy::LeastLoaded);
+        runtime.block_on(async {
+            for i in 0..3 {
+                out_channels
+                    .send_to(&NodeId(1), Content::new(i))
+                    .await
+                    .unwrap();
+            }
+            for _ in 0..4 {
+                let id = out_channels.send_balanced(Content::new(0)).await.unwrap();
+                assert_ne!(id, NodeId(1));
+            }
+        });
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(2)), 5);
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(3)), 5);
+        let result = runtime.block_on(OutChannels::default().send_balanced(Content::new(0)));
+        assert!(matches!(result, Err(SendErr::NoSuchChannel)));
+    }
+
+    /// Test for the backpressure of a receiver.
+    ///
+    /// With the default threshold of 80%, a channel of capacity 10 takes 9 packets, then the
+    /// receiver signals backpressure and the 10th packet is handed back by `try_send_to`. Once
+    /// the receiver drains the channel, the signal is cleared and the packet can be sent.
+    #[test]
+    fn test_backpressure() {
+        let in_channels = InChannels::default();
+        let signal = in_channels.backpressure_signal();
+        let mut out_channels = OutChannels::default();
+        let (tx, mut rx) = mpsc::channel::<Content>(10);
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        out_channels.set_backpressure(NodeId(1), in_channels.backpressure());
+
+        for i in 0..9 {
+            assert!(out_channels
+                .try_send_to(&NodeId(1), Content::new(i))
+                .is_ok());
+        }
+        assert!(!signal.load(Ordering::Relaxed));
+        let result = out_channels.try_send_to(&NodeId(1), Content::new(9));
+        let Err(SendErr::Backpressure(content)) = result else {
+            panic!("expected backpressure");
+        };
+        assert!(signal.load(Ordering::Relaxed));
+
+        for _ in 0..5 {
+            rx.try_recv().unwrap();
+        }
+        assert!(out_channels.try_send_to(&NodeId(1), content).is_ok());
+        assert!(!signal.load(Ordering::Relaxed));
+        assert!(out_channels
+            .blocking_send_to(&NodeId(1), Content::new(10))
+            .is_ok());
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(1)), 11);
+    }
+
+    /// Test for the backpressure of a receiver with several senders.
+    ///
+    /// Step 1: A fills its channel to the receiver above the threshold, then B sends through an
+    /// empty channel: the signal stays set, since the channel from A is still nearly full.
+    ///
+    /// Step 2: a further send from A waits until the receiver drains the channel from A below
+    /// the threshold, and the signal is cleared once it is.
+    #[test]
+    fn test_backpressure_multiple_senders() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut in_channels = InChannels::default();
+        let signal = in_channels.backpressure_signal();
+        let (a, b, receiver) = (NodeId(1), NodeId(2), NodeId(3));
+        let mut senders = vec![];
+        for sender in [a, b] {
+            let (tx, rx) = mpsc::channel::<Content>(10);
+            in_channels.insert(sender, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+            let mut out_channels = OutChannels::default();
+            out_channels.insert(receiver, Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+            out_channels.set_backpressure(receiver, in_channels.backpressure());
+            senders.push(out_channels);
+        }
+        let (out_b, out_a) = (senders.pop().unwrap(), senders.pop().unwrap());
+
+        for i in 0..9 {
+            out_a.blocking_send_to(&receiver, Content::new(i)).unwrap();
+        }
+        let Err(SendErr::Backpressure(content)) = out_a.try_send_to(&receiver, Content::new(9))
+        else {
+            panic!("expected backpressure");
+        };
+        assert!(signal.load(Ordering::Relaxed));
+        out_b.blocking_send_to(&receiver, Content::new(0)).unwrap();
+        assert!(signal.load(Ordering::Relaxed));
+
+        let sending = runtime.spawn(async move { out_a.send_to(&receiver, content).await });
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!sending.is_finished());
+        let content = in_channels.blocking_recv_from(&a).unwrap();
+        assert_eq!(content.get::<i32>(), Some(&0));
+        runtime.block_on(sending).unwrap().unwrap();
+        assert!(!signal.load(Ordering::Relaxed));
+    }
+
+    /// Test for sending through the typed view of the output channels.
+    ///
+    /// A packet sent through the typed channels is counted by the untyped ones, and reported
+    /// to the events, since both share the same channels and settings.
+    #[test]
+    fn test_typed_out_channels() {
+        let mut out_channels = OutChannels::default();
+        let (tx, mut rx) = mpsc::channel::<Content>(8);
+        out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        let events = Arc::new(broadcast::channel(8).0);
+        let mut event_rx = events.subscribe();
+        out_channels.set_events(NodeId(0), events);
+
+        let typed = TypedOutChannels::<i32>::new(out_channels.senders.clone());
+        typed.blocking_send_to(&NodeId(1), 7).unwrap();
+        assert_eq!(rx.try_recv().unwrap().get::<i32>(), Some(&7));
+        #[cfg(feature = "metrics")]
+        assert_eq!(out_channels.messages_sent(&NodeId(1)), 1);
+        assert!(matches!(
+            event_rx.try_recv(),
+            Ok(GraphEvent::MessageSent {
+                from: NodeId(0),
+                to: NodeId(1)
+            })
+        ));
+    }
+
+    /// Test for tracking the packets sent through a broadcast channel as in-flight work.
+    ///
+    /// A packet sent to two receivers is in flight until both receive it, and a receiver
+    /// lagging behind releases the packets it missed.
+    #[test]
+    fn test_broadcast_in_flight() {
+        let in_flight = Arc::new(InFlight::default());
+        let (tx, rx) = broadcast::channel::<Content>(2);
+        let mut out_channels = OutChannels::default();
+        out_channels.insert(
+            NodeId(1),
+            Arc::new(Mutex::new(OutChannel::Bcst(tx.clone()))),
+        );
+        out_channels.set_in_flight(in_flight.clone());
+        let mut receivers = vec![];
+        for rx in [rx, tx.subscribe()] {
+            let mut in_channels = InChannels::default();
+            in_channels.insert(NodeId(0), Arc::new(Mutex::new(InChannel::Bcst(rx))));
+            in_channels.set_in_flight(in_flight.clone());
+            receivers.push(in_channels);
+        }
+        let idle = |in_flight: &Arc<InFlight>| {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                tokio::time::timeout(Duration::from_millis(10), in_flight.wait_idle())
+                    .await
+                    .is_ok()
+            })
+        };
+
+        out_channels
+            .blocking_send_to(&NodeId(1), Content::new(1))
+            .unwrap();
+        receivers[0].blocking_recv_from(&NodeId(0)).unwrap();
+        assert!(!idle(&in_flight));
+        receivers[1].blocking_recv_from(&NodeId(0)).unwrap();
+        assert!(idle(&in_flight));
+
+        for i in 0..3 {
+            out_channels
+                .blocking_send_to(&NodeId(1), Content::new(i))
+                .unwrap();
+        }
+        receivers[0].close(&NodeId(0));
+        assert!(matches!(
+            receivers[1].blocking_recv_from(&NodeId(0)),
+            Err(RecvErr::Lagged(1))
+        ));
+        assert!(!idle(&in_flight));
+        for _ in 0..2 {
+            receivers[1].blocking_recv_from(&NodeId(0)).unwrap();
+        }
+        assert!(idle(&in_flight));
+    }
+
+    /// Test for sending with acknowledgement.
+    ///
+    /// The send waits until the packet is received. A packet dropped by closing the channel
+    /// before it is received fails with `AckError::Dropped`.
+    #[test]
+    fn test_send_with_ack() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (tx, rx) = mpsc::channel::<Content>(8);
+            let mut out_channels = OutChannels::default();
+            out_channels.insert(NodeId(1), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+            let out_channels = Arc::new(out_channels);
+            let mut in_channels = InChannels::default();
+            in_channels.insert(NodeId(0), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+
+            let sender = out_channels.clone();
+            let send =
+                tokio::spawn(
+                    async move { sender.send_with_ack(&NodeId(1), Content::new(1)).await },
+                );
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            assert!(!send.is_finished());
+            in_channels.recv_from(&NodeId(0)).await.unwrap();
+            assert!(send.await.unwrap().is_ok());
+
+            let sender = out_channels.clone();
+            let send =
+                tokio::spawn(
+                    async move { sender.send_with_ack(&NodeId(1), Content::new(2)).await },
+                );
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            assert!(!send.is_finished());
+            in_channels.close_async(&NodeId(0)).await;
+            assert!(matches!(send.await.unwrap(), Err(AckError::Dropped)));
+        });
     }
 }
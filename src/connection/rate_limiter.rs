@@ -0,0 +1,82 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// # Rate Limiter
+/// A token bucket limiting the number of packets sent through an edge per second.
+///
+/// The bucket holds at most one token, and refills at a rate of `per_sec` tokens per second.
+/// Every send takes a token, or waits until the next one is available. Therefore, a burst of
+/// sends is never let through at once, but smoothed to one packet every `1 / per_sec` second.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_sec: f64,
+    /// Available tokens, and the time they were last refilled.
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    /// Create a [`RateLimiter`] allowing `per_sec` packets per second.
+    ///
+    /// # Panics
+    /// Panics if `per_sec` is 0.
+    pub fn new(per_sec: u32) -> Self {
+        assert!(per_sec > 0, "rate limit must be positive");
+        Self {
+            per_sec: per_sec as f64,
+            bucket: Mutex::new((1.0, Instant::now())),
+        }
+    }
+
+    /// Wait asynchronously until a token is available, and take it.
+    pub(crate) async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Block the current thread until a token is available, and take it.
+    pub(crate) fn blocking_acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Take a token if available, otherwise return how long to wait for the next one.
+    pub(crate) fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, last) = &mut *bucket;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.per_sec).min(1.0);
+        *last = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - *tokens) / self.per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test for the rate of acquired tokens.
+    ///
+    /// Acquire 5 tokens at a rate of 20 per second: the first one is available immediately,
+    /// and each of the rest takes 50ms.
+    #[test]
+    fn test_rate_limit() {
+        let limiter = RateLimiter::new(20);
+        let start = Instant::now();
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            for _ in 0..5 {
+                limiter.acquire().await;
+            }
+        });
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+}
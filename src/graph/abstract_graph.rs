@@ -26,8 +26,8 @@ impl AbstractGraph {
 
     /// Adds a node to the abstract graph
     pub fn add_node(&mut self, node_id: NodeId) {
-        if !self.in_degree.contains_key(&node_id) {
-            self.in_degree.insert(node_id, 0);
+        if let std::collections::hash_map::Entry::Vacant(e) = self.in_degree.entry(node_id) {
+            e.insert(0);
             self.edges.insert(node_id, HashSet::new());
         }
     }
@@ -73,6 +73,36 @@ impl AbstractGraph {
             .insert(abstract_node_id, concrete_node_id);
     }
 
+    /// Removes a node and its edges from the abstract graph. A folded node is only removed from
+    /// its abstract node, whose edges are kept.
+    pub fn remove_node(&mut self, node_id: NodeId) {
+        if let Some(abstract_id) = self.folded_nodes.remove(&node_id) {
+            if let Some(concrete_ids) = self.unfold_abstract_nodes.get_mut(&abstract_id) {
+                concrete_ids.retain(|id| *id != node_id);
+            }
+            return;
+        }
+
+        for to in self.edges.remove(&node_id).unwrap_or_default() {
+            if let Some(degree) = self.in_degree.get_mut(&to) {
+                *degree -= 1;
+            }
+        }
+        for tos in self.edges.values_mut() {
+            tos.remove(&node_id);
+        }
+        self.in_degree.remove(&node_id);
+    }
+
+    /// Moves all the nodes and edges of `other` into this abstract graph.
+    pub fn merge(&mut self, other: AbstractGraph) {
+        self.in_degree.extend(other.in_degree);
+        self.edges.extend(other.edges);
+        self.folded_nodes.extend(other.folded_nodes);
+        self.unfold_abstract_nodes
+            .extend(other.unfold_abstract_nodes);
+    }
+
     /// Look up the concrete node IDs that an abstract node ID has been unfolded into.
     pub fn unfold_node(&self, abstract_node_id: NodeId) -> Option<&Vec<NodeId>> {
         self.unfold_abstract_nodes.get(&abstract_node_id)
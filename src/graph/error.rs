@@ -1,23 +1,236 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    node::{error::NodeError, node::NodeId},
+    utils::output::Output,
+};
+
 #[derive(Clone, Debug)]
 pub enum GraphError {
     GraphLoopDetected,
     GraphNotActive,
+    /// The graph has no node to run.
+    Empty,
+    /// Every node of the graph waits for the input of another node, so none can start.
+    NoSource,
     ExecutionFailed {
         node_name: String,
         node_id: usize,
         error: String,
+        /// The outputs of the nodes that succeeded in the same run, see
+        /// [`GraphError::partial_outputs`].
+        partial_outputs: Arc<HashMap<NodeId, Output>>,
     },
     PanicOccurred {
         node_name: String,
         node_id: usize,
+        /// The outputs of the nodes that succeeded in the same run, see
+        /// [`GraphError::partial_outputs`].
+        partial_outputs: Arc<HashMap<NodeId, Output>>,
+    },
+    WarmUpFailed {
+        node_name: String,
+        node_id: usize,
+        error: NodeError,
+    },
+    NodeFailed {
+        node_name: String,
+        node_id: usize,
+        error: NodeError,
+        /// The outputs of the nodes that succeeded in the same run, see
+        /// [`GraphError::partial_outputs`].
+        partial_outputs: Arc<HashMap<NodeId, Output>>,
     },
     MultipleErrors(Vec<GraphError>),
+    /// The node does not exist in the graph.
+    UnknownNode(usize),
+    /// A node with this name is in both graphs merged by
+    /// [`Graph::merge`](crate::Graph::merge).
+    DuplicateNode(String),
+    /// A node id is in both graphs combined by [`Graph::embed`](crate::Graph::embed) or
+    /// [`Graph::merge`](crate::Graph::merge), e.g. nodes created with ids of their own.
+    DuplicateNodeId(usize),
+    /// The two nodes are connected already.
+    DuplicateEdge {
+        from: usize,
+        to: usize,
+    },
+    /// The two nodes are not connected.
+    UnknownEdge {
+        from: usize,
+        to: usize,
+    },
+    /// A typed edge carries a different type than the other typed edges of its sender, see
+    /// [`Graph::add_typed_edge`](crate::Graph::add_typed_edge).
+    TypeMismatch {
+        from: usize,
+        to: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The graph cannot be built from a [`GraphSpec`](crate::GraphSpec), with the reason.
+    InvalidSpec(String),
+    /// The run did not complete in time, see
+    /// [`Graph::run_with_deadline`](crate::Graph::run_with_deadline).
+    DeadlineExceeded {
+        /// The nodes cancelled before completing, sorted by id.
+        unfinished: Vec<NodeId>,
+    },
     /// Contains the original error message when runtime creation failed
     RuntimeCreationFailed(String),
 }
 
 impl std::fmt::Display for GraphError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            GraphError::GraphLoopDetected => write!(f, "loop detected in the graph"),
+            GraphError::GraphNotActive => write!(f, "graph is not active"),
+            GraphError::Empty => write!(f, "graph has no node"),
+            GraphError::NoSource => write!(f, "every node in the graph waits for another node"),
+            GraphError::ExecutionFailed {
+                node_name,
+                node_id,
+                error,
+                ..
+            } => write!(
+                f,
+                "execution failed [name: {}, id: {}] - {}",
+                node_name, node_id, error
+            ),
+            GraphError::PanicOccurred {
+                node_name, node_id, ..
+            } => {
+                write!(f, "panic occurred [name: {}, id: {}]", node_name, node_id)
+            }
+            GraphError::WarmUpFailed {
+                node_name,
+                node_id,
+                error,
+            } => write!(
+                f,
+                "warm-up failed [name: {}, id: {}] - {}",
+                node_name, node_id, error
+            ),
+            GraphError::NodeFailed {
+                node_name,
+                node_id,
+                error,
+                ..
+            } => write!(
+                f,
+                "node failed [name: {}, id: {}] - {}",
+                node_name, node_id, error
+            ),
+            GraphError::MultipleErrors(errors) => {
+                write!(f, "{} errors occurred:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {}", error)?;
+                }
+                Ok(())
+            }
+            GraphError::UnknownNode(id) => write!(f, "node {} does not exist in the graph", id),
+            GraphError::DeadlineExceeded { unfinished } => {
+                let ids: Vec<usize> = unfinished.iter().map(|id| id.0).collect();
+                write!(f, "deadline exceeded, unfinished nodes: {:?}", ids)
+            }
+            GraphError::DuplicateNode(name) => {
+                write!(f, "node named {} is in both graphs", name)
+            }
+            GraphError::DuplicateNodeId(id) => write!(f, "node {} is in both graphs", id),
+            GraphError::DuplicateEdge { from, to } => {
+                write!(f, "duplicate edge from node {} to node {}", from, to)
+            }
+            GraphError::UnknownEdge { from, to } => {
+                write!(f, "no edge from node {} to node {}", from, to)
+            }
+            GraphError::TypeMismatch {
+                from,
+                to,
+                expected,
+                found,
+            } => write!(
+                f,
+                "edge from node {} to node {} carries {}, but the node sends {}",
+                from, to, found, expected
+            ),
+            GraphError::InvalidSpec(reason) => write!(f, "invalid graph spec: {}", reason),
+            GraphError::RuntimeCreationFailed(e) => write!(f, "failed to create runtime: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl GraphError {
+    /// Returns the outputs of the nodes that succeeded in the run that failed with this
+    /// error, to inspect the intermediate results without running the graph again.
+    ///
+    /// Only the errors of a node failing during the run carry them, it returns `None` for
+    /// the other errors. [`GraphError::MultipleErrors`] returns the outputs of its first error.
+    pub fn partial_outputs(&self) -> Option<&HashMap<NodeId, Output>> {
+        match self {
+            GraphError::ExecutionFailed {
+                partial_outputs, ..
+            }
+            | GraphError::PanicOccurred {
+                partial_outputs, ..
+            }
+            | GraphError::NodeFailed {
+                partial_outputs, ..
+            } => Some(partial_outputs.as_ref()),
+            GraphError::MultipleErrors(errors) => {
+                errors.iter().find_map(GraphError::partial_outputs)
+            }
+            _ => None,
+        }
+    }
+
+    /// Set the outputs carried by the errors of a failing node, see
+    /// [`GraphError::partial_outputs`]. The errors share a single copy of the outputs.
+    pub(crate) fn set_partial_outputs(&mut self, outputs: &Arc<HashMap<NodeId, Output>>) {
+        match self {
+            GraphError::ExecutionFailed {
+                partial_outputs, ..
+            }
+            | GraphError::PanicOccurred {
+                partial_outputs, ..
+            }
+            | GraphError::NodeFailed {
+                partial_outputs, ..
+            } => *partial_outputs = outputs.clone(),
+            GraphError::MultipleErrors(errors) => errors
+                .iter_mut()
+                .for_each(|error| error.set_partial_outputs(outputs)),
+            _ => {}
+        }
+    }
+}
+
+/// Potential problems of a graph found by [`Graph::validate`](crate::Graph::validate).
+///
+/// Unlike [`GraphError`], diagnostics do not prevent the graph from running, but usually
+/// indicate a mistake when building it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphDiagnostic {
+    /// A group of nodes not connected to the main part of the graph.
+    DisconnectedComponent(Vec<NodeId>),
+    /// A node receives packets of different types through its typed edges, see
+    /// [`Graph::add_typed_edge`](crate::Graph::add_typed_edge).
+    TypeMismatch {
+        node: NodeId,
+        types: Vec<&'static str>,
+    },
+}
+
+impl std::fmt::Display for GraphDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GraphDiagnostic::DisconnectedComponent(ids) => {
+                write!(f, "nodes {:?} are disconnected from the main graph", ids)
+            }
+            GraphDiagnostic::TypeMismatch { node, types } => {
+                write!(f, "node {:?} receives different types {:?}", node, types)
+            }
+        }
     }
 }
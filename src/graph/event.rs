@@ -0,0 +1,54 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    node::{error::NodeError, node::NodeId},
+    utils::output::Output,
+};
+
+/// The lifecycle events of a running graph, see [`Graph::event_stream`](crate::Graph).
+#[derive(Clone, Debug)]
+pub enum GraphEvent {
+    /// The graph starts running its nodes.
+    GraphStarted,
+    /// All the nodes of the graph completed, successfully or not.
+    GraphCompleted,
+    /// The node starts its run.
+    NodeStarted(NodeId),
+    /// The node completed successfully, after running for the given time.
+    NodeCompleted(NodeId, Duration),
+    /// The node failed. A panic or an error [`Output`](crate::Output) is reported as a
+    /// [`NodeError::Custom`].
+    NodeFailed(NodeId, NodeError),
+    /// A packet is sent through the edge from `from` to `to`.
+    MessageSent { from: NodeId, to: NodeId },
+    /// The receiver `to`, lagging behind the broadcast edge from `from`, skipped to the newest
+    /// packet, dropping `dropped` packets, see
+    /// [`Graph::set_fast_forward`](crate::Graph::set_fast_forward).
+    FastForwarded {
+        from: NodeId,
+        to: NodeId,
+        dropped: u64,
+    },
+}
+
+/// A callback registered with [`Graph::on_node_complete`](crate::Graph::on_node_complete).
+pub(crate) type CompleteCallback = Arc<dyn Fn(NodeId, &Output) + Send + Sync>;
+/// A callback registered with [`Graph::on_node_error`](crate::Graph::on_node_error).
+pub(crate) type ErrorCallback = Arc<dyn Fn(NodeId, &NodeError) + Send + Sync>;
+
+/// The callbacks called by the tasks of the nodes as they finish.
+#[derive(Clone, Default)]
+pub(crate) struct NodeCallbacks {
+    pub(crate) complete: Vec<CompleteCallback>,
+    pub(crate) error: Vec<ErrorCallback>,
+}
+
+impl NodeCallbacks {
+    pub(crate) fn node_completed(&self, id: NodeId, output: &Output) {
+        self.complete.iter().for_each(|f| f(id, output));
+    }
+
+    pub(crate) fn node_failed(&self, id: NodeId, error: &NodeError) {
+        self.error.iter().for_each(|f| f(id, error));
+    }
+}
@@ -1,25 +1,67 @@
 use std::hash::Hash;
 use std::sync::atomic::Ordering;
 use std::{
-    collections::{HashMap, HashSet},
-    panic::{self, AssertUnwindSafe},
-    sync::{atomic::AtomicBool, Arc},
+    any::{self, TypeId},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use crate::{
-    connection::{in_channel::InChannel, information_packet::Content, out_channel::OutChannel},
-    node::node::{Node, NodeId, NodeTable},
-    utils::{env::EnvVar, execstate::ExecState},
+    connection::{
+        cutoff::Cutoff,
+        in_channel::{ChannelType, InChannel},
+        information_packet::Content,
+        out_channel::{BalancePolicy, OutChannel},
+        rate_limiter::RateLimiter,
+    },
+    node::{
+        error::NodeError,
+        id_allocate::alloc_id,
+        node::{Node, NodeId, NodeName, NodeTable},
+        node_context::NodeContext,
+        registry::NodeRegistry,
+        task_scope::TaskScope,
+    },
+    utils::{
+        env::EnvVar,
+        execstate::ExecState,
+        in_flight::InFlight,
+        output::OutputError,
+        watchdog::{watch, WatchState},
+    },
     Output,
 };
 
+#[cfg(feature = "resource-aware")]
+use crate::{node::node::ResourceRequirements, utils::resource_pool::ResourcePool};
+use futures::{
+    future::{self, Either},
+    stream::{FuturesUnordered, Stream, StreamExt},
+    FutureExt,
+};
 use log::{debug, error, info};
-use tokio::sync::mpsc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, Semaphore};
+use tokio::sync::{Mutex, MutexGuard};
 use tokio::task;
+use tokio::time;
 
 use super::abstract_graph::AbstractGraph;
-use super::error::GraphError;
+use super::error::{GraphDiagnostic, GraphError};
+use super::event::{GraphEvent, NodeCallbacks};
+use super::input::GraphInput;
+use super::scheduler::{DefaultScheduler, Scheduler};
+use super::snapshot::Snapshot;
+use super::spec::GraphSpec;
+
+/// The result of running a [`Graph`].
+pub type GraphResult = Result<(), GraphError>;
 
 /// [`Graph`] is dagrs's main body.
 ///
@@ -37,7 +79,6 @@ use super::error::GraphError;
 ///   This ensures that the `Graph` cannot be executed again without resetting its state.
 ///
 /// The [`Graph`] is designed to efficiently manage task execution with built-in fault tolerance and flexible scheduling.
-
 pub struct Graph {
     /// Define the Net struct that holds all nodes
     pub(crate) nodes: HashMap<NodeId, Arc<Mutex<dyn Node>>>,
@@ -59,6 +100,52 @@ pub struct Graph {
     pub(crate) blocks: Vec<HashSet<NodeId>>,
     /// Abstract representation of the graph structure, used for cycle detection
     pub(crate) abstract_graph: AbstractGraph,
+    /// Maps node IDs to the IDs of the nodes they are connected to.
+    pub(crate) edges: HashMap<NodeId, HashSet<NodeId>>,
+    /// Maximum execution time of specific nodes, overriding `default_node_timeout`.
+    pub(crate) node_timeouts: HashMap<NodeId, Duration>,
+    /// Maximum execution time of every node without a specific timeout. No limit if `None`.
+    pub(crate) default_node_timeout: Option<Duration>,
+    /// Counts the running nodes and the packets not yet received, see [`Graph::await_quiescence`].
+    pub(crate) in_flight: Arc<InFlight>,
+    /// The nodes completed in a previous run and their outputs, which are skipped in the next runs.
+    pub(crate) restored: HashMap<NodeId, Option<Content>>,
+    /// Maximum number of times a node can run by returning [`Output::Loop`].
+    pub(crate) max_loop_iterations: usize,
+    /// Number of packets dropped from a broadcast edge above which lagging is logged.
+    pub(crate) lag_warning_threshold: u64,
+    /// Number of consecutive lags after which a broadcast edge is fast-forwarded, see
+    /// [`Graph::set_fast_forward`].
+    pub(crate) fast_forwards: HashMap<(NodeId, NodeId), u32>,
+    /// The type of the packets carried by each typed edge, see [`Graph::add_typed_edge`].
+    pub(crate) edge_types: HashMap<(NodeId, NodeId), EdgeType>,
+    /// Reports the lifecycle events of the runs, see [`Graph::event_stream`].
+    pub(crate) events: Arc<broadcast::Sender<GraphEvent>>,
+    /// The edges added by [`Graph::add_feedback_edge`], which are not part of the DAG.
+    pub(crate) feedback_edges: HashSet<(NodeId, NodeId)>,
+    /// Time without activity after which a running node is restarted. No watchdog if `None`.
+    pub(crate) watchdog: Option<Duration>,
+    /// Maximum number of times the watchdog restarts a node in a run.
+    pub(crate) max_watchdog_restarts: usize,
+    /// The activity of the nodes watched by the watchdog.
+    pub(crate) watch_states: HashMap<NodeId, Arc<WatchState>>,
+    /// Decides the order in which the nodes of a block are started, see [`Graph::run_with`].
+    pub(crate) scheduler: Arc<dyn Scheduler>,
+    /// Time limit of the current run, see [`Graph::run_with_deadline`].
+    pub(crate) run_deadline: Option<Duration>,
+    /// Where to send the output of each node in the next run, see [`Graph::output_stream`].
+    pub(crate) output_senders: HashMap<NodeId, Vec<oneshot::Sender<Output>>>,
+    /// Called as the nodes finish, see [`Graph::on_node_complete`] and [`Graph::on_node_error`].
+    pub(crate) callbacks: NodeCallbacks,
+    /// The tags added to the nodes by [`Graph::tag_node`].
+    pub(crate) tags: HashMap<NodeId, BTreeSet<String>>,
+    /// The only nodes run in the current run, see [`Graph::run_tagged`]. All nodes if `None`.
+    pub(crate) run_only: Option<HashSet<NodeId>>,
+    /// Total resources of the running nodes. No limit if `None`.
+    #[cfg(feature = "resource-aware")]
+    pub(crate) resource_limits: Option<ResourceRequirements>,
+    /// Bounds the number of running nodes, see [`Graph::set_parallelism`]. No limit if `None`.
+    pub(crate) parallelism: Option<Arc<Semaphore>>,
 }
 
 impl Graph {
@@ -73,13 +160,41 @@ impl Graph {
             in_degree: HashMap::new(),
             blocks: vec![],
             abstract_graph: AbstractGraph::new(),
+            edges: HashMap::new(),
+            node_timeouts: HashMap::new(),
+            default_node_timeout: None,
+            in_flight: Arc::new(InFlight::default()),
+            restored: HashMap::new(),
+            max_loop_iterations: 1000,
+            lag_warning_threshold: 100,
+            fast_forwards: HashMap::new(),
+            edge_types: HashMap::new(),
+            events: Arc::new(broadcast::channel(1024).0),
+            feedback_edges: HashSet::new(),
+            watchdog: None,
+            max_watchdog_restarts: 3,
+            watch_states: HashMap::new(),
+            scheduler: Arc::new(DefaultScheduler),
+            run_deadline: None,
+            output_senders: HashMap::new(),
+            callbacks: NodeCallbacks::default(),
+            tags: HashMap::new(),
+            run_only: None,
+            #[cfg(feature = "resource-aware")]
+            resource_limits: None,
+            parallelism: None,
         }
     }
 
     /// Reset the graph state but keep the nodes.
     pub fn reset(&mut self) {
-        self.execute_states = HashMap::new();
+        self.reset_runs();
         self.env = Arc::new(EnvVar::new(NodeTable::default()));
+    }
+
+    /// Reset the state of the last run, keeping the environment.
+    fn reset_runs(&mut self) {
+        self.execute_states = HashMap::new();
         self.is_active = Arc::new(AtomicBool::new(true));
         self.blocks.clear();
     }
@@ -107,7 +222,7 @@ impl Graph {
         } else {
             let id = node.id();
             let node = Arc::new(Mutex::new(node));
-            self.node_count = self.node_count + 1;
+            self.node_count += 1;
             self.nodes.insert(id, node);
             self.in_degree.insert(id, 0);
             self.abstract_graph.add_node(id);
@@ -115,44 +230,633 @@ impl Graph {
             log::debug!("Add node {:?} to concrete & abstract graph", id);
         }
     }
-    /// Adds an edge between two nodes in the `Graph`.
-    /// If the outgoing port of the sending node is empty and the number of receiving nodes is > 1, use the broadcast channel
-    /// An MPSC channel is used if the outgoing port of the sending node is empty and the number of receiving nodes is equal to 1
-    /// If the outgoing port of the sending node is not empty, adding any number of receiving nodes will change all relevant channels to broadcast
-    pub fn add_edge(&mut self, from_id: NodeId, all_to_ids: Vec<NodeId>) {
+    /// Builds a graph from the declarative `spec`, creating each node with the factory
+    /// registered in `registry` for its type.
+    ///
+    /// The ids of the nodes are allocated in the order of the spec, and the environment of the
+    /// graph maps their names to their ids. Returns [`GraphError::InvalidSpec`] if a name is
+    /// duplicated or unknown, a type is not registered, or a factory returns a node with
+    /// another id than the allocated one.
+    pub fn build_from_spec(spec: &GraphSpec, registry: &NodeRegistry) -> Result<Graph, GraphError> {
+        let mut node_table = NodeTable::new();
+        for node in &spec.nodes {
+            if node_table.get(&node.name).is_some() {
+                return Err(GraphError::InvalidSpec(format!(
+                    "duplicate node name {}",
+                    node.name
+                )));
+            }
+            node_table.alloc_id_for(&node.name);
+        }
+        let id_of = |name: &str| {
+            node_table
+                .get(name)
+                .copied()
+                .ok_or_else(|| GraphError::InvalidSpec(format!("unknown node name {}", name)))
+        };
+        let edges = spec
+            .edges
+            .iter()
+            .map(|edge| Ok((id_of(&edge.from)?, id_of(&edge.to)?)))
+            .collect::<Result<Vec<_>, GraphError>>()?;
+        let ids = spec
+            .nodes
+            .iter()
+            .map(|node| id_of(&node.name))
+            .collect::<Result<Vec<_>, GraphError>>()?;
+
+        let mut graph = Graph::new();
+        graph.set_env(EnvVar::new(node_table));
+        for (node, id) in spec.nodes.iter().zip(ids) {
+            let created = registry
+                .create(&node.type_name, id, graph.env.clone())
+                .ok_or_else(|| {
+                    GraphError::InvalidSpec(format!("unknown node type {}", node.type_name))
+                })?;
+            if created.id() != id {
+                return Err(GraphError::InvalidSpec(format!(
+                    "node {} is created with id {} instead of {}",
+                    node.name,
+                    created.id().0,
+                    id.0
+                )));
+            }
+            graph.add_node(created);
+        }
+        for (from, to) in edges {
+            graph.connect(from, to)?;
+        }
+        Ok(graph)
+    }
+
+    /// Adds edges from node `from_id` to every node in `all_to_ids`.
+    ///
+    /// Each edge gets its own channel, see [`Graph::connect`] for details. Unlike `connect`,
+    /// edges that exist already are coalesced: they are skipped rather than reported as errors.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, before adding any edge.
+    pub fn add_edge(&mut self, from_id: NodeId, all_to_ids: Vec<NodeId>) -> Result<(), GraphError> {
         let to_ids = Self::remove_duplicates(all_to_ids);
-        let mut rx_map: HashMap<NodeId, mpsc::Receiver<Content>> = HashMap::new();
+        if let Some(id) = std::iter::once(&from_id)
+            .chain(&to_ids)
+            .find(|id| !self.node_exists(**id))
+        {
+            return Err(GraphError::UnknownNode(id.0));
+        }
+        for to_id in to_ids {
+            if !self.edge_exists(from_id, to_id) {
+                self.connect(from_id, to_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if there's an edge from node `from_id` to node `to_id`.
+    pub fn has_edge(&self, from_id: NodeId, to_id: NodeId) -> bool {
+        self.edges
+            .get(&from_id)
+            .is_some_and(|to_ids| to_ids.contains(&to_id))
+    }
+
+    /// Returns true if the node `id` is in the graph.
+    pub fn node_exists(&self, id: NodeId) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    /// Returns true if a node named `name` is in the graph.
+    pub fn node_exists_by_name(&self, name: &str) -> bool {
+        self.nodes
+            .values()
+            .any(|node| node.blocking_lock().name() == name)
+    }
+
+    /// Returns true if node `from_id` is connected to node `to_id`, by an edge or a feedback
+    /// edge, see [`Graph::add_feedback_edge`].
+    pub fn edge_exists(&self, from_id: NodeId, to_id: NodeId) -> bool {
+        self.has_edge(from_id, to_id) || self.feedback_edges.contains(&(from_id, to_id))
+    }
+
+    /// Tags the node `id` with `tag`, e.g. `"io"` or `"critical"`, to operate on a group of nodes
+    /// at once, see [`Graph::get_nodes_by_tag`].
+    pub fn tag_node(&mut self, id: NodeId, tag: &str) {
+        self.tags.entry(id).or_default().insert(tag.to_owned());
+    }
+
+    /// Returns the ids of the nodes tagged with `tag`, sorted: the ones tagged with
+    /// [`Graph::tag_node`], and the ones whose [`Node::describe`] reports the tag, e.g. a
+    /// [`DefaultNode`](crate::DefaultNode) built with tags.
+    pub fn get_nodes_by_tag(&self, tag: &str) -> Vec<NodeId> {
+        let mut ids: Vec<NodeId> = self
+            .nodes
+            .iter()
+            .filter(|(id, node)| {
+                self.tags.get(id).is_some_and(|tags| tags.contains(tag))
+                    || node
+                        .blocking_lock()
+                        .describe()
+                        .tags
+                        .iter()
+                        .any(|t| t == tag)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns the ids of the nodes wired to the input of node `id`, sorted, or an empty vector
+    /// if the node does not exist.
+    ///
+    /// They are read from the [`InChannels`](crate::InChannels) of the node, so they include
+    /// the feedback edges, and the synthetic sender of [`Graph::add_source_with_data`]. The
+    /// node is locked, so call it before or after running the graph rather than during the run.
+    pub fn inputs_of(&self, id: NodeId) -> Vec<NodeId> {
+        let Some(node) = self.nodes.get(&id) else {
+            return vec![];
+        };
+        let mut ids: Vec<NodeId> = node.blocking_lock().input_channels().get_sender_ids();
+        ids.sort();
+        ids
+    }
+
+    /// Returns the ids of the nodes wired to the output of node `id`, sorted, or an empty
+    /// vector if the node does not exist.
+    ///
+    /// They are read from the [`OutChannels`](crate::OutChannels) of the node, like
+    /// [`Graph::inputs_of`].
+    pub fn outputs_of(&self, id: NodeId) -> Vec<NodeId> {
+        let Some(node) = self.nodes.get(&id) else {
+            return vec![];
+        };
+        let mut ids: Vec<NodeId> = node.blocking_lock().output_channels().get_receiver_ids();
+        ids.sort();
+        ids
+    }
+
+    /// Connects node `from_id` to node `to_id` with a single edge.
+    ///
+    /// A new `MPSC` channel is created for the edge: the sender is inserted into the
+    /// [`OutChannels`](crate::OutChannels) of `from_id` under the key `to_id`, and the receiver
+    /// into the [`InChannels`](crate::InChannels) of `to_id` under the key `from_id`. See
+    /// [`Graph::connect_with`] for the other kinds of channels.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, or
+    /// [`GraphError::DuplicateEdge`] if the two nodes are connected already, so that an edge is
+    /// never wired twice by accident.
+    ///
+    /// # Example
+    /// Wire a linear chain `A -> B -> C`:
+    /// ```rust
+    /// use dagrs::{DefaultNode, Graph, Node, NodeName, NodeTable};
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let a = DefaultNode::new(NodeName::from("A"), &mut node_table);
+    /// let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+    /// let c = DefaultNode::new(NodeName::from("C"), &mut node_table);
+    /// let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+    ///
+    /// let mut graph = Graph::new();
+    /// graph.add_node(a);
+    /// graph.add_node(b);
+    /// graph.add_node(c);
+    /// graph.connect(a_id, b_id).unwrap();
+    /// graph.connect(b_id, c_id).unwrap();
+    /// assert!(graph.connect(a_id, b_id).is_err());
+    /// ```
+    pub fn connect(&mut self, from_id: NodeId, to_id: NodeId) -> Result<(), GraphError> {
+        let (tx, rx) = mpsc::channel::<Content>(32);
+        self.connect_channel(
+            from_id,
+            to_id,
+            Arc::new(Mutex::new(OutChannel::Mpsc(tx))),
+            InChannel::Mpsc(rx),
+        )
+    }
+
+    /// Connects node `from_id` to node `to_id` with a `oneshot` channel, for handing off
+    /// exactly one packet.
+    ///
+    /// The first send through this edge consumes the channel, and any further send fails with
+    /// [`SendErr::ClosedChannel`](crate::SendErr::ClosedChannel). The receiver yields the single
+    /// packet, then [`RecvErr::Closed`](crate::RecvErr::Closed).
+    ///
+    /// Returns [`GraphError::DuplicateEdge`] if the two nodes are connected already.
+    pub fn connect_once(&mut self, from_id: NodeId, to_id: NodeId) -> Result<(), GraphError> {
+        let (tx, rx) = oneshot::channel::<Content>();
+        self.connect_channel(
+            from_id,
+            to_id,
+            Arc::new(Mutex::new(OutChannel::Once(Some(tx)))),
+            InChannel::Once(rx),
+        )
+    }
+
+    /// Connects node `from_id` to node `to_id` with a channel of the given `kind`: like
+    /// [`Graph::connect`] for [`ChannelType::Mpsc`], [`Graph::connect_once`] for
+    /// [`ChannelType::Once`], and [`Graph::connect_broadcast`] with a single receiver and a
+    /// capacity of 32 packets for [`ChannelType::Broadcast`].
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, or
+    /// [`GraphError::DuplicateEdge`] if the two nodes are connected already.
+    pub fn connect_with(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        kind: ChannelType,
+    ) -> Result<(), GraphError> {
+        match kind {
+            ChannelType::Mpsc => self.connect(from_id, to_id),
+            ChannelType::Broadcast => self.connect_broadcast(from_id, &[to_id], 32),
+            ChannelType::Once => self.connect_once(from_id, to_id),
+        }
+    }
 
-        // Update channels
+    /// Connects node `from_id` to all the nodes `to_ids` with a single `broadcast` channel of
+    /// `capacity` packets: each packet sent through it is received by every node of `to_ids`.
+    ///
+    /// The packet is sent once, whichever way the sender sends it: through
+    /// [`OutChannels::broadcast`](crate::OutChannels::broadcast), or to any of the receivers
+    /// with [`OutChannels::send_to`](crate::OutChannels::send_to). However,
+    /// [`OutChannels::close_to`](crate::OutChannels::close_to) closes the channel for the given
+    /// receiver only, while the others keep receiving through it. A send never waits for the receivers: a receiver lagging more than
+    /// `capacity` packets behind misses the oldest ones, and gets a
+    /// [`RecvErr::Lagged`](crate::RecvErr::Lagged) with their number instead, see
+    /// [`Graph::set_lag_warning_threshold`] and [`Graph::set_fast_forward`].
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, or
+    /// [`GraphError::DuplicateEdge`] if `from_id` is connected to one of `to_ids` already, in
+    /// which case no edge is added.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn connect_broadcast(
+        &mut self,
+        from_id: NodeId,
+        to_ids: &[NodeId],
+        capacity: usize,
+    ) -> Result<(), GraphError> {
+        assert!(capacity > 0, "capacity must be positive");
+        if let Some(id) = std::iter::once(&from_id)
+            .chain(to_ids)
+            .find(|id| !self.node_exists(**id))
+        {
+            return Err(GraphError::UnknownNode(id.0));
+        }
+        let mut targets = HashSet::new();
+        if let Some(to_id) = to_ids
+            .iter()
+            .find(|to_id| self.edge_exists(from_id, **to_id) || !targets.insert(**to_id))
+        {
+            return Err(GraphError::DuplicateEdge {
+                from: from_id.0,
+                to: to_id.0,
+            });
+        }
+        let (tx, _) = broadcast::channel::<Content>(capacity);
+        let rxs: Vec<_> = to_ids.iter().map(|_| tx.subscribe()).collect();
+        let tx = Arc::new(Mutex::new(OutChannel::Bcst(tx)));
+        for (to_id, rx) in to_ids.iter().zip(rxs) {
+            self.connect_channel(from_id, *to_id, tx.clone(), InChannel::Bcst(rx))?;
+        }
+        // Let the sender close the shared channel for a single receiver.
+        if to_ids.len() > 1 {
+            let sent = Arc::new(AtomicU64::new(0));
+            for to_id in to_ids {
+                let cutoff = Arc::new(Cutoff::default());
+                self.nodes[to_id]
+                    .blocking_lock()
+                    .input_channels()
+                    .set_cutoff(&from_id, cutoff.clone());
+                self.nodes[&from_id]
+                    .blocking_lock()
+                    .output_channels()
+                    .set_cutoff(*to_id, sent.clone(), cutoff);
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects node `from_id` to node `to_id` like [`Graph::connect`], with a channel of
+    /// `capacity` packets, and records that the edge carries packets of type `T`.
+    ///
+    /// The typed edges of a node all carry the same type, since a node sends a single type of
+    /// packets. Returns [`GraphError::TypeMismatch`] if `from_id` has a typed edge of another
+    /// type already, or [`GraphError::DuplicateEdge`] if the two nodes are connected already.
+    /// A node receiving different types through its typed edges is reported by
+    /// [`Graph::validate`].
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn add_typed_edge<T: Send + Sync + 'static>(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        capacity: usize,
+    ) -> Result<(), GraphError> {
+        let edge_type = EdgeType::of::<T>();
+        if let Some(((_, to), other)) = self
+            .edge_types
+            .iter()
+            .find(|((from, _), other)| *from == from_id && other.type_id != edge_type.type_id)
+        {
+            return Err(GraphError::TypeMismatch {
+                from: from_id.0,
+                to: to.0,
+                expected: other.type_name,
+                found: edge_type.type_name,
+            });
+        }
+        let (tx, rx) = mpsc::channel::<Content>(capacity);
+        self.connect_channel(
+            from_id,
+            to_id,
+            Arc::new(Mutex::new(OutChannel::Mpsc(tx))),
+            InChannel::Mpsc(rx),
+        )?;
+        self.edge_types.insert((from_id, to_id), edge_type);
+        Ok(())
+    }
+
+    /// Connects node `from_id` back to node `to_id` with a feedback edge, going backward in
+    /// the topological order, for iterative algorithms.
+    ///
+    /// The edge is a `mpsc` channel of capacity 1, preloaded with `initial_value`, so that
+    /// `to_id` can receive its first packet from `from_id` without waiting for it to run. A
+    /// feedback edge is not part of the DAG: it is never reported as a loop, and it does not
+    /// order the nodes. The channel stays open until `from_id` is removed, so the nodes must
+    /// agree on when to stop iterating, e.g. after a number of packets.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, or
+    /// [`GraphError::DuplicateEdge`] if `from_id` is connected to `to_id` already.
+    pub fn add_feedback_edge(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        mut initial_value: Content,
+    ) -> Result<(), GraphError> {
+        if let Some(id) = [from_id, to_id]
+            .into_iter()
+            .find(|id| !self.node_exists(*id))
+        {
+            return Err(GraphError::UnknownNode(id.0));
+        }
+        if self.edge_exists(from_id, to_id) {
+            return Err(GraphError::DuplicateEdge {
+                from: from_id.0,
+                to: to_id.0,
+            });
+        }
+
+        let (tx, rx) = mpsc::channel::<Content>(1);
+        initial_value.mark_sent();
+        tx.try_send(initial_value).unwrap();
+        self.in_flight.add(1);
+        let backpressure = {
+            let mut to_node = self.nodes[&to_id].blocking_lock();
+            let in_channels = to_node.input_channels();
+            in_channels.insert(from_id, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+            in_channels.backpressure()
+        };
+        let mut from_node = self.nodes[&from_id].blocking_lock();
+        let out_channels = from_node.output_channels();
+        out_channels.insert(to_id, Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+        out_channels.set_backpressure(to_id, backpressure);
+        drop(from_node);
+        self.feedback_edges.insert((from_id, to_id));
+        Ok(())
+    }
+
+    /// Adds `node` to the graph with its input preloaded with `data`, and returns its id.
+    ///
+    /// The packets are buffered in a `mpsc` channel from a synthetic sender, which is not a node
+    /// of the graph and is closed already: the node receives them in order as if an upstream
+    /// node had sent them, then sees the channel closed. This replaces a dedicated node seeding
+    /// the pipeline. The channel is consumed by the first run, like a feedback edge.
+    ///
+    /// The id of the synthetic sender is the only key of the input channels of `node` that is
+    /// not the id of a node, so `node` can tell the seeded packets apart with
+    /// [`InChannels::recv_any`](crate::InChannels::recv_any). A loop node, which is expanded
+    /// into its subgraph, is added without data.
+    pub fn add_source_with_data(
+        &mut self,
+        node: impl Node + 'static,
+        data: Vec<Content>,
+    ) -> NodeId {
+        let id = node.id();
+        self.add_node(node);
+        let Some(node) = self.nodes.get(&id) else {
+            return id;
+        };
+
+        let (tx, rx) = mpsc::channel::<Content>(data.len().max(1));
+        let count = data.len();
+        for mut content in data {
+            content.mark_sent();
+            tx.try_send(content).unwrap();
+        }
+        self.in_flight.add(count);
+        node.blocking_lock()
+            .input_channels()
+            .insert(alloc_id(), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        id
+    }
+
+    /// Inserts the sender `tx` into the output channels of `from_id` and the receiver `rx` into
+    /// the input channels of `to_id`, unless a node does not exist or the two nodes are
+    /// connected already. The edges of a `broadcast` channel share the same `tx`.
+    fn connect_channel(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        tx: Arc<Mutex<OutChannel>>,
+        rx: InChannel,
+    ) -> Result<(), GraphError> {
+        if let Some(id) = [from_id, to_id]
+            .into_iter()
+            .find(|id| !self.node_exists(*id))
+        {
+            return Err(GraphError::UnknownNode(id.0));
+        }
+        if self.edge_exists(from_id, to_id) {
+            return Err(GraphError::DuplicateEdge {
+                from: from_id.0,
+                to: to_id.0,
+            });
+        }
+        let backpressure = self
+            .nodes
+            .get(&to_id)
+            .map(|to_node| to_node.blocking_lock().input_channels().backpressure());
         {
             let from_node_lock = self.nodes.get_mut(&from_id).unwrap();
             let mut from_node = from_node_lock.blocking_lock();
-            let from_channel = from_node.output_channels();
-
-            for to_id in &to_ids {
-                if !from_channel.0.contains_key(to_id) {
-                    let (tx, rx) = mpsc::channel::<Content>(32);
-                    from_channel.insert(*to_id, Arc::new(Mutex::new(OutChannel::Mpsc(tx.clone()))));
-                    rx_map.insert(*to_id, rx);
-                    self.in_degree
-                        .entry(*to_id)
-                        .and_modify(|e| *e += 1)
-                        .or_insert(0);
-
-                    // Update abstract graph
-                    self.abstract_graph.add_edge(from_id, *to_id);
-                }
+            let out_channels = from_node.output_channels();
+            out_channels.insert(to_id, tx);
+            if let Some(backpressure) = backpressure {
+                out_channels.set_backpressure(to_id, backpressure);
             }
         }
-        for to_id in &to_ids {
-            if let Some(to_node_lock) = self.nodes.get_mut(to_id) {
-                let mut to_node = to_node_lock.blocking_lock();
-                let to_channel = to_node.input_channels();
-                if let Some(rx) = rx_map.remove(&to_id) {
-                    to_channel.insert(from_id, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
-                }
+        self.in_degree
+            .entry(to_id)
+            .and_modify(|e| *e += 1)
+            .or_insert(0);
+        self.edges.entry(from_id).or_default().insert(to_id);
+
+        // Update abstract graph
+        self.abstract_graph.add_edge(from_id, to_id);
+
+        if let Some(to_node_lock) = self.nodes.get_mut(&to_id) {
+            let mut to_node = to_node_lock.blocking_lock();
+            to_node
+                .input_channels()
+                .insert(from_id, Arc::new(Mutex::new(rx)));
+        }
+        Ok(())
+    }
+
+    /// Connects node `from_id` to node `to_id` like [`Graph::connect`], and limits the rate of
+    /// packets sent through this edge to `per_sec` packets per second.
+    ///
+    /// The limit applies to this specific edge only. Sending to it waits until the
+    /// [`RateLimiter`] allows, while the other edges of `from_id` are not affected. A burst of
+    /// sends is smoothed rather than let through at once, see [`RateLimiter`] for details.
+    ///
+    /// Returns [`GraphError::DuplicateEdge`] if the two nodes are connected already.
+    ///
+    /// # Panics
+    /// Panics if `per_sec` is 0.
+    pub fn connect_rate_limited(
+        &mut self,
+        from_id: NodeId,
+        to_id: NodeId,
+        per_sec: u32,
+    ) -> Result<(), GraphError> {
+        let limiter = RateLimiter::new(per_sec);
+        self.connect(from_id, to_id)?;
+        let mut from_node = self.nodes[&from_id].blocking_lock();
+        from_node.output_channels().set_rate_limiter(to_id, limiter);
+        Ok(())
+    }
+
+    /// Connects node `from_id` to node `to_id` like [`Graph::connect`], compressing the packets
+    /// sent through the edge when they are serialized.
+    ///
+    /// The packets go through the channel unchanged, and are received marked with
+    /// [`Content::with_compression`], so that a `String` or a `Vec<u8>` is compressed with LZ4
+    /// once the receiver serializes it, e.g. to forward it to another process, and decompressed
+    /// when deserialized. In-process, the edge costs nothing more than [`Graph::connect`].
+    /// Across processes, it trades CPU time on both ends for fewer bytes to transfer, which pays
+    /// off for large, compressible payloads sent to many consumers, while small or already
+    /// compressed ones only cost CPU time.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if a node does not exist, or
+    /// [`GraphError::DuplicateEdge`] if the two nodes are connected already.
+    #[cfg(feature = "compression")]
+    pub fn connect_compressed(&mut self, from_id: NodeId, to_id: NodeId) -> Result<(), GraphError> {
+        self.connect(from_id, to_id)?;
+        let mut from_node = self.nodes[&from_id].blocking_lock();
+        from_node.output_channels().set_compression(to_id);
+        Ok(())
+    }
+
+    /// Embeds all the nodes and edges of graph `sub` into this graph as a single logical unit.
+    ///
+    /// The embedded nodes keep their ids, which are allocated globally, so they only collide with
+    /// the ones in this graph for nodes created with ids of their own, e.g. with
+    /// [`DefaultNode::with_id`](crate::DefaultNode::with_id). The node timeouts of `sub` are kept
+    /// as well, while its environment variables are discarded.
+    ///
+    /// Returns a [`SubgraphHandle`] exposing the boundary nodes of `sub`, so that the nodes of this
+    /// graph can be connected to them with [`Graph::connect`]. Returns
+    /// [`GraphError::DuplicateNodeId`] without embedding anything if a node id of `sub` is in
+    /// this graph already.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{DefaultNode, Graph, Node, NodeName, NodeTable};
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let mut sub = Graph::new();
+    /// let x = DefaultNode::new(NodeName::from("X"), &mut node_table);
+    /// let y = DefaultNode::new(NodeName::from("Y"), &mut node_table);
+    /// let (x_id, y_id) = (x.id(), y.id());
+    /// sub.add_node(x);
+    /// sub.add_node(y);
+    /// sub.connect(x_id, y_id).unwrap();
+    ///
+    /// let mut graph = Graph::new();
+    /// let a = DefaultNode::new(NodeName::from("A"), &mut node_table);
+    /// let a_id = a.id();
+    /// graph.add_node(a);
+    ///
+    /// let handle = graph.embed(sub).unwrap();
+    /// for entry in handle.entries() {
+    ///     graph.connect(a_id, *entry).unwrap();
+    /// }
+    /// ```
+    pub fn embed(&mut self, sub: Graph) -> Result<SubgraphHandle, GraphError> {
+        if let Some(id) = sub
+            .nodes
+            .keys()
+            .filter(|id| self.nodes.contains_key(id))
+            .min()
+        {
+            return Err(GraphError::DuplicateNodeId(id.0));
+        }
+        let mut entries = vec![];
+        let mut exits = vec![];
+        for (id, node) in &sub.nodes {
+            let mut node = node.blocking_lock();
+            if node.input_channels().is_empty() {
+                entries.push(*id);
+            }
+            if node.output_channels().is_empty() {
+                exits.push(*id);
             }
         }
+        entries.sort();
+        exits.sort();
+
+        self.nodes.extend(sub.nodes);
+        self.node_count += sub.node_count;
+        self.in_degree.extend(sub.in_degree);
+        self.abstract_graph.merge(sub.abstract_graph);
+        self.edges.extend(sub.edges);
+        self.node_timeouts.extend(sub.node_timeouts);
+        self.edge_types.extend(sub.edge_types);
+        self.fast_forwards.extend(sub.fast_forwards);
+        self.feedback_edges.extend(sub.feedback_edges);
+        self.tags.extend(sub.tags);
+
+        Ok(SubgraphHandle { entries, exits })
+    }
+
+    /// Combines this graph with `other` into a single graph, holding the nodes and the edges
+    /// of both, so that pipelines can be assembled from subgraphs built independently.
+    ///
+    /// The node ids are unique across the graphs of a process, so the nodes keep their ids, and
+    /// the two parts can then be connected, e.g. with [`Graph::add_edge`]. The settings of
+    /// `other`, such as the timeouts of its nodes, are kept, while the settings of the whole
+    /// graph, such as the watchdog, are the ones of this graph, like with [`Graph::embed`].
+    ///
+    /// Returns [`GraphError::DuplicateNode`] if a node name is in both graphs, or
+    /// [`GraphError::DuplicateNodeId`] if a node id is, see [`Graph::embed`].
+    pub fn merge(mut self, other: Graph) -> Result<Graph, GraphError> {
+        let names: HashSet<NodeName> = self
+            .nodes
+            .values()
+            .map(|node| node.blocking_lock().name())
+            .collect();
+        if let Some(name) = other
+            .nodes
+            .values()
+            .map(|node| node.blocking_lock().name())
+            .find(|name| names.contains(name))
+        {
+            return Err(GraphError::DuplicateNode(name));
+        }
+        self.embed(other)?;
+        Ok(self)
     }
 
     /// Initializes the network, setting up the nodes.
@@ -162,14 +866,170 @@ impl Graph {
             self.execute_states
                 .insert(*node, Arc::new(ExecState::new()));
         });
+        for (id, output) in &self.restored {
+            if let Some(state) = self.execute_states.get(id) {
+                state.set_output(Output::Out(output.clone()));
+                state.exe_success();
+            }
+        }
     }
 
     /// This function is used for the execution of a single dag.
+    ///
+    /// It creates a new runtime, and blocks on it until the graph completes, so it panics if
+    /// called from a task of a runtime already. An application holding a runtime, e.g. a web
+    /// server, should rather call [`Graph::run_on`] with its handle, or await
+    /// [`Graph::run_async`].
     pub fn start(&mut self) -> Result<(), GraphError> {
         let runtime = tokio::runtime::Runtime::new()
             .map_err(|e| GraphError::RuntimeCreationFailed(e.to_string()))?;
         runtime.block_on(async { self.async_start().await })
     }
+    /// Executes the graph on the runtime of `handle`, rather than creating a new runtime like
+    /// [`Graph::start`], and blocks until it completes.
+    ///
+    /// It can be called outside of any runtime, e.g. from a thread spawned by an application
+    /// holding the runtime, as well as from a task of a multi-threaded runtime, which is then
+    /// moved off the runtime thread while waiting, see [`tokio::task::block_in_place`]. In
+    /// async code, prefer awaiting [`Graph::run_async`].
+    ///
+    /// # Panics
+    /// Panics if called from a task of a current-thread runtime.
+    pub fn run_on(&mut self, handle: &tokio::runtime::Handle) -> Result<(), GraphError> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            task::block_in_place(|| handle.block_on(self.async_start()))
+        } else {
+            handle.block_on(self.async_start())
+        }
+    }
+
+    /// Executes the graph like [`Graph::start`], spawning the nodes of each block in the order
+    /// decided by `scheduler` rather than by priority, see [`Scheduler`]. The scheduler is kept for the
+    /// following runs.
+    pub fn run_with(&mut self, scheduler: impl Scheduler + 'static) -> Result<(), GraphError> {
+        self.set_scheduler(scheduler);
+        self.start()
+    }
+
+    /// Executes the graph like [`Graph::start`], aborting the whole run if it has not completed
+    /// within `deadline`, as a safety net for runs that may hang.
+    ///
+    /// Once the deadline is exceeded, the nodes still running or waiting to start are cancelled,
+    /// and [`GraphError::DeadlineExceeded`] is returned with the ids of the nodes that did not
+    /// complete, whatever the errors of the other nodes. The outputs of the completed nodes are
+    /// available as usual.
+    pub fn run_with_deadline(&mut self, deadline: Duration) -> Result<(), GraphError> {
+        self.run_deadline = Some(deadline);
+        let result = self.start();
+        self.run_deadline = None;
+        result
+    }
+
+    /// Executes only the nodes tagged with `tag`, see [`Graph::get_nodes_by_tag`], and the
+    /// nodes they transitively depend on, like [`Graph::start`].
+    ///
+    /// The other nodes are skipped like the nodes restored from a [`Snapshot`]: their channels
+    /// are closed instead of running them, so a node run sending to a skipped successor gets a
+    /// closed channel, and the skipped nodes have no output.
+    pub fn run_tagged(&mut self, tag: &str) -> GraphResult {
+        let tagged = self.get_nodes_by_tag(tag);
+        let mut predecessors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for (from, tos) in &self.edges {
+            for to in tos {
+                predecessors.entry(*to).or_default().push(*from);
+            }
+        }
+        let mut run_only = Self::traverse(tagged.iter().copied(), |id| {
+            predecessors.get(id).cloned().unwrap_or_default()
+        });
+        run_only.extend(tagged);
+
+        self.run_only = Some(run_only);
+        let result = self.start();
+        self.run_only = None;
+        result
+    }
+
+    /// Executes the graph like [`Graph::start`], with a copy of the [`EnvVar`] of the graph, so
+    /// that successive runs start from the same environment, e.g. in test suites running the
+    /// same pipeline with different inputs.
+    ///
+    /// The state of the last run is reset first, like with [`Graph::reset`], except that the
+    /// environment is kept, so the graph can be run again.
+    ///
+    /// The nodes of the run share the copy, which is dropped with the run rather than kept by
+    /// the graph. The copy holds the same values, so a value with interior mutability, e.g. a
+    /// `Mutex`, is still shared with the environment of the graph, and should be set anew
+    /// before each run to isolate it.
+    pub fn run_isolated(&mut self) -> GraphResult {
+        self.reset_runs();
+        let base = self.env.clone();
+        self.env = Arc::new((*base).clone());
+        let result = self.start();
+        self.env = base;
+        result
+    }
+
+    /// Executes the graph on the current runtime, same as [`Graph::async_start`]. The results
+    /// are returned once the graph completes, without blocking the runtime thread.
+    pub async fn run_async(&mut self) -> Result<(), GraphError> {
+        self.async_start().await
+    }
+
+    /// Executes the graph like [`Graph::start`], with `inputs` preloaded into the nodes.
+    ///
+    /// The packets for each node are put into a new input channel keyed by [`NodeId::INPUT`]
+    /// before any node runs, and the channel is closed once they are all received, so the node
+    /// reads them like packets from a predecessor. The channels are removed after the run.
+    /// Returns [`GraphError::UnknownNode`] if a node in `inputs` does not exist.
+    pub fn run_with_input(&mut self, inputs: HashMap<NodeId, Vec<Content>>) -> GraphResult {
+        if let Some(id) = inputs.keys().find(|id| !self.nodes.contains_key(id)) {
+            return Err(GraphError::UnknownNode(id.0));
+        }
+        for (id, contents) in &inputs {
+            let (tx, rx) = mpsc::channel::<Content>(contents.len().max(1));
+            for content in contents {
+                let mut content = content.clone();
+                content.mark_sent();
+                tx.try_send(content).unwrap();
+            }
+            self.in_flight.add(contents.len());
+            self.nodes[id]
+                .blocking_lock()
+                .input_channels()
+                .insert(NodeId::INPUT, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        }
+
+        let result = self.start();
+        for id in inputs.keys() {
+            self.nodes[id]
+                .blocking_lock()
+                .input_channels()
+                .close(&NodeId::INPUT);
+        }
+        result
+    }
+
+    /// Opens an input channel of `capacity` packets into the node `id`, keyed by
+    /// [`NodeId::INPUT`], and returns the [`GraphInput`] streaming packets into it while the
+    /// graph runs, e.g. with [`Graph::run_async`].
+    ///
+    /// The node counts as a source, and completes once it has read the packets it needs, or the
+    /// input is finished if it reads until the channel is closed. Opening the input of the node
+    /// again, e.g. before the next run, replaces the channel: the previous handle is
+    /// disconnected, and its sends fail. Returns [`GraphError::UnknownNode`] if the node does not
+    /// exist.
+    pub fn open_input(&mut self, id: NodeId, capacity: usize) -> Result<GraphInput, GraphError> {
+        let Some(node) = self.nodes.get(&id) else {
+            return Err(GraphError::UnknownNode(id.0));
+        };
+        let (tx, rx) = mpsc::channel::<Content>(capacity.max(1));
+        node.blocking_lock()
+            .input_channels()
+            .insert(NodeId::INPUT, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        Ok(GraphInput::new(tx, self.in_flight.clone()))
+    }
+
     /// Executes a single DAG within an existing async runtime.
     ///
     /// Use this method when you are already running inside an async context
@@ -190,7 +1050,9 @@ impl Graph {
     /// - The graph must have been properly configured (nodes and edges
     ///   added) before calling this method.
     ///
-    /// If those conditions are not met, execution may fail at runtime.
+    /// If those conditions are not met, execution may fail at runtime. Running a graph without
+    /// any node returns [`GraphError::Empty`], and a graph where every node waits for the input
+    /// of another node returns [`GraphError::NoSource`] rather than waiting forever.
     ///
     /// # Examples
     ///
@@ -206,18 +1068,86 @@ impl Graph {
     /// }
     /// ```
     pub async fn async_start(&mut self) -> Result<(), GraphError> {
+        if self.nodes.is_empty() {
+            return Err(GraphError::Empty);
+        }
         self.init();
         let is_loop = self.check_loop_and_partition().await;
         if is_loop {
             return Err(GraphError::GraphLoopDetected);
         }
+        if !self.has_source().await {
+            return Err(GraphError::NoSource);
+        }
 
         if !self.is_active.load(Ordering::Relaxed) {
             return Err(GraphError::GraphNotActive);
         }
+        self.warm_up().await?;
         self.run().await
     }
 
+    /// Returns true if any node can start without waiting for another node, i.e. it has no
+    /// input edge, or has inputs preloaded by [`Graph::run_with_input`] or opened by
+    /// [`Graph::open_input`].
+    async fn has_source(&self) -> bool {
+        let targets: HashSet<&NodeId> = self.edges.values().flatten().collect();
+        if self.nodes.keys().any(|id| !targets.contains(id)) {
+            return true;
+        }
+        for node in self.nodes.values() {
+            if node.lock().await.input_channels().contains(&NodeId::INPUT) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Calls [`Node::warm_up`] on all the nodes concurrently, before any node runs.
+    ///
+    /// # Returns
+    /// - `Ok(())` if all nodes are warmed up successfully
+    /// - `Err(GraphError::WarmUpFailed)` for the failed node, or `MultipleErrors` if multiple nodes fail
+    async fn warm_up(&self) -> Result<(), GraphError> {
+        let tasks = self.nodes.values().map(|node| {
+            let node = Arc::clone(node);
+            let env = Arc::clone(&self.env);
+            task::spawn(async move {
+                let mut node = node.lock().await;
+                node.warm_up(env)
+                    .await
+                    .map_err(|error| GraphError::WarmUpFailed {
+                        node_name: node.name(),
+                        node_id: node.id().0,
+                        error,
+                    })
+            })
+        });
+        let results = futures::future::join_all(tasks).await;
+
+        let mut errors = vec![];
+        for (node, result) in self.nodes.values().zip(results) {
+            match result {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => {
+                    error!("Warm-up failed - {}", e);
+                    errors.push(e);
+                }
+                Err(_) => {
+                    let node = node.lock().await;
+                    let (node_name, node_id) = (node.name(), node.id().0);
+                    error!("Warm-up panicked [name: {}, id: {}]", node_name, node_id);
+                    errors.push(GraphError::PanicOccurred {
+                        node_name,
+                        node_id,
+                        partial_outputs: Arc::default(),
+                    });
+                }
+            }
+        }
+        Self::collect_errors(errors)
+    }
+
     /// Executes the graph's nodes in a concurrent manner, respecting the block structure.
     ///
     /// - Executes nodes in blocks, where blocks are separated by conditional nodes
@@ -236,79 +1166,408 @@ impl Graph {
         let mut chunks = vec![];
         let condition_flag = Arc::new(Mutex::new(true));
         let errors = Arc::new(Mutex::new(Vec::new()));
+        // The senders of the nodes not run are dropped with the run, ending the output streams.
+        let mut output_senders = std::mem::take(&mut self.output_senders);
+        let mut discarded_outputs = HashSet::new();
+        // The timeouts set on the nodes themselves, see `Node::timeout`.
+        let mut own_timeouts = HashMap::new();
+        let deadline = self
+            .run_deadline
+            .map(|deadline| time::Instant::now() + deadline);
+        let mut deadline_exceeded = false;
+        #[cfg(feature = "resource-aware")]
+        let resource_pool = self
+            .resource_limits
+            .map(|limits| Arc::new(ResourcePool::new(limits)));
+        if self.watchdog.is_some() {
+            for node_id in self.nodes.keys() {
+                self.watch_states.entry(*node_id).or_default();
+            }
+        }
+        // Watch the nodes while the others are spawned, as they may wait for a permit.
+        let watchdog = self.watchdog.map(|stall_timeout| {
+            let nodes = self
+                .watch_states
+                .iter()
+                .map(|(id, state)| (*id, state.clone()))
+                .collect();
+            task::spawn(watch(nodes, stall_timeout))
+        });
+        // The number of predecessors of each node not spawned yet.
+        let mut unspawned_predecessors: HashMap<NodeId, usize> = HashMap::new();
+        for successor in self.edges.values().flatten() {
+            *unspawned_predecessors.entry(*successor).or_default() += 1;
+        }
+        let mut expired = false;
+        #[cfg(feature = "resource-aware")]
+        let mut requirements = HashMap::new();
+        let callbacks = Arc::new(self.callbacks.clone());
+        let progress = self.env.progress();
+        progress.start(self.nodes.keys().filter(|id| !self.is_skipped(id)).count());
+        let _ = self.events.send(GraphEvent::GraphStarted);
 
         // Start the nodes by blocks
         for block in &self.blocks {
+            if expired {
+                break;
+            }
             let mut chunk = vec![];
+            // Spawn the nodes with higher priority first.
+            let mut block_nodes = Vec::with_capacity(block.len());
             for node_id in block {
+                let mut node = self.nodes[node_id].lock().await;
+                if self.is_skipped(node_id) {
+                    for successor in self.edges.get(node_id).into_iter().flatten() {
+                        if let Some(count) = unspawned_predecessors.get_mut(successor) {
+                            *count -= 1;
+                        }
+                    }
+                    // Close the channels so that the neighbors do not wait for the skipped node,
+                    // once a restored node has handed its output to its successors.
+                    node.input_channels().close_all().await;
+                    if let Some(Some(output)) = self.restored.get(node_id) {
+                        node.output_channels().set_in_flight(self.in_flight.clone());
+                        node.output_channels().try_broadcast(output.clone());
+                    }
+                    node.output_channels().close_all();
+                    continue;
+                }
+                node.input_channels().set_in_flight(self.in_flight.clone());
+                node.input_channels()
+                    .set_lag_warning(*node_id, self.lag_warning_threshold);
+                for (&(from, to), &after_lags) in &self.fast_forwards {
+                    if to == *node_id {
+                        node.input_channels().set_fast_forward(
+                            from,
+                            to,
+                            after_lags,
+                            self.events.clone(),
+                        );
+                    }
+                }
+                node.output_channels().set_in_flight(self.in_flight.clone());
+                node.output_channels()
+                    .set_events(*node_id, self.events.clone());
+                if self.watchdog.is_some() {
+                    let state = &self.watch_states[node_id];
+                    node.input_channels()
+                        .set_activity(state.last_activity.clone());
+                    node.output_channels()
+                        .set_activity(state.last_activity.clone());
+                    node.output_channels()
+                        .set_pending_sends(state.pending_sends.clone());
+                }
+                if !node.retain_output() {
+                    discarded_outputs.insert(*node_id);
+                }
+                if let Some(timeout) = node.timeout() {
+                    own_timeouts.insert(*node_id, timeout);
+                }
+                #[cfg(feature = "resource-aware")]
+                requirements.insert(*node_id, node.resource_requirements());
+                block_nodes.push((node_id, node.priority()));
+            }
+            block_nodes.sort_by_key(|(node_id, priority)| (Reverse(*priority), **node_id));
+            let mut pending: Vec<NodeId> = block_nodes.into_iter().map(|(id, _)| *id).collect();
+            while !pending.is_empty() {
+                // A node is spawned once all its predecessors are, so that it never holds a
+                // permit or resources while one of them waits for them. In a cycle, any node is.
+                let mut ready: Vec<NodeId> = pending
+                    .iter()
+                    .filter(|id| {
+                        unspawned_predecessors
+                            .get(id)
+                            .is_none_or(|count| *count == 0)
+                    })
+                    .copied()
+                    .collect();
+                if ready.is_empty() {
+                    ready = pending.clone();
+                }
+                let node_id = &self
+                    .scheduler
+                    .next_to_spawn(&ready)
+                    .filter(|id| ready.contains(id))
+                    .unwrap_or(ready[0]);
+                // Wait until the resources of the node are available, and hold them until its
+                // task ends.
+                #[cfg(feature = "resource-aware")]
+                let resources = match &resource_pool {
+                    Some(pool) => {
+                        match until_deadline(deadline, pool.acquire(requirements[node_id])).await {
+                            Some(resources) => Some(resources),
+                            None => {
+                                expired = true;
+                                break;
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                // Wait for a permit to run, and hold it until the task of the node ends.
+                let permit = match &self.parallelism {
+                    Some(semaphore) => {
+                        match until_deadline(deadline, semaphore.clone().acquire_owned()).await {
+                            Some(permit) => Some(permit),
+                            None => {
+                                expired = true;
+                                break;
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                pending.retain(|id| id != node_id);
+                for successor in self.edges.get(node_id).into_iter().flatten() {
+                    if let Some(count) = unspawned_predecessors.get_mut(successor) {
+                        *count -= 1;
+                    }
+                }
                 let node = self.nodes.get(node_id).unwrap();
-                let execute_state = self.execute_states[&node_id].clone();
+                let execute_state = self.execute_states[node_id].clone();
                 let node_clone = Arc::clone(&self.env);
-                let node = Arc::clone(&node);
+                let node = Arc::clone(node);
                 let condition_flag = condition_flag.clone();
+                let timeout = self
+                    .node_timeouts
+                    .get(node_id)
+                    .or(own_timeouts.get(node_id))
+                    .copied()
+                    .or(self.default_node_timeout);
+
+                let watch = self.watchdog.and_then(|stall_timeout| {
+                    let state = self.watch_states.get(node_id)?.clone();
+                    Some((state, stall_timeout))
+                });
+                let max_watchdog_restarts = self.max_watchdog_restarts;
+                let max_loop_iterations = self.max_loop_iterations;
+                let events = self.events.clone();
+                let in_flight = self.in_flight.clone();
+                let in_flight_guard = in_flight.enter();
+                let output_senders = output_senders.remove(node_id).unwrap_or_default();
+                let retain_output = !discarded_outputs.contains(node_id);
+                let callbacks = callbacks.clone();
 
                 let task = task::spawn({
                     let errors = Arc::clone(&errors);
-                    async move {
-                        // create an Arc pointer to node, used for error handling.
-                        let node_ref = node.clone();
+                    let output_state = execute_state.clone();
+                    let (task_callbacks, task_id) = (callbacks.clone(), *node_id);
+                    let run = async move {
+                        let _in_flight_guard = in_flight_guard;
+                        let _permit = permit;
+                        #[cfg(feature = "resource-aware")]
+                        let _resources = resources;
+                        // Abort the background tasks of the node however the task ends.
+                        let scope = TaskScope::default();
+                        let _scope_guard = scope.abort_on_drop();
                         // Lock the node before running its method
                         let mut node = node.lock().await;
                         let node_name = node.name();
-                        let node_id = node.id().0;
-                        let result = panic::catch_unwind(AssertUnwindSafe(|| async move {
-                            node.run(node_clone).await
-                        }));
-
-                        match result {
-                            Ok(out) => {
-                                let out = out.await;
-                                if out.is_err() {
-                                    let error = out.get_err().unwrap_or("".to_string());
-                                    error!(
-                                        "Execution failed [name: {}, id: {}] - {}",
-                                        node_name, node_id, error
-                                    );
-                                    execute_state.set_output(out);
-                                    execute_state.exe_fail();
-                                    let mut errors_lock = errors.lock().await;
-                                    errors_lock.push(GraphError::ExecutionFailed {
-                                        node_name,
-                                        node_id,
-                                        error,
-                                    });
-                                } else {
-                                    // If the ouput is produced by a ConditionalNode, check the value:
-                                    // - true: go on execution
-                                    // - false: set conditional_exec
-                                    if let Some(false) = out.conditional_result() {
-                                        let mut cf = condition_flag.lock().await;
-                                        *cf = false;
-                                        info!(
-                                            "Condition failed on [name: {}, id: {}]. The rest nodes will abort.",
-                                            node_name, node_id,
-                                        )
+                        let id = node.id();
+                        let node_id = id.0;
+                        let mut iterations = 0;
+                        let mut restarts = 0;
+                        let _ = events.send(GraphEvent::NodeStarted(id));
+                        let started_at = Instant::now();
+
+                        loop {
+                            if let Some((watch, _)) = &watch {
+                                watch.start();
+                            }
+                            // The error is `None` if the node panics.
+                            let result = {
+                                let (scope, env, node_mut) =
+                                    (scope.clone(), node_clone.clone(), &mut *node);
+                                let context = NodeContext::new(id, node_name.clone());
+                                // Catch the panics of the node while creating its future, as
+                                // well as while polling it.
+                                let out = AssertUnwindSafe(async move {
+                                    scope.enter(context.enter(node_mut.run(env))).await
+                                })
+                                .catch_unwind()
+                                .map(|result| result.map_err(|_| None));
+                                let out = async move {
+                                    match timeout {
+                                        Some(timeout) => time::timeout(timeout, out)
+                                            .await
+                                            .unwrap_or(Err(Some(NodeError::Timeout(timeout)))),
+                                        None => out.await,
                                     }
+                                };
+                                match &watch {
+                                    // Cancel the run of a stalled node, and run it again, unless
+                                    // it is deadlocked or restarted too many times already.
+                                    Some((watch, stall_timeout)) => match future::select(
+                                        Box::pin(out),
+                                        Box::pin(watch.stalled()),
+                                    )
+                                    .await
+                                    {
+                                        Either::Left((result, _)) => result,
+                                        Either::Right(_) if watch.is_deadlocked() => {
+                                            Err(Some(NodeError::Deadlock))
+                                        }
+                                        Either::Right(_) if restarts < max_watchdog_restarts => {
+                                            restarts += 1;
+                                            continue;
+                                        }
+                                        Either::Right(_) => {
+                                            Err(Some(NodeError::Timeout(*stall_timeout)))
+                                        }
+                                    },
+                                    None => out.await,
+                                }
+                            };
+                            if let Some((watch, _)) = &watch {
+                                watch.stop();
+                            }
 
-                                    // Save the execution state.
-                                    execute_state.set_output(out);
-                                    execute_state.exe_success();
-                                    debug!(
-                                        "Execution succeed [name: {}, id: {}]",
-                                        node_name, node_id,
-                                    );
+                            let out = match result {
+                                Ok(out) => out,
+                                Err(error) => {
+                                    // The run is cancelled or panicked, close the channels so that its neighbors will not wait for it.
+                                    // Use the async lock (do not use blocking_lock inside runtime).
+                                    node.input_channels().close_all().await;
+                                    node.output_channels().close_all();
+
+                                    let node_error = error
+                                        .clone()
+                                        .unwrap_or(NodeError::Custom("panic occurred".to_string()));
+                                    callbacks.node_failed(id, &node_error);
+                                    let _ = events.send(GraphEvent::NodeFailed(id, node_error));
+                                    let mut errors_lock = errors.lock().await;
+                                    match error {
+                                        Some(error) => {
+                                            error!(
+                                                "Execution failed [name: {}, id: {}] - {}",
+                                                node_name, node_id, error
+                                            );
+                                            execute_state
+                                                .set_output(Output::Err(error.to_string()));
+                                            execute_state.exe_fail();
+                                            errors_lock.push(GraphError::NodeFailed {
+                                                node_name,
+                                                node_id,
+                                                error,
+                                                partial_outputs: Arc::default(),
+                                            });
+                                        }
+                                        None => {
+                                            error!(
+                                                "Execution failed [name: {}, id: {}]",
+                                                node_name, node_id,
+                                            );
+                                            execute_state.exe_fail();
+                                            errors_lock.push(GraphError::PanicOccurred {
+                                                node_name,
+                                                node_id,
+                                                partial_outputs: Arc::default(),
+                                            });
+                                        }
+                                    }
+                                    return;
                                 }
+                            };
+                            // Collect the logs apart from the output, see `Output::with_logs`.
+                            let (out, logs) = out.take_logs();
+                            execute_state.add_logs(logs);
+
+                            // Run the node again with the content fed back through a channel
+                            // keyed by its own id, until it stops looping or reaches the limit.
+                            if iterations > 0 {
+                                node.input_channels().close_async(&id).await;
                             }
-                            Err(_) => {
-                                // Close all the channels using the async lock (do not use blocking_lock inside runtime)
-                                let mut node_guard = node_ref.lock().await;
-                                node_guard.input_channels().close_all();
-                                node_guard.output_channels().close_all();
+                            if let Output::Loop(mut content) = out {
+                                iterations += 1;
+                                if iterations < max_loop_iterations {
+                                    let (tx, rx) = mpsc::channel::<Content>(1);
+                                    content.mark_sent();
+                                    tx.try_send(content).unwrap();
+                                    in_flight.add(1);
+                                    node.input_channels()
+                                        .insert(id, Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+                                    continue;
+                                }
+
+                                node.input_channels().close_all().await;
+                                node.output_channels().close_all();
 
-                                error!("Execution failed [name: {}, id: {}]", node_name, node_id,);
+                                let error = NodeError::TooManyIterations(iterations);
+                                callbacks.node_failed(id, &error);
+                                let _ = events.send(GraphEvent::NodeFailed(id, error.clone()));
+                                error!(
+                                    "Execution failed [name: {}, id: {}] - {}",
+                                    node_name, node_id, error
+                                );
+                                execute_state.set_output(Output::Err(error.to_string()));
+                                execute_state.exe_fail();
+                                let mut errors_lock = errors.lock().await;
+                                errors_lock.push(GraphError::NodeFailed {
+                                    node_name,
+                                    node_id,
+                                    error,
+                                    partial_outputs: Arc::default(),
+                                });
+                                return;
+                            }
+                            if out.is_err() {
+                                let error = out.get_err().unwrap_or("".to_string());
+                                let node_error = NodeError::Custom(error.clone());
+                                callbacks.node_failed(id, &node_error);
+                                let _ = events.send(GraphEvent::NodeFailed(id, node_error));
+                                error!(
+                                    "Execution failed [name: {}, id: {}] - {}",
+                                    node_name, node_id, error
+                                );
+                                execute_state.set_output(out);
+                                execute_state.exe_fail();
                                 let mut errors_lock = errors.lock().await;
-                                errors_lock.push(GraphError::PanicOccurred { node_name, node_id });
+                                errors_lock.push(GraphError::ExecutionFailed {
+                                    node_name,
+                                    node_id,
+                                    error,
+                                    partial_outputs: Arc::default(),
+                                });
+                            } else {
+                                // If the ouput is produced by a ConditionalNode, check the value:
+                                // - true: go on execution
+                                // - false: set conditional_exec
+                                if let Some(false) = out.conditional_result() {
+                                    let mut cf = condition_flag.lock().await;
+                                    *cf = false;
+                                    info!(
+                                        "Condition failed on [name: {}, id: {}]. The rest nodes will abort.",
+                                        node_name, node_id,
+                                    )
+                                }
+
+                                // Save the execution state.
+                                execute_state.set_output(out);
+                                execute_state.exe_success();
+                                let _ = events
+                                    .send(GraphEvent::NodeCompleted(id, started_at.elapsed()));
+                                debug!("Execution succeed [name: {}, id: {}]", node_name, node_id,);
                             }
+                            return;
+                        }
+                    };
+                    let progress = progress.clone();
+                    async move {
+                        run.await;
+                        progress.complete();
+                        let output = output_state.get_full_output();
+                        // A panicking node has no output.
+                        let output = if output_state.is_success() || output.is_err() {
+                            output
+                        } else {
+                            Output::Err("panic occurred".to_string())
+                        };
+                        task_callbacks.node_completed(task_id, &output);
+                        for sender in output_senders {
+                            let _ = sender.send(output.clone());
+                        }
+                        if !retain_output && output_state.is_success() {
+                            output_state.discard_output();
                         }
                     }
                 });
@@ -319,28 +1578,73 @@ impl Graph {
 
         // Await all chunks to complete.
         for chunk in chunks {
-            // If condition flag is false, abort the rest chuncks.
-            if *condition_flag.lock().await == false {
+            // If condition flag is false, or the deadline is exceeded, abort the rest chuncks.
+            if deadline_exceeded || !*condition_flag.lock().await {
                 chunk.iter().for_each(|handle| handle.abort());
-            } else {
-                let _ = futures::future::join_all(chunk).await;
+                continue;
+            }
+            let handles: Vec<_> = chunk.iter().map(|handle| handle.abort_handle()).collect();
+            let mut chunk = futures::future::join_all(chunk);
+            match deadline {
+                Some(deadline) => {
+                    if time::timeout_at(deadline, &mut chunk).await.is_err() {
+                        error!("Deadline exceeded, aborting the run");
+                        deadline_exceeded = true;
+                        handles.iter().for_each(|handle| handle.abort());
+                        // Wait for the cancelled tasks to release their nodes.
+                        let _ = chunk.await;
+                    }
+                }
+                None => {
+                    let _ = chunk.await;
+                }
             }
         }
         // let _ = futures::future::join_all(tasks).await;
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+        let _ = self.events.send(GraphEvent::GraphCompleted);
 
         self.is_active
             .store(false, std::sync::atomic::Ordering::Relaxed);
 
-        let errors = errors.lock().await;
+        if deadline_exceeded {
+            let mut unfinished: Vec<NodeId> = self
+                .execute_states
+                .iter()
+                .filter(|(_, state)| !state.is_finished())
+                .map(|(id, _)| *id)
+                .collect();
+            unfinished.sort();
+            return Err(GraphError::DeadlineExceeded { unfinished });
+        }
+        let mut errors = errors.lock().await.clone();
         if !errors.is_empty() {
-            if errors.len() == 1 {
-                return Err(errors[0].clone());
-            } else {
-                return Err(GraphError::MultipleErrors(errors.clone()));
+            let partial_outputs: Arc<HashMap<NodeId, Output>> = Arc::new(
+                self.execute_states
+                    .iter()
+                    .filter(|(_, state)| state.is_success() && !state.is_discarded())
+                    .map(|(id, state)| (*id, state.get_full_output()))
+                    .collect(),
+            );
+            for error in &mut errors {
+                error.set_partial_outputs(&partial_outputs);
             }
         }
+        Self::collect_errors(errors)
+    }
 
-        Ok(())
+    /// Folds the errors produced by nodes into a single result.
+    /// - Returns `Ok(())` if there's no error
+    /// - Returns the error itself if only one failure occurs
+    /// - Returns `MultipleErrors` if multiple nodes fail
+    fn collect_errors(mut errors: Vec<GraphError>) -> Result<(), GraphError> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(GraphError::MultipleErrors(errors)),
+        }
     }
 
     /// Checks for cycles in the abstract graph, and partitions the graph into blocks.
@@ -391,6 +1695,580 @@ impl Graph {
         has_cycle
     }
 
+    /// Partitions the nodes into weakly connected components, i.e. the edges are considered
+    /// undirected. Nodes of different components cannot exchange any data.
+    ///
+    /// The components are sorted by size in descending order, so the main pipeline is always
+    /// the first one. The nodes in each component are sorted by id.
+    pub fn connected_components(&self) -> Vec<Vec<NodeId>> {
+        // Union-find, each node points to its parent, and a root points to itself.
+        let mut parents: HashMap<NodeId, NodeId> = self.nodes.keys().map(|&id| (id, id)).collect();
+        fn find(parents: &mut HashMap<NodeId, NodeId>, id: NodeId) -> NodeId {
+            let parent = parents[&id];
+            if parent == id {
+                return id;
+            }
+            let root = find(parents, parent);
+            parents.insert(id, root);
+            root
+        }
+
+        for (from, tos) in &self.edges {
+            for to in tos {
+                if !parents.contains_key(from) || !parents.contains_key(to) {
+                    continue;
+                }
+                let (from_root, to_root) = (find(&mut parents, *from), find(&mut parents, *to));
+                parents.insert(from_root, to_root);
+            }
+        }
+
+        let mut components: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for id in self.nodes.keys() {
+            let root = find(&mut parents, *id);
+            components.entry(root).or_default().push(*id);
+        }
+        let mut components: Vec<Vec<NodeId>> = components.into_values().collect();
+        components.iter_mut().for_each(|component| component.sort());
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
+        components
+    }
+
+    /// Returns the nodes reachable from node `id` through outgoing edges. `id` itself is
+    /// included only if it lies on a cycle.
+    pub fn reachable_from(&self, id: NodeId) -> HashSet<NodeId> {
+        Self::traverse([id], |id| {
+            self.edges.get(id).into_iter().flatten().copied().collect()
+        })
+    }
+
+    /// Removes the nodes neither reachable from any source node (without input edges) nor
+    /// ancestors of any sink node (without output edges), which can never exchange data with
+    /// the rest of the graph, e.g. a cycle of nodes only connected to each other.
+    ///
+    /// The channels between the removed nodes and the remaining ones are closed. Returns the
+    /// ids of the removed nodes, sorted.
+    pub fn prune_unreachable_nodes(&mut self) -> Vec<NodeId> {
+        let mut predecessors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for (from, tos) in &self.edges {
+            for to in tos {
+                predecessors.entry(*to).or_default().push(*from);
+            }
+        }
+        let sources: Vec<NodeId> = self
+            .nodes
+            .keys()
+            .filter(|id| !predecessors.contains_key(id))
+            .copied()
+            .collect();
+        let sinks: Vec<NodeId> = self
+            .nodes
+            .keys()
+            .filter(|id| self.edges.get(id).is_none_or(|tos| tos.is_empty()))
+            .copied()
+            .collect();
+
+        let mut live = Self::traverse(sources.clone(), |id| {
+            self.edges.get(id).into_iter().flatten().copied().collect()
+        });
+        live.extend(Self::traverse(sinks.clone(), |id| {
+            predecessors.get(id).cloned().unwrap_or_default()
+        }));
+        live.extend(sources.into_iter().chain(sinks));
+
+        let mut pruned: Vec<NodeId> = self
+            .nodes
+            .keys()
+            .filter(|id| !live.contains(id))
+            .copied()
+            .collect();
+        pruned.sort();
+        for id in &pruned {
+            self.remove_node(*id, predecessors.get(id).map_or(&[], |ids| ids.as_slice()));
+        }
+        pruned
+    }
+
+    /// Returns true if the node `id` is not run in the next run, because it is restored from a
+    /// snapshot, see [`Graph::restore`], or not tagged, see [`Graph::run_tagged`].
+    fn is_skipped(&self, id: &NodeId) -> bool {
+        self.restored.contains_key(id)
+            || self
+                .run_only
+                .as_ref()
+                .is_some_and(|run_only| !run_only.contains(id))
+    }
+
+    /// Visits the nodes reachable from `starts`, where `next` returns the neighbors of a node.
+    fn traverse<F>(starts: impl IntoIterator<Item = NodeId>, next: F) -> HashSet<NodeId>
+    where
+        F: Fn(&NodeId) -> Vec<NodeId>,
+    {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<NodeId> = starts.into_iter().flat_map(|id| next(&id)).collect();
+        while let Some(id) = stack.pop() {
+            if visited.insert(id) {
+                stack.extend(next(&id));
+            }
+        }
+        visited
+    }
+
+    /// Removes node `id` and its edges, closing the channels of its neighbors.
+    fn remove_node(&mut self, id: NodeId, predecessors: &[NodeId]) {
+        for from in predecessors {
+            if let Some(tos) = self.edges.get_mut(from) {
+                tos.remove(&id);
+            }
+            if let Some(node) = self.nodes.get(from) {
+                node.blocking_lock().output_channels().close(&id);
+            }
+        }
+        for to in self.edges.remove(&id).unwrap_or_default() {
+            if let Some(degree) = self.in_degree.get_mut(&to) {
+                *degree = degree.saturating_sub(1);
+            }
+            if let Some(node) = self.nodes.get(&to) {
+                node.blocking_lock().input_channels().close(&id);
+            }
+        }
+
+        if self.abstract_graph.get_abstract_node_id(&id).is_none() {
+            self.node_count -= 1;
+        }
+        self.abstract_graph.remove_node(id);
+        self.nodes.remove(&id);
+        self.execute_states.remove(&id);
+        self.in_degree.remove(&id);
+        self.node_timeouts.remove(&id);
+        self.restored.remove(&id);
+        self.tags.remove(&id);
+        self.edge_types
+            .retain(|(from, to), _| *from != id && *to != id);
+        self.fast_forwards
+            .retain(|(from, to), _| *from != id && *to != id);
+        self.watch_states.remove(&id);
+        for (from, to) in std::mem::take(&mut self.feedback_edges) {
+            if from == id {
+                if let Some(node) = self.nodes.get(&to) {
+                    node.blocking_lock().input_channels().close(&id);
+                }
+            } else if to == id {
+                if let Some(node) = self.nodes.get(&from) {
+                    node.blocking_lock().output_channels().close(&id);
+                }
+            } else {
+                self.feedback_edges.insert((from, to));
+            }
+        }
+    }
+
+    /// Returns the critical path of the graph, i.e. the path with the largest total
+    /// [`Node::weight`], from its first node to its last node.
+    ///
+    /// Since the nodes of a path cannot run concurrently, its weight is a lower bound of the
+    /// execution time of the whole graph, and its nodes are the bottlenecks worth optimizing.
+    /// Nodes on a cycle are ignored. Ties are broken by the smallest node ids.
+    ///
+    /// The weights are read without waiting for the nodes, so it returns `None` if a node is
+    /// busy, e.g. while the graph runs. It can be called inside a Tokio runtime.
+    pub fn compute_critical_path(&self) -> Option<Vec<NodeId>> {
+        let mut in_degree: HashMap<NodeId, usize> = self.nodes.keys().map(|&id| (id, 0)).collect();
+        for to in self.edges.values().flatten() {
+            if let Some(degree) = in_degree.get_mut(to) {
+                *degree += 1;
+            }
+        }
+        let weights: HashMap<NodeId, u64> = self
+            .nodes
+            .iter()
+            .map(|(id, node)| Some((*id, node.try_lock().ok()?.weight())))
+            .collect::<Option<_>>()?;
+
+        // The weight of the heaviest path ending at each node, and its previous node.
+        let mut distances: HashMap<NodeId, (u64, Option<NodeId>)> = HashMap::new();
+        let mut ready: BTreeSet<NodeId> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        while let Some(id) = ready.pop_first() {
+            let distance = distances.entry(id).or_insert((weights[&id], None)).0;
+            let mut tos: Vec<NodeId> = self
+                .edges
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .filter(|to| in_degree.contains_key(to))
+                .copied()
+                .collect();
+            tos.sort();
+            for to in tos {
+                let candidate = distance + weights[&to];
+                let entry = distances.entry(to).or_insert((0, None));
+                if entry.1.is_none() || candidate > entry.0 {
+                    *entry = (candidate, Some(id));
+                }
+                let degree = in_degree.get_mut(&to).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.insert(to);
+                }
+            }
+        }
+
+        let Some(mut last) = distances
+            .iter()
+            .filter(|(id, _)| in_degree[id] == 0)
+            .max_by(|(a_id, (a, _)), (b_id, (b, _))| a.cmp(b).then(b_id.cmp(a_id)))
+            .map(|(id, _)| *id)
+        else {
+            return Some(vec![]);
+        };
+        let mut path = vec![last];
+        while let Some(prev) = distances[&last].1 {
+            path.push(prev);
+            last = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Checks the graph for likely wiring mistakes without running it.
+    ///
+    /// Reports a [`GraphDiagnostic::DisconnectedComponent`] for each connected component
+    /// isolated from the main (largest) one, see [`Graph::connected_components`], and a
+    /// [`GraphDiagnostic::TypeMismatch`] for each node receiving different types through its
+    /// typed edges, see [`Graph::add_typed_edge`].
+    pub fn validate(&self) -> Vec<GraphDiagnostic> {
+        let mut diagnostics: Vec<GraphDiagnostic> = self
+            .connected_components()
+            .into_iter()
+            .skip(1)
+            .map(GraphDiagnostic::DisconnectedComponent)
+            .collect();
+
+        let mut received: BTreeMap<NodeId, BTreeMap<TypeId, &'static str>> = BTreeMap::new();
+        for ((_, to), edge_type) in &self.edge_types {
+            received
+                .entry(*to)
+                .or_default()
+                .insert(edge_type.type_id, edge_type.type_name);
+        }
+        for (node, types) in received {
+            if types.len() > 1 {
+                let mut types: Vec<_> = types.into_values().collect();
+                types.sort_unstable();
+                diagnostics.push(GraphDiagnostic::TypeMismatch { node, types });
+            }
+        }
+        diagnostics
+    }
+
+    /// Waits until the graph is quiescent: no node is running, and every packet sent through
+    /// the channels has been received.
+    ///
+    /// Packets dropped by closing a channel do not count, but a packet never received keeps
+    /// the graph from being quiescent. Resolves immediately if the graph is quiescent already.
+    pub async fn await_quiescence(&self) {
+        self.in_flight.wait_idle().await
+    }
+
+    /// Returns the node `id`, locked for inspection, e.g. of its channels, or `None` if the node
+    /// does not exist.
+    ///
+    /// The node is locked without waiting, so it also returns `None` if the node is busy, e.g.
+    /// while the graph runs, and it can be called inside a Tokio runtime. The node stays locked
+    /// until the returned guard is dropped.
+    pub fn get_node(&self, id: NodeId) -> Option<MutexGuard<'_, dyn Node>> {
+        self.nodes.get(&id)?.try_lock().ok()
+    }
+
+    /// Returns the node named `name`, locked like [`Graph::get_node`], or `None` if there is no
+    /// such node. If several nodes have the same name, any of them is returned. Busy nodes are
+    /// skipped.
+    pub fn get_node_by_name(&self, name: &str) -> Option<MutexGuard<'_, dyn Node>> {
+        self.nodes
+            .values()
+            .filter_map(|node| node.try_lock().ok())
+            .find(|node| node.name() == name)
+    }
+
+    /// Returns true if the two graphs have the same structure: the same node names, and the same
+    /// edges and feedback edges between them, whatever the ids of the nodes.
+    ///
+    /// This compares graphs built in different orders, e.g. in tests. The actions, the channel
+    /// types and the settings of the graphs are not compared. Nodes with the same name are
+    /// told apart by their number only.
+    pub fn structural_eq(&self, other: &Graph) -> bool {
+        self.structure() == other.structure()
+    }
+
+    /// Returns the sorted node names, and the edges and feedback edges by names.
+    fn structure(&self) -> Structure {
+        let names: HashMap<NodeId, NodeName> = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (*id, node.blocking_lock().name()))
+            .collect();
+        let by_names = |(from, to): (&NodeId, &NodeId)| (names[from].clone(), names[to].clone());
+        let edges = self
+            .edges
+            .iter()
+            .flat_map(|(from, to_ids)| to_ids.iter().map(move |to| (from, to)))
+            .map(by_names)
+            .collect();
+        let feedback_edges = self
+            .feedback_edges
+            .iter()
+            .map(|(from, to)| by_names((from, to)))
+            .collect();
+        let mut node_names: Vec<NodeName> = names.into_values().collect();
+        node_names.sort();
+        Structure {
+            node_names,
+            edges,
+            feedback_edges,
+        }
+    }
+
+    /// Returns true if the node `id` has not started, and each of its input channels has a
+    /// packet to receive or is closed.
+    ///
+    /// Nodes without inputs are always ready before they start. Returns false if the node
+    /// does not exist.
+    pub fn is_ready(&self, id: &NodeId) -> bool {
+        self.status(id) == NodeStatus::Ready
+    }
+
+    /// Returns the execution status of the node `id`. It only peeks at the node without
+    /// waiting, so it's cheap to call at any time.
+    ///
+    /// A node is reported as [`NodeStatus::Running`] while the graph holds it, which also
+    /// covers its warm-up. Returns [`NodeStatus::Pending`] if the node does not exist.
+    pub fn status(&self, id: &NodeId) -> NodeStatus {
+        let Some(node) = self.nodes.get(id) else {
+            return NodeStatus::Pending;
+        };
+        if let Some(state) = self.execute_states.get(id) {
+            if state.is_finished() {
+                return if state.is_success() {
+                    NodeStatus::Done
+                } else {
+                    NodeStatus::Failed
+                };
+            }
+        }
+        match node.try_lock() {
+            Ok(mut node) => {
+                if node.input_channels().is_ready() {
+                    NodeStatus::Ready
+                } else {
+                    NodeStatus::Pending
+                }
+            }
+            Err(_) => NodeStatus::Running,
+        }
+    }
+
+    /// Returns the packet counters of every edge, keyed by `(from, to)`. The packets sent and
+    /// received are only counted with the `metrics` feature, which is off by default so that
+    /// the sends and receives do not pay for the counters.
+    ///
+    /// The nodes are locked without waiting to read their channels, so the edges of busy nodes,
+    /// e.g. while the graph runs, are left out. It can be called inside a Tokio runtime.
+    #[cfg(feature = "metrics")]
+    pub fn edge_stats(&self) -> HashMap<(NodeId, NodeId), EdgeStats> {
+        let mut stats = HashMap::new();
+        for (from, tos) in &self.edges {
+            for to in tos {
+                let (Some(from_node), Some(to_node)) = (self.nodes.get(from), self.nodes.get(to))
+                else {
+                    continue;
+                };
+                let Ok(mut from_node) = from_node.try_lock() else {
+                    continue;
+                };
+                let sent = from_node.output_channels().messages_sent(to);
+                drop(from_node);
+                let Ok(mut to_node) = to_node.try_lock() else {
+                    continue;
+                };
+                let in_channels = to_node.input_channels();
+                stats.insert(
+                    (*from, *to),
+                    EdgeStats {
+                        sent,
+                        received: in_channels.messages_received(from),
+                        dropped: in_channels.messages_dropped(from),
+                    },
+                );
+            }
+        }
+        stats
+    }
+
+    /// Describes every edge of the graph, sorted by `(from, to)`, e.g. for visualization
+    /// tools and topology serializers. The feedback edges, see [`Graph::add_feedback_edge`],
+    /// are not part of the DAG and not described.
+    ///
+    /// The nodes are locked without waiting to read their channels, so the edges from busy
+    /// nodes, e.g. while the graph runs, are left out. It can be called inside a Tokio runtime.
+    pub fn get_connection_info(&self) -> Vec<ConnectionInfo> {
+        let mut connections = vec![];
+        for (from, tos) in &self.edges {
+            let Some(from_node) = self.nodes.get(from) else {
+                continue;
+            };
+            let Ok(mut from_node) = from_node.try_lock() else {
+                continue;
+            };
+            for to in tos {
+                let Some((channel_type, capacity)) = from_node.output_channels().channel_info(to)
+                else {
+                    continue;
+                };
+                connections.push(ConnectionInfo {
+                    from: *from,
+                    to: *to,
+                    channel_type,
+                    capacity,
+                    type_id: self
+                        .edge_types
+                        .get(&(*from, *to))
+                        .map(|edge_type| edge_type.type_id),
+                });
+            }
+        }
+        connections.sort_by_key(|connection| (connection.from, connection.to));
+        connections
+    }
+
+    /// Returns the number of packets successfully sent through every edge, keyed by
+    /// `(from, to)`. It is the `sent` counter of [`Graph::edge_stats`], which has the same
+    /// caveats.
+    #[cfg(feature = "metrics")]
+    pub fn edge_message_counts(&self) -> HashMap<(NodeId, NodeId), u64> {
+        self.edge_stats()
+            .into_iter()
+            .map(|(edge, stats)| (edge, stats.sent))
+            .collect()
+    }
+
+    /// Subscribes to the lifecycle events of the next runs of the graph.
+    ///
+    /// Each subscriber receives every event independently, e.g. one for logging and another
+    /// one for metrics. Events are kept for up to 1024 events behind the slowest subscriber,
+    /// after which it misses the oldest ones, see [`broadcast::Receiver::recv`]. The nodes
+    /// report their sends to the events only while the graph runs them.
+    pub fn event_stream(&self) -> broadcast::Receiver<GraphEvent> {
+        self.events.subscribe()
+    }
+
+    /// Streams the output of each node of the next run, as soon as the node completes.
+    ///
+    /// The stream must be created before running the graph, e.g. with [`Graph::run_async`],
+    /// and polled concurrently with the run: it yields the nodes in the order their tasks
+    /// report them, which is not always the order of the edges, as a successor may complete
+    /// before the task of its predecessor is done, and ends once the run is over. A failed node
+    /// yields its error [`Output`], or `Output::Err("panic occurred")` if it panicked, even
+    /// after an await. The nodes that do not run, e.g. behind a failed condition, yield
+    /// nothing.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{DefaultNode, Graph, NodeName, NodeTable};
+    /// use futures::StreamExt;
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let mut graph = Graph::new();
+    /// graph.add_node(DefaultNode::new(NodeName::from("A"), &mut node_table));
+    ///
+    /// let runtime = tokio::runtime::Runtime::new().unwrap();
+    /// let outputs = graph.output_stream();
+    /// let (result, outputs) = runtime.block_on(async {
+    ///     futures::join!(graph.run_async(), outputs.collect::<Vec<_>>())
+    /// });
+    /// result.unwrap();
+    /// assert_eq!(outputs.len(), 1);
+    /// ```
+    pub fn output_stream(&mut self) -> impl Stream<Item = (NodeId, Output)> {
+        self.nodes
+            .keys()
+            .map(|id| {
+                let (tx, rx) = oneshot::channel();
+                let id = *id;
+                self.output_senders.entry(id).or_default().push(tx);
+                rx.map(move |output| output.ok().map(|output| (id, output)))
+            })
+            .collect::<FuturesUnordered<_>>()
+            .filter_map(future::ready)
+    }
+
+    /// Registers `f` to be called with the output of each node as soon as it finishes, in all
+    /// the next runs, e.g. to update a progress bar without polling [`Graph::get_outputs`].
+    ///
+    /// `f` is called in the task of the node, once the node has run, so it should return
+    /// quickly. A failed node is reported too, with its error [`Output`], or
+    /// `Output::Err("panic occurred")` if it panicked, like with [`Graph::output_stream`].
+    /// The nodes that do not run, e.g. behind a failed condition, are not reported. All the
+    /// registered callbacks are called, in the order they were registered.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use dagrs::{DefaultNode, Graph, NodeName, NodeTable};
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let mut graph = Graph::new();
+    /// graph.add_node(DefaultNode::new(NodeName::from("A"), &mut node_table));
+    ///
+    /// let completed = Arc::new(Mutex::new(vec![]));
+    /// let sink = completed.clone();
+    /// graph.on_node_complete(move |id, _| sink.lock().unwrap().push(id));
+    /// graph.start().unwrap();
+    /// assert_eq!(completed.lock().unwrap().len(), 1);
+    /// ```
+    pub fn on_node_complete(&mut self, f: impl Fn(NodeId, &Output) + Send + Sync + 'static) {
+        self.callbacks.complete.push(Arc::new(f));
+    }
+
+    /// Registers `f` to be called with the error of each node as soon as it fails, in all the
+    /// next runs, e.g. to raise an alert while the rest of the graph is still running.
+    ///
+    /// `f` is called in the task of the node, before the callbacks of
+    /// [`Graph::on_node_complete`], with the same [`NodeError`] as the
+    /// [`GraphEvent::NodeFailed`] event. All the registered callbacks are called, in the order
+    /// they were registered.
+    pub fn on_node_error(&mut self, f: impl Fn(NodeId, &NodeError) + Send + Sync + 'static) {
+        self.callbacks.error.push(Arc::new(f));
+    }
+
+    /// Captures the nodes completed successfully in the last run, and their outputs.
+    pub fn snapshot(&self) -> Snapshot {
+        let completed = self
+            .execute_states
+            .iter()
+            .filter(|(_, state)| state.is_success())
+            .map(|(&id, state)| (id, state.get_output()))
+            .collect();
+        Snapshot { completed }
+    }
+
+    /// Marks the nodes completed in `snapshot` as done, so that the next runs only execute the
+    /// remaining nodes. The outputs of the completed nodes are available as if they had run.
+    ///
+    /// Instead of running a completed node, its output is sent once to each of its successors,
+    /// then its channels are closed, so that the successors receive the output, followed by
+    /// [`RecvErr::Closed`](crate::RecvErr::Closed). A node which sends other packets than its
+    /// output, or several packets, is not replayed: its successors only receive its output.
+    /// The output is not sent to the successors whose channel is full, e.g. a feedback edge
+    /// holding its initial value, nor by a node completed without output.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.restored = snapshot.completed;
+    }
+
     /// Get the output of all tasks.
     pub fn get_results<T: Send + Sync + 'static>(&self) -> HashMap<NodeId, Option<Arc<T>>> {
         self.execute_states
@@ -405,9 +2283,34 @@ impl Graph {
             .collect()
     }
 
+    /// Get the output of the node `id` as type `T`.
+    ///
+    /// Returns an [`OutputError`] if the node has not run, produced no output, or its output
+    /// is not of type `T`.
+    pub fn get_output_as<T: Send + Sync + 'static>(
+        &self,
+        id: &NodeId,
+    ) -> Result<Arc<T>, OutputError> {
+        let state = self
+            .execute_states
+            .get(id)
+            .ok_or(OutputError::NoSuchNode(*id))?;
+        let content = state.get_output().ok_or(OutputError::NoOutput(*id))?;
+        content.into_inner().ok_or(OutputError::TypeMismatch {
+            node_id: *id,
+            expected: std::any::type_name::<T>(),
+        })
+    }
+
+    /// Returns the outputs of all the nodes of the last run.
+    ///
+    /// The outputs of the nodes completed successfully whose [`Node::retain_output`] returns
+    /// false are dropped as soon as they complete, and left out: only the outputs streamed by
+    /// [`Graph::output_stream`] carry them. The output of a failed node is always kept.
     pub fn get_outputs(&self) -> HashMap<NodeId, Output> {
         self.execute_states
             .iter()
+            .filter(|(_, state)| !state.is_discarded())
             .map(|(&id, state)| {
                 let t = state.get_full_output();
                 (id, t)
@@ -415,6 +2318,186 @@ impl Graph {
             .collect()
     }
 
+    /// Returns the outputs of [`Graph::get_outputs`] sorted by [`NodeId`], so that iterating
+    /// them is deterministic, e.g. to log them or compare them to a golden file.
+    pub fn get_outputs_ordered(&self) -> Vec<(NodeId, Output)> {
+        let mut outputs: Vec<(NodeId, Output)> = self.get_outputs().into_iter().collect();
+        outputs.sort_by_key(|(id, _)| *id);
+        outputs
+    }
+
+    /// Returns the logs attached to the outputs of the node `id` in the last run, see
+    /// [`Output::with_logs`], in the order they were produced. A node looping with
+    /// [`Output::Loop`] collects the logs of all its runs.
+    ///
+    /// The logs are kept even if the output of the node is not, see [`Node::retain_output`].
+    /// Returns an empty list if the node has not run, or produced no logs.
+    pub fn get_logs(&self, id: &NodeId) -> Vec<String> {
+        self.execute_states
+            .get(id)
+            .map_or_else(Vec::new, |state| state.get_logs())
+    }
+
+    /// Limit the execution time of the node `id`.
+    ///
+    /// If the node's `run` does not finish within `timeout`, it is cancelled, its channels are
+    /// closed and a [`NodeError::Timeout`] is recorded. This overrides the timeout of the node
+    /// itself, see [`Node::timeout`], and the default node timeout.
+    pub fn set_node_timeout(&mut self, id: NodeId, timeout: Duration) {
+        self.node_timeouts.insert(id, timeout);
+    }
+
+    /// Limit the execution time of every node that has no timeout set by [`Graph::set_node_timeout`].
+    pub fn set_default_node_timeout(&mut self, timeout: Duration) {
+        self.default_node_timeout = Some(timeout);
+    }
+
+    /// Set the maximum number of times a node can run by returning [`Output::Loop`], 1000 by
+    /// default. A node still looping at the last run fails with
+    /// [`NodeError::TooManyIterations`].
+    pub fn set_max_loop_iterations(&mut self, max: usize) {
+        self.max_loop_iterations = max.max(1);
+    }
+
+    /// Watch the running nodes in the background, and restart the ones without activity for
+    /// `stall_timeout`, so that a hung node does not hang the whole graph.
+    ///
+    /// Each send or receive of a node through its channels counts as an activity, and so does
+    /// starting a run. Every `stall_timeout / 2`, the run of each node inactive for `stall_timeout`
+    /// is cancelled and started again. The packets the cancelled run received are lost, so the
+    /// nodes should be idempotent. A node waiting longer than `stall_timeout` for its input, e.g.
+    /// behind a slow predecessor, is restarted as well.
+    ///
+    /// Unlike [`Graph::set_node_timeout`], this applies to every node, and only bounds the time
+    /// between two activities rather than the whole run. A node still stalling after being
+    /// restarted 3 times in a run, see [`Graph::set_max_watchdog_restarts`], fails with
+    /// [`NodeError::Timeout`] instead of hanging the graph.
+    ///
+    /// When all the running nodes are inactive for `stall_timeout` while sending, e.g. two nodes
+    /// sending to each other through full channels, restarting them would not help: they fail
+    /// with [`NodeError::Deadlock`] instead, and the ids of the deadlocked nodes are logged.
+    ///
+    /// # Panics
+    /// Panics if `stall_timeout` is zero.
+    pub fn set_watchdog(&mut self, stall_timeout: Duration) {
+        assert!(!stall_timeout.is_zero(), "stall timeout must be positive");
+        self.watchdog = Some(stall_timeout);
+    }
+
+    /// Set the maximum number of times the watchdog restarts a stalled node in a run, 3 by
+    /// default, see [`Graph::set_watchdog`]. A node stalling once more fails with
+    /// [`NodeError::Timeout`]. With 0, a stalled node fails without being restarted.
+    pub fn set_max_watchdog_restarts(&mut self, max: usize) {
+        self.max_watchdog_restarts = max;
+    }
+
+    /// Set the number of packets dropped from a broadcast edge, see
+    /// [`Graph::connect_broadcast`], because the receiver lags behind, above which a warning
+    /// naming the slow receiver is logged on every further lag. It is 100 by default. The
+    /// dropped packets are also counted by
+    /// [`InChannels::messages_dropped`](crate::InChannels::messages_dropped), and reported by
+    /// `Graph::edge_stats` with the `metrics` feature.
+    pub fn set_lag_warning_threshold(&mut self, threshold: u64) {
+        self.lag_warning_threshold = threshold;
+    }
+
+    /// Fast-forward the broadcast edge from `from` to `to`, see [`Graph::connect_broadcast`],
+    /// once its receiver lagged behind `after_lags` times in a row: the receiver is
+    /// resubscribed, skipping all the packets still buffered, so that it catches up with the
+    /// latest packets instead of lagging on every receive.
+    ///
+    /// The skipped packets are lost silently for the receiving node: it only sees a single
+    /// [`RecvErr::Lagged`](crate::RecvErr::Lagged) with the number of packets dropped, which are
+    /// also counted like the other dropped packets, see [`Graph::set_lag_warning_threshold`],
+    /// reported by a [`GraphEvent::FastForwarded`] event and logged. This suits consumers
+    /// interested in recent packets only, e.g. monitors or previews, not the ones which need
+    /// every packet. The lag count is reset whenever the receiver catches up. Edges other than broadcast ones never lag, and are not affected.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if either node does not exist, or
+    /// [`GraphError::UnknownEdge`] if `from` is not connected to `to`.
+    ///
+    /// # Panics
+    /// Panics if `after_lags` is zero.
+    pub fn set_fast_forward(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        after_lags: u32,
+    ) -> Result<(), GraphError> {
+        assert!(after_lags > 0, "the number of lags must be positive");
+        for id in [from, to] {
+            if !self.nodes.contains_key(&id) {
+                return Err(GraphError::UnknownNode(id.0));
+            }
+        }
+        if !self.edge_exists(from, to) {
+            return Err(GraphError::UnknownEdge {
+                from: from.0,
+                to: to.0,
+            });
+        }
+        self.fast_forwards.insert((from, to), after_lags);
+        Ok(())
+    }
+
+    /// Limit the total resources required by the running nodes, as given by
+    /// [`Node::resource_requirements`]. A node is started only once its requirements fit in the
+    /// resources left by the running ones, and it holds them until it completes. A node requiring
+    /// more than the limits runs alone.
+    ///
+    /// The resources are given out in the order of the edges, like the permits of
+    /// [`Graph::set_parallelism`]: a node takes them only once all its predecessors took theirs,
+    /// so that a node waiting for its inputs never keeps them from starting. A node waiting for
+    /// resources still blocks its predecessors once their channels towards it are full, which
+    /// hangs the run if they hold the resources it needs: the limits must admit the nodes
+    /// streaming more packets to each other than their channels hold at the same time.
+    #[cfg(feature = "resource-aware")]
+    pub fn set_resource_limits(&mut self, limits: ResourceRequirements) {
+        self.resource_limits = Some(limits);
+    }
+
+    /// Run at most `max_concurrent` nodes at a time in the next runs, e.g. the number of CPUs
+    /// available to the process, so that a wide graph does not exhaust the CPU or the memory.
+    /// The other nodes are started once a running node completes. There is no limit by default.
+    ///
+    /// The permits are given out in the order of the edges: a node is given one only once all
+    /// its predecessors were, so that a node waiting for its inputs never keeps them from
+    /// running, whatever the priorities or the [`Scheduler`]. A node still
+    /// waits for its turn while its predecessors run, which hangs the run if they block on a
+    /// full channel towards it while holding all the permits: `max_concurrent` should admit
+    /// the nodes streaming more packets to each other than their channels hold at the same
+    /// time.
+    ///
+    /// # Panics
+    /// Panics if `max_concurrent` is zero.
+    pub fn set_parallelism(&mut self, max_concurrent: usize) {
+        assert!(max_concurrent > 0, "parallelism must be positive");
+        self.parallelism = Some(Arc::new(Semaphore::new(max_concurrent)));
+    }
+
+    /// Set how the node `id` distributes the packets among its successors with
+    /// [`OutChannels::send_balanced`](crate::OutChannels::send_balanced). The successors are
+    /// picked round-robin by default.
+    ///
+    /// Returns [`GraphError::UnknownNode`] if the node does not exist.
+    pub fn set_balance_policy(
+        &mut self,
+        id: NodeId,
+        policy: BalancePolicy,
+    ) -> Result<(), GraphError> {
+        let node = self.nodes.get(&id).ok_or(GraphError::UnknownNode(id.0))?;
+        node.blocking_lock()
+            .output_channels()
+            .set_balance_policy(policy);
+        Ok(())
+    }
+
+    /// Spawn the nodes of each block in the order decided by `scheduler` in the next runs, see
+    /// [`Scheduler`].
+    pub fn set_scheduler(&mut self, scheduler: impl Scheduler + 'static) {
+        self.scheduler = Arc::new(scheduler);
+    }
+
     /// Before the dag starts executing, set the dag's global environment variable.
     pub fn set_env(&mut self, env: EnvVar) {
         self.env = Arc::new(env);
@@ -430,13 +2513,116 @@ impl Graph {
     }
 }
 
+/// The execution status of a node, see [`Graph::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    /// The node has not started, and waits for some of its inputs.
+    Pending,
+    /// The node has not started, and all of its inputs are satisfied.
+    Ready,
+    /// The node is running.
+    Running,
+    /// The node finished successfully.
+    Done,
+    /// The node finished with an error.
+    Failed,
+}
+
+/// The packet counters of an edge, see [`Graph::edge_stats`].
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EdgeStats {
+    /// The packets successfully sent through the edge.
+    pub sent: u64,
+    /// The packets successfully received from the edge.
+    pub received: u64,
+    /// The packets dropped because the receiver lagged behind a broadcast channel.
+    pub dropped: u64,
+}
+
+/// The description of an edge, see [`Graph::get_connection_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub from: NodeId,
+    pub to: NodeId,
+    /// The kind of channel of the edge.
+    pub channel_type: ChannelType,
+    /// The number of packets the channel can buffer, 0 if unknown.
+    pub capacity: usize,
+    /// The type of the packets carried by a typed edge, see [`Graph::add_typed_edge`].
+    pub type_id: Option<TypeId>,
+}
+
+/// The type of the packets carried by a typed edge, see [`Graph::add_typed_edge`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EdgeType {
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl EdgeType {
+    fn of<T: 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+        }
+    }
+}
+
+/// The structure of a graph, independent of the node ids, see [`Graph::structural_eq`].
+#[derive(PartialEq)]
+struct Structure {
+    node_names: Vec<NodeName>,
+    edges: BTreeSet<(NodeName, NodeName)>,
+    feedback_edges: BTreeSet<(NodeName, NodeName)>,
+}
+
+/// Handle of a graph embedded by [`Graph::embed`], exposing the boundary nodes of the subgraph.
+#[derive(Debug, Clone)]
+pub struct SubgraphHandle {
+    entries: Vec<NodeId>,
+    exits: Vec<NodeId>,
+}
+
+impl SubgraphHandle {
+    /// Nodes of the subgraph without any input channel, sorted by id.
+    pub fn entries(&self) -> &[NodeId] {
+        &self.entries
+    }
+
+    /// Nodes of the subgraph without any output channel, sorted by id.
+    pub fn exits(&self) -> &[NodeId] {
+        &self.exits
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Waits for `future` until the `deadline` of a run, if any. Returns `None` once it is exceeded.
+async fn until_deadline<F: Future>(
+    deadline: Option<time::Instant>,
+    future: F,
+) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graph::loop_subgraph::LoopSubgraph;
+    use crate::graph::spec::{EdgeSpec, NodeSpec};
     use crate::node::conditional_node::{Condition, ConditionalNode};
     use crate::node::default_node::DefaultNode;
     use crate::{
-        Action, Content, EnvVar, InChannels, Node, NodeName, NodeTable, OutChannels, Output,
+        Action, Content, EnvVar, InChannels, Node, NodeError, NodeName, NodeTable, OutChannels,
+        Output,
     };
     use async_trait::async_trait;
     use std::sync::Arc;
@@ -453,7 +2639,7 @@ mod tests {
 
     impl HelloAction {
         pub fn new() -> Self {
-            Self::default()
+            Self
         }
     }
 
@@ -487,7 +2673,7 @@ mod tests {
         graph.add_node(node);
         graph.add_node(node1);
 
-        graph.add_edge(node_id, vec![node1_id]);
+        graph.add_edge(node_id, vec![node1_id]).unwrap();
 
         match graph.start() {
             Ok(_) => {
@@ -501,32 +2687,617 @@ mod tests {
         }
     }
 
-    /// A test condition that always fails.
+    /// Test for getting typed outputs.
     ///
-    /// This condition is used in tests to verify the behavior of conditional nodes
-    /// when their condition evaluates to false. The `run` method always returns false,
-    /// simulating a failing condition.
-    struct FailingCondition;
-    #[async_trait::async_trait]
-    impl Condition for FailingCondition {
-        async fn run(&self, _: &mut InChannels, _: &OutChannels, _: Arc<EnvVar>) -> bool {
-            false
+    /// Step 1: run a graph of a node without output and a node outputting a String.
+    ///
+    /// Step 2: verify the output and the errors of a missing node, an empty output and a
+    /// mismatched type.
+    #[test]
+    fn test_get_output_as() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let empty = DefaultNode::new(NodeName::from("Empty"), &mut node_table);
+        let hello = DefaultNode::with_action(NodeName::from("Hello"), HelloAction, &mut node_table);
+        let (empty_id, hello_id) = (empty.id(), hello.id());
+        graph.add_node(empty);
+        graph.add_node(hello);
+        graph.start().unwrap();
+
+        assert_eq!(
+            *graph.get_output_as::<String>(&hello_id).unwrap(),
+            "Hello world"
+        );
+        assert_eq!(
+            graph.get_output_as::<usize>(&hello_id),
+            Err(OutputError::TypeMismatch {
+                node_id: hello_id,
+                expected: "usize"
+            })
+        );
+        assert_eq!(
+            graph.get_output_as::<String>(&empty_id),
+            Err(OutputError::NoOutput(empty_id))
+        );
+        let unknown_id = NodeId(usize::MAX);
+        assert_eq!(
+            graph.get_output_as::<String>(&unknown_id),
+            Err(OutputError::NoSuchNode(unknown_id))
+        );
+    }
+
+    /// An implementation of [`Action`] that adds `self.0` to the number received from its
+    /// predecessor (if any), then forwards the sum to all of its successors.
+    struct AddAction(usize);
+    #[async_trait]
+    impl Action for AddAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            out_channels: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            let mut sum = self.0;
+            for input in in_channels.map(|content| content.unwrap()).await {
+                sum += *input.get::<usize>().unwrap();
+            }
+            out_channels.broadcast(Content::new(sum)).await;
+            Output::new(sum)
         }
     }
 
-    /// Step 1: Create a new graph and node table.
+    /// Test for wiring a linear chain `A -> B -> C` with [`Graph::connect`].
     ///
-    /// Step 2: Create two nodes - a conditional node that will fail and a hello action node.
+    /// Step 1: create three nodes, each adding a constant to its input.
     ///
-    /// Step 3: Add nodes to graph and set up dependencies between them.
+    /// Step 2: connect A to B and B to C.
     ///
-    /// Step 4: Run the graph and verify the conditional node fails as expected.
+    /// Step 3: run the graph and verify the value flows through the chain.
     #[test]
-    fn test_conditional_execution() {
+    fn test_connect_linear_chain() {
         let mut graph = Graph::new();
         let mut node_table = NodeTable::new();
 
-        // Create conditional node that will fail
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let c = DefaultNode::with_action(NodeName::from("C"), AddAction(100), &mut node_table);
+        let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_node(c);
+        graph.connect(a_id, b_id).unwrap();
+        graph.connect(b_id, c_id).unwrap();
+
+        graph.start().unwrap();
+
+        let results = graph.get_results::<usize>();
+        assert_eq!(*results[&a_id].clone().unwrap(), 1);
+        assert_eq!(*results[&b_id].clone().unwrap(), 11);
+        assert_eq!(*results[&c_id].clone().unwrap(), 111);
+    }
+
+    /// Test for choosing the kind of channel of each edge.
+    ///
+    /// Step 1: wire a linear chain `A -> B -> C -> D` with a broadcast, a oneshot and a mpsc
+    /// channel, in that order.
+    ///
+    /// Step 2: verify the kinds of the edges, then run the graph and verify the value flows
+    /// through the chain.
+    #[test]
+    fn test_connect_with() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = [1, 10, 100, 1000]
+            .into_iter()
+            .map(|n| {
+                let name = NodeName::from(format!("Add {}", n));
+                let node = DefaultNode::with_action(name, AddAction(n), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        let kinds = [ChannelType::Broadcast, ChannelType::Once, ChannelType::Mpsc];
+        for (pair, kind) in ids.windows(2).zip(kinds) {
+            graph.connect_with(pair[0], pair[1], kind).unwrap();
+        }
+        assert!(matches!(
+            graph.connect_with(ids[0], ids[1], ChannelType::Mpsc),
+            Err(GraphError::DuplicateEdge { .. })
+        ));
+        let connections = graph.get_connection_info();
+        for (pair, kind) in ids.windows(2).zip(kinds) {
+            let connection = connections
+                .iter()
+                .find(|connection| connection.from == pair[0] && connection.to == pair[1])
+                .unwrap();
+            assert_eq!(connection.channel_type, kind);
+        }
+
+        graph.start().unwrap();
+        let results = graph.get_results::<usize>();
+        assert_eq!(*results[&ids[3]].clone().unwrap(), 1111);
+    }
+
+    /// Test for rejecting duplicate edges.
+    ///
+    /// Step 1: connect A to B, then connect them again with `connect` and `connect_once`.
+    ///
+    /// Step 2: verify both calls fail, `add_edge` coalesces the edge, and B still has a single
+    /// input channel, so the graph runs as `A -> B`.
+    #[test]
+    fn test_duplicate_edge() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+
+        graph.connect(a_id, b_id).unwrap();
+        assert!(graph.has_edge(a_id, b_id));
+        assert!(!graph.has_edge(b_id, a_id));
+        assert!(matches!(
+            graph.connect(a_id, b_id),
+            Err(GraphError::DuplicateEdge { from, to }) if from == a_id.0 && to == b_id.0
+        ));
+        assert!(graph.connect_once(a_id, b_id).is_err());
+        graph.add_edge(a_id, vec![b_id]).unwrap();
+
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 11);
+    }
+
+    /// Test for waiting until a graph is quiescent.
+    ///
+    /// Step 1: run `A -> B`, where B receives the packet from A, and verify the graph is
+    /// quiescent afterwards.
+    ///
+    /// Step 2: run `C -> D`, where D never receives the packet from C, and verify the graph
+    /// never becomes quiescent.
+    #[test]
+    fn test_await_quiescence() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let wait = |graph: &Graph| {
+            runtime.block_on(async {
+                time::timeout(Duration::from_millis(100), graph.await_quiescence()).await
+            })
+        };
+        let mut node_table = NodeTable::new();
+
+        let mut graph = Graph::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+        graph.start().unwrap();
+        assert!(wait(&graph).is_ok());
+
+        let mut graph = Graph::new();
+        let c = DefaultNode::with_action(NodeName::from("C"), AddAction(1), &mut node_table);
+        let d = DefaultNode::with_action(NodeName::from("D"), HelloAction, &mut node_table);
+        let (c_id, d_id) = (c.id(), d.id());
+        graph.add_node(c);
+        graph.add_node(d);
+        graph.connect(c_id, d_id).unwrap();
+        graph.start().unwrap();
+        assert!(wait(&graph).is_err());
+    }
+
+    /// A node that records whether it has been warmed up, and outputs the record when it runs.
+    /// Warming up fails if `fail_warm_up` is set.
+    struct WarmUpNode {
+        id: NodeId,
+        name: NodeName,
+        in_channels: InChannels,
+        out_channels: OutChannels,
+        fail_warm_up: bool,
+        warmed_up: bool,
+    }
+
+    impl WarmUpNode {
+        fn new(name: &str, fail_warm_up: bool, node_table: &mut NodeTable) -> Self {
+            Self {
+                id: node_table.alloc_id_for(name),
+                name: NodeName::from(name),
+                in_channels: InChannels::default(),
+                out_channels: OutChannels::default(),
+                fail_warm_up,
+                warmed_up: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Node for WarmUpNode {
+        fn id(&self) -> NodeId {
+            self.id
+        }
+
+        fn name(&self) -> NodeName {
+            self.name.clone()
+        }
+
+        fn input_channels(&mut self) -> &mut InChannels {
+            &mut self.in_channels
+        }
+
+        fn output_channels(&mut self) -> &mut OutChannels {
+            &mut self.out_channels
+        }
+
+        async fn run(&mut self, _: Arc<EnvVar>) -> Output {
+            Output::new(self.warmed_up)
+        }
+
+        async fn warm_up(&mut self, _: Arc<EnvVar>) -> Result<(), NodeError> {
+            if self.fail_warm_up {
+                return Err(NodeError::Custom("warm-up failed".to_string()));
+            }
+            self.warmed_up = true;
+            Ok(())
+        }
+    }
+
+    /// Test for warming up nodes before they run.
+    ///
+    /// Step 1: run a graph of two nodes, both of them should have been warmed up when they run.
+    ///
+    /// Step 2: run a graph with a node failing to warm up, the graph should not run.
+    #[test]
+    fn test_warm_up() {
+        let mut node_table = NodeTable::new();
+        let a = WarmUpNode::new("A", false, &mut node_table);
+        let b = WarmUpNode::new("B", false, &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+
+        let mut graph = Graph::new();
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+        graph.start().unwrap();
+
+        let results = graph.get_results::<bool>();
+        assert!(*results[&a_id].clone().unwrap());
+        assert!(*results[&b_id].clone().unwrap());
+
+        let c = WarmUpNode::new("C", true, &mut node_table);
+        let c_id = c.id();
+        let mut graph = Graph::new();
+        graph.add_node(c);
+
+        match graph.start() {
+            Err(GraphError::WarmUpFailed { node_id, .. }) => assert_eq!(node_id, c_id.0),
+            _ => panic!("expected the warm-up of node C to fail"),
+        }
+        assert!(graph.get_results::<bool>()[&c_id].is_none());
+    }
+
+    /// An implementation of [`Action`] that sleeps for `self.0`, then outputs whether all of its
+    /// inputs are closed.
+    struct SleepAction(Duration);
+    #[async_trait]
+    impl Action for SleepAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            _: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            tokio::time::sleep(self.0).await;
+            let closed = in_channels.map(|content| content.is_err()).await;
+            Output::new(closed.into_iter().all(|closed| closed))
+        }
+    }
+
+    /// Test for cancelling a node that runs for too long.
+    ///
+    /// Step 1: create a node that sleeps for an hour, and its successor that returns quickly.
+    ///
+    /// Step 2: set a short timeout for the sleeping node, and a default timeout for the others.
+    ///
+    /// Step 3: run the graph and verify only the sleeping node times out, and its successor
+    /// sees the closed channel.
+    #[test]
+    fn test_node_timeout() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+
+        let slow = DefaultNode::with_action(
+            NodeName::from("Slow"),
+            SleepAction(Duration::from_secs(3600)),
+            &mut node_table,
+        );
+        let fast = DefaultNode::with_action(
+            NodeName::from("Fast"),
+            SleepAction(Duration::ZERO),
+            &mut node_table,
+        );
+        let (slow_id, fast_id) = (slow.id(), fast.id());
+        graph.add_node(slow);
+        graph.add_node(fast);
+        graph.connect(slow_id, fast_id).unwrap();
+        graph.set_node_timeout(slow_id, Duration::from_millis(50));
+        graph.set_default_node_timeout(Duration::from_secs(10));
+
+        match graph.start() {
+            Err(GraphError::NodeFailed {
+                node_id,
+                error: NodeError::Timeout(timeout),
+                ..
+            }) => {
+                assert_eq!(node_id, slow_id.0);
+                assert_eq!(timeout, Duration::from_millis(50));
+            }
+            _ => panic!("expected the slow node to time out"),
+        }
+        assert!(*graph.get_results::<bool>()[&fast_id].clone().unwrap());
+    }
+
+    /// Test for the timeout set on the node itself.
+    ///
+    /// The node built with a short timeout times out, though the default timeout is long.
+    #[test]
+    fn test_node_own_timeout() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let slow = DefaultNode::builder("Slow", &mut node_table)
+            .action(SleepAction(Duration::from_secs(3600)))
+            .timeout(Duration::from_millis(50))
+            .build();
+        graph.add_node(slow);
+        graph.set_default_node_timeout(Duration::from_secs(10));
+
+        assert!(matches!(
+            graph.start(),
+            Err(GraphError::NodeFailed {
+                error: NodeError::Timeout(timeout),
+                ..
+            }) if timeout == Duration::from_millis(50)
+        ));
+    }
+
+    /// Test for embedding a subgraph into a graph.
+    ///
+    /// Step 1: create a subgraph with a linear chain `X -> Y -> Z`.
+    ///
+    /// Step 2: create a graph with nodes `P` and `Q`, and embed the subgraph.
+    ///
+    /// Step 3: connect `P` to the entry of the subgraph, and its exit to `Q`.
+    ///
+    /// Step 4: run the graph and verify the value flows through all the nodes.
+    ///
+    /// Step 5: embed a subgraph holding a node with the id of `P`, and verify it fails without
+    /// changing the graph.
+    #[test]
+    fn test_embed_subgraph() {
+        let mut node_table = NodeTable::new();
+
+        let mut sub = Graph::new();
+        let x = DefaultNode::with_action(NodeName::from("X"), AddAction(10), &mut node_table);
+        let y = DefaultNode::with_action(NodeName::from("Y"), AddAction(100), &mut node_table);
+        let z = DefaultNode::with_action(NodeName::from("Z"), AddAction(1000), &mut node_table);
+        let (x_id, y_id, z_id) = (x.id(), y.id(), z.id());
+        sub.add_node(x);
+        sub.add_node(y);
+        sub.add_node(z);
+        sub.connect(x_id, y_id).unwrap();
+        sub.connect(y_id, z_id).unwrap();
+
+        let mut graph = Graph::new();
+        let p = DefaultNode::with_action(NodeName::from("P"), AddAction(1), &mut node_table);
+        let q = DefaultNode::with_action(NodeName::from("Q"), AddAction(10000), &mut node_table);
+        let (p_id, q_id) = (p.id(), q.id());
+        graph.add_node(p);
+        graph.add_node(q);
+
+        let handle = graph.embed(sub).unwrap();
+        assert_eq!(handle.entries(), &[x_id]);
+        assert_eq!(handle.exits(), &[z_id]);
+        graph.connect(p_id, handle.entries()[0]).unwrap();
+        graph.connect(handle.exits()[0], q_id).unwrap();
+
+        graph.start().unwrap();
+        let results = graph.get_results::<usize>();
+        assert_eq!(results.len(), 5);
+        assert_eq!(*results[&q_id].clone().unwrap(), 11111);
+
+        let mut colliding = Graph::new();
+        let r = DefaultNode::with_action(NodeName::from("R"), AddAction(7), &mut node_table);
+        let r_id = r.id();
+        colliding.add_node(r);
+        colliding.add_node(DefaultNode::with_id(
+            p_id,
+            NodeName::from("P2"),
+            AddAction(7),
+        ));
+        colliding.connect(r_id, p_id).unwrap();
+        assert!(matches!(
+            graph.embed(colliding),
+            Err(GraphError::DuplicateNodeId(id)) if id == p_id.0
+        ));
+        assert_eq!(graph.nodes.len(), 5);
+        assert!(!graph.nodes.contains_key(&r_id));
+        assert_eq!(graph.in_degree[&p_id], 0);
+        assert!(graph.get_node_by_name("P").is_some());
+        assert!(graph.get_node_by_name("P2").is_none());
+    }
+
+    /// An implementation of [`Action`] that sends `self.0` to all of its successors twice, and
+    /// outputs the results of the second sends.
+    struct SendTwiceAction(usize);
+    #[async_trait]
+    impl Action for SendTwiceAction {
+        async fn run(
+            &self,
+            _: &mut InChannels,
+            out_channels: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            out_channels.broadcast(Content::new(self.0)).await;
+            let second = out_channels.broadcast(Content::new(self.0)).await;
+            Output::new(second.iter().all(|res| res.is_err()))
+        }
+    }
+
+    /// An implementation of [`Action`] that receives from its predecessor until the channel is
+    /// closed, and outputs all the received numbers.
+    struct CollectAction;
+    #[async_trait]
+    impl Action for CollectAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            _: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            let mut received = vec![];
+            for id in in_channels.get_sender_ids() {
+                while let Ok(content) = in_channels.recv_from(&id).await {
+                    received.push(*content.get::<usize>().unwrap());
+                }
+            }
+            Output::new(received)
+        }
+    }
+
+    /// Test for the oneshot edge.
+    ///
+    /// Step 1: connect a node sending twice to a node receiving until closed with a oneshot edge.
+    ///
+    /// Step 2: run the graph and verify the second send fails, and exactly one packet is received.
+    #[test]
+    fn test_connect_once() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), SendTwiceAction(7), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), CollectAction, &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect_once(a_id, b_id).unwrap();
+
+        graph.start().unwrap();
+        assert!(*graph.get_results::<bool>()[&a_id].clone().unwrap());
+        assert_eq!(
+            *graph.get_results::<Vec<usize>>()[&b_id].clone().unwrap(),
+            vec![7]
+        );
+    }
+
+    /// Test for finding the isolated parts of a graph.
+    ///
+    /// Step 1: create a graph with components `A -> B -> C`, `D -> E` and `F`.
+    ///
+    /// Step 2: verify the components are sorted by size, and the two smaller ones are reported
+    /// by `validate`.
+    #[test]
+    fn test_connected_components() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = ["A", "B", "C", "D", "E", "F"]
+            .into_iter()
+            .map(|name| {
+                let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        graph.connect(ids[1], ids[2]).unwrap();
+        graph.connect(ids[0], ids[1]).unwrap();
+        graph.connect(ids[3], ids[4]).unwrap();
+
+        let components = graph.connected_components();
+        assert_eq!(
+            components,
+            vec![ids[0..3].to_vec(), ids[3..5].to_vec(), vec![ids[5]]]
+        );
+        assert_eq!(
+            graph.validate(),
+            vec![
+                GraphDiagnostic::DisconnectedComponent(ids[3..5].to_vec()),
+                GraphDiagnostic::DisconnectedComponent(vec![ids[5]]),
+            ]
+        );
+    }
+
+    /// Test for typed edges.
+    ///
+    /// Step 1: connect A to B and C with typed edges of `i32`, and D to C with a typed edge of
+    /// `String`.
+    ///
+    /// Step 2: verify a typed edge of another type from A is rejected, and the validation
+    /// reports C receiving both types.
+    #[test]
+    fn test_add_typed_edge() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = ["A", "B", "C", "D"]
+            .into_iter()
+            .map(|name| {
+                let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        let [a, b, c, d] = ids[..] else {
+            unreachable!()
+        };
+
+        graph.add_typed_edge::<i32>(a, b, 4).unwrap();
+        graph.add_typed_edge::<i32>(a, c, 4).unwrap();
+        assert!(matches!(
+            graph.add_typed_edge::<i32>(a, b, 4),
+            Err(GraphError::DuplicateEdge { .. })
+        ));
+        assert!(matches!(
+            graph.add_typed_edge::<String>(a, d, 4),
+            Err(GraphError::TypeMismatch { expected: "i32", found, .. })
+                if found == any::type_name::<String>()
+        ));
+        assert!(!graph.has_edge(a, d));
+
+        graph.add_typed_edge::<String>(d, c, 4).unwrap();
+        let mut types = vec!["i32", any::type_name::<String>()];
+        types.sort_unstable();
+        assert_eq!(
+            graph.validate(),
+            vec![GraphDiagnostic::TypeMismatch { node: c, types }]
+        );
+    }
+
+    /// A test condition that always fails.
+    ///
+    /// This condition is used in tests to verify the behavior of conditional nodes
+    /// when their condition evaluates to false. The `run` method always returns false,
+    /// simulating a failing condition.
+    struct FailingCondition;
+    #[async_trait::async_trait]
+    impl Condition for FailingCondition {
+        async fn run(&self, _: &mut InChannels, _: &OutChannels, _: Arc<EnvVar>) -> bool {
+            false
+        }
+    }
+
+    /// Step 1: Create a new graph and node table.
+    ///
+    /// Step 2: Create two nodes - a conditional node that will fail and a hello action node.
+    ///
+    /// Step 3: Add nodes to graph and set up dependencies between them.
+    ///
+    /// Step 4: Run the graph and verify the conditional node fails as expected.
+    #[test]
+    fn test_conditional_execution() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+
+        // Create conditional node that will fail
         let node_a_name = "Node A";
         let node_a = ConditionalNode::with_condition(
             NodeName::from(node_a_name),
@@ -549,7 +3320,7 @@ mod tests {
         graph.add_node(node_b);
 
         // Add edge from A to B
-        graph.add_edge(node_a_id, vec![node_b_id]);
+        graph.add_edge(node_a_id, vec![node_b_id]).unwrap();
 
         // Execute graph
         match graph.start() {
@@ -562,4 +3333,2264 @@ mod tests {
             }
         }
     }
+
+    /// An implementation of [`Action`] with a fixed priority, which records the priority when
+    /// it starts running.
+    struct PriorityAction(u8, Arc<std::sync::Mutex<Vec<u8>>>);
+    #[async_trait]
+    impl Action for PriorityAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            self.1.lock().unwrap().push(self.0);
+            Output::empty()
+        }
+
+        fn priority(&self) -> u8 {
+            self.0
+        }
+    }
+
+    /// Test for the priority of nodes.
+    ///
+    /// Step 1: create independent nodes with different priorities.
+    ///
+    /// Step 2: run the graph on a single-threaded runtime, and verify the nodes are started in
+    /// descending order of priority.
+    #[test]
+    fn test_priority() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let started = Arc::new(std::sync::Mutex::new(vec![]));
+        for (i, priority) in [1, 5, 0, 3].into_iter().enumerate() {
+            graph.add_node(DefaultNode::with_action(
+                NodeName::from(format!("Node {}", i)),
+                PriorityAction(priority, started.clone()),
+                &mut node_table,
+            ));
+        }
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(graph.async_start())
+            .unwrap();
+        assert_eq!(*started.lock().unwrap(), vec![5, 3, 1, 0]);
+    }
+
+    /// Test for a node with low priority among a flood of nodes with high priority.
+    ///
+    /// The node with low priority is started last, but it is started.
+    #[test]
+    fn test_priority_no_starvation() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let started = Arc::new(std::sync::Mutex::new(vec![]));
+        for i in 0..=100 {
+            let priority = if i == 50 { 0 } else { u8::MAX };
+            graph.add_node(DefaultNode::with_action(
+                NodeName::from(format!("Node {}", i)),
+                PriorityAction(priority, started.clone()),
+                &mut node_table,
+            ));
+        }
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(graph.async_start())
+            .unwrap();
+        let started = started.lock().unwrap();
+        assert_eq!(started.len(), 101);
+        assert_eq!(started.last(), Some(&0));
+    }
+
+    /// A [`Scheduler`] starting the nodes with the lowest priority first.
+    struct LowestPriorityFirst;
+    impl Scheduler for LowestPriorityFirst {
+        fn next_to_spawn(&self, pending: &[NodeId]) -> Option<NodeId> {
+            pending.last().copied()
+        }
+    }
+
+    /// Test for a custom scheduler.
+    ///
+    /// Run independent nodes with different priorities on a single-threaded runtime, and
+    /// verify they are started in ascending order of priority.
+    #[test]
+    fn test_scheduler() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let started = Arc::new(std::sync::Mutex::new(vec![]));
+        for (i, priority) in [1, 5, 0, 3].into_iter().enumerate() {
+            graph.add_node(DefaultNode::with_action(
+                NodeName::from(format!("Node {}", i)),
+                PriorityAction(priority, started.clone()),
+                &mut node_table,
+            ));
+        }
+        graph.set_scheduler(LowestPriorityFirst);
+
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(graph.async_start())
+            .unwrap();
+        assert_eq!(*started.lock().unwrap(), vec![0, 1, 3, 5]);
+    }
+
+    /// An implementation of [`Action`] that keeps increasing a counter in a background task
+    /// while it sleeps.
+    struct HeartbeatAction(Arc<std::sync::atomic::AtomicUsize>);
+    #[async_trait]
+    impl Action for HeartbeatAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let beats = self.0.clone();
+            TaskScope::current().spawn(async move {
+                loop {
+                    beats.fetch_add(1, Ordering::Relaxed);
+                    time::sleep(Duration::from_millis(5)).await;
+                }
+            });
+            time::sleep(Duration::from_millis(30)).await;
+            Output::empty()
+        }
+    }
+
+    /// Test for the background tasks of a node.
+    ///
+    /// Step 1: run a node that spawns a heartbeat task in its [`TaskScope`].
+    ///
+    /// Step 2: verify the heartbeat ran while the node was running, and stopped as soon as the
+    /// node returned, though the runtime is still alive.
+    #[test]
+    fn test_task_scope() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let beats = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        graph.add_node(DefaultNode::with_action(
+            NodeName::from("Heartbeat"),
+            HeartbeatAction(beats.clone()),
+            &mut node_table,
+        ));
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            graph.async_start().await.unwrap();
+            let beats_at_end = beats.load(Ordering::Relaxed);
+            assert!(beats_at_end > 0);
+            time::sleep(Duration::from_millis(50)).await;
+            // A beat being polled on another worker when aborted may still land.
+            assert!(beats.load(Ordering::Relaxed) <= beats_at_end + 1);
+        });
+    }
+
+    /// An implementation of [`Action`] that outputs the [`NodeContext`] of its node.
+    struct ContextAction;
+    #[async_trait]
+    impl Action for ContextAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            Output::new(NodeContext::current())
+        }
+    }
+
+    /// Test for reading the identity of the node from its action.
+    ///
+    /// Two nodes share the same action, and each one outputs its own id and name. Outside of a
+    /// run, there is no context.
+    #[test]
+    fn test_node_context() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), ContextAction, &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), ContextAction, &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+
+        graph.start().unwrap();
+        for (id, name) in [(a_id, "A"), (b_id, "B")] {
+            let context = graph.get_output_as::<NodeContext>(&id).unwrap();
+            assert_eq!(context.id(), id);
+            assert_eq!(context.name(), name);
+        }
+        assert!(NodeContext::try_current().is_none());
+    }
+
+    /// An implementation of [`Action`] that outputs the progress of the graph when it runs.
+    struct ProgressAction;
+    #[async_trait]
+    impl Action for ProgressAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, env: Arc<EnvVar>) -> Output {
+            Output::new(env.graph_progress())
+        }
+    }
+
+    /// Test for observing the progress of the graph.
+    ///
+    /// Each of the 3 nodes sees 3 nodes to run and fewer completed, and all are completed once
+    /// the graph has run.
+    #[test]
+    fn test_graph_progress() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut ids = vec![];
+        for name in ["A", "B", "C"] {
+            let node =
+                DefaultNode::with_action(NodeName::from(name), ProgressAction, &mut node_table);
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        assert_eq!(graph.env.graph_progress(), (0, 0));
+
+        graph.start().unwrap();
+        for id in &ids {
+            let (completed, total) = *graph.get_output_as::<(usize, usize)>(id).unwrap();
+            assert_eq!(total, 3);
+            assert!(completed < 3);
+        }
+        assert_eq!(graph.env.graph_progress(), (3, 3));
+    }
+
+    /// An implementation of [`Action`] that outputs 1 with logs, or fails with logs.
+    struct LogAction {
+        fail: bool,
+    }
+    #[async_trait]
+    impl Action for LogAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let out = if self.fail {
+                Output::error("failed".to_string())
+            } else {
+                Output::new(1)
+            };
+            out.with_logs(vec!["read".to_string()])
+                .with_logs(vec!["written".to_string()])
+        }
+    }
+
+    /// Test for collecting the logs of the nodes apart from their outputs.
+    ///
+    /// The logs of a successful node and of a failed node are collected in order, while the
+    /// output and the error are recorded as without logs.
+    #[test]
+    fn test_get_logs() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ok = DefaultNode::with_action(
+            NodeName::from("ok"),
+            LogAction { fail: false },
+            &mut node_table,
+        );
+        let failed = DefaultNode::with_action(
+            NodeName::from("failed"),
+            LogAction { fail: true },
+            &mut node_table,
+        );
+        let silent = DefaultNode::new(NodeName::from("silent"), &mut node_table);
+        let (ok_id, failed_id, silent_id) = (ok.id(), failed.id(), silent.id());
+        graph.add_node(ok);
+        graph.add_node(failed);
+        graph.add_node(silent);
+
+        assert!(graph.start().is_err());
+        let logs = vec!["read".to_string(), "written".to_string()];
+        assert_eq!(graph.get_logs(&ok_id), logs);
+        assert_eq!(*graph.get_output_as::<i32>(&ok_id).unwrap(), 1);
+        assert_eq!(graph.get_logs(&failed_id), logs);
+        let outputs = graph.get_outputs();
+        assert_eq!(outputs[&failed_id].get_err(), Some("failed".to_string()));
+        assert!(!matches!(outputs[&failed_id], Output::WithLogs(..)));
+        assert!(graph.get_logs(&silent_id).is_empty());
+    }
+
+    /// An implementation of [`Action`] that keeps the environment of each of its runs.
+    struct KeepEnvAction(Arc<std::sync::Mutex<Vec<Arc<EnvVar>>>>);
+    #[async_trait]
+    impl Action for KeepEnvAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, env: Arc<EnvVar>) -> Output {
+            self.0.lock().unwrap().push(env);
+            Output::empty()
+        }
+    }
+
+    /// Test for running the graph with a copy of its environment.
+    ///
+    /// Each of two runs gets its own copy, holding the variables of the environment of the
+    /// graph, which is restored after the run.
+    #[test]
+    fn test_run_isolated() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let envs = Arc::new(std::sync::Mutex::new(vec![]));
+        graph.add_node(DefaultNode::with_action(
+            NodeName::from("A"),
+            KeepEnvAction(envs.clone()),
+            &mut node_table,
+        ));
+        let mut env = EnvVar::new(node_table);
+        env.set("threshold", 0.5f64);
+        graph.set_env(env);
+        let base = graph.env.clone();
+
+        graph.run_isolated().unwrap();
+        graph.run_isolated().unwrap();
+        let envs = envs.lock().unwrap();
+        assert_eq!(envs.len(), 2);
+        assert!(!Arc::ptr_eq(&envs[0], &envs[1]));
+        for env in envs.iter() {
+            assert!(!Arc::ptr_eq(env, &base));
+            assert_eq!(env.keys(), base.keys());
+            assert_eq!(env.get::<f64>("threshold"), Some(0.5));
+        }
+        assert!(Arc::ptr_eq(&graph.env, &base));
+    }
+
+    /// Test for running the nodes with a tag.
+    ///
+    /// Step 1: create `A -> B -> C`, tag B, and build D with the tag, while E is not tagged.
+    ///
+    /// Step 2: run the tagged nodes, and verify only B and D ran, along with A which B depends
+    /// on.
+    #[test]
+    fn test_run_tagged() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut ids = vec![];
+        for name in ["A", "B", "C", "D", "E"] {
+            let mut builder = DefaultNode::builder(name, &mut node_table).action(ContextAction);
+            if name == "D" {
+                builder = builder.tags(vec!["io".to_string()]);
+            }
+            let node = builder.build();
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        graph.connect(ids[0], ids[1]).unwrap();
+        graph.connect(ids[1], ids[2]).unwrap();
+        graph.tag_node(ids[1], "io");
+        graph.tag_node(ids[4], "cpu");
+        assert_eq!(graph.get_nodes_by_tag("io"), vec![ids[1], ids[3]]);
+        assert!(graph.get_nodes_by_tag("unknown").is_empty());
+
+        graph.run_tagged("io").unwrap();
+        let ran: Vec<bool> = ids
+            .iter()
+            .map(|id| graph.get_output_as::<NodeContext>(id).is_ok())
+            .collect();
+        assert_eq!(ran, vec![true, true, false, true, false]);
+    }
+
+    /// An implementation of [`Action`] that counts its runs and outputs the count. It fails in
+    /// the first run if `fail_first` is set.
+    struct FlakyAction {
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+        fail_first: bool,
+    }
+    #[async_trait]
+    impl Action for FlakyAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let runs = self.runs.fetch_add(1, Ordering::Relaxed) + 1;
+            if self.fail_first && runs == 1 {
+                Output::error("first run fails".to_string())
+            } else {
+                Output::new(runs)
+            }
+        }
+    }
+
+    /// Test for the partial outputs carried by the error of a failed run.
+    ///
+    /// Run a graph `A -> B` where B fails: the error carries the output of A only.
+    #[test]
+    fn test_partial_outputs() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(
+            NodeName::from("A"),
+            FlakyAction {
+                runs: Arc::default(),
+                fail_first: false,
+            },
+            &mut node_table,
+        );
+        let b = DefaultNode::with_action(
+            NodeName::from("B"),
+            FlakyAction {
+                runs: Arc::default(),
+                fail_first: true,
+            },
+            &mut node_table,
+        );
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        let error = graph.start().unwrap_err();
+        assert!(matches!(error, GraphError::ExecutionFailed { node_id, .. } if node_id == b_id.0));
+        let partial_outputs = error.partial_outputs().unwrap();
+        assert_eq!(partial_outputs.len(), 1);
+        let a_output = partial_outputs[&a_id].get_out().unwrap();
+        assert_eq!(a_output.get::<usize>(), Some(&1));
+        assert!(GraphError::Empty.partial_outputs().is_none());
+
+        // The nested errors share the outputs instead of copying them.
+        let mut errors = GraphError::MultipleErrors(vec![error.clone(), error]);
+        errors.set_partial_outputs(&Arc::new(HashMap::new()));
+        let GraphError::MultipleErrors(errors) = errors else {
+            unreachable!()
+        };
+        assert!(std::ptr::eq(
+            errors[0].partial_outputs().unwrap(),
+            errors[1].partial_outputs().unwrap()
+        ));
+    }
+
+    /// Test for snapshotting and restoring the execution state.
+    ///
+    /// Step 1: run a graph where node A succeeds and node B fails, and take a snapshot.
+    ///
+    /// Step 2: restore the snapshot and run again, verify only B runs, while the output of A
+    /// is kept.
+    #[test]
+    fn test_snapshot_restore() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let (a_runs, b_runs) = (Arc::default(), Arc::default());
+        let a = DefaultNode::with_action(
+            NodeName::from("A"),
+            FlakyAction {
+                runs: Arc::clone(&a_runs),
+                fail_first: false,
+            },
+            &mut node_table,
+        );
+        let b = DefaultNode::with_action(
+            NodeName::from("B"),
+            FlakyAction {
+                runs: Arc::clone(&b_runs),
+                fail_first: true,
+            },
+            &mut node_table,
+        );
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+
+        assert!(graph.start().is_err());
+        let snapshot = graph.snapshot();
+        assert!(snapshot.is_completed(&a_id));
+        assert!(!snapshot.is_completed(&b_id));
+        assert_eq!(snapshot.output(&a_id).unwrap().get::<usize>(), Some(&1));
+
+        graph.reset();
+        graph.restore(snapshot);
+        graph.start().unwrap();
+        assert_eq!(a_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 1);
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 2);
+    }
+
+    /// Test for restoring a snapshot into a new graph.
+    ///
+    /// Step 1: run a graph `A -> B` where A sends 1 to B, which fails, and take a snapshot.
+    ///
+    /// Step 2: build the same graph again, with A adding 100 instead, and restore the snapshot:
+    /// verify A is not run, and B receives the restored output of A.
+    #[test]
+    fn test_restore_sends_outputs() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(
+            NodeName::from("B"),
+            FlakyAction {
+                runs: Arc::default(),
+                fail_first: true,
+            },
+            &mut node_table,
+        );
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+        assert!(graph.start().is_err());
+        let snapshot = graph.snapshot();
+
+        let mut graph = Graph::new();
+        graph.add_node(DefaultNode::with_id(
+            a_id,
+            NodeName::from("A"),
+            AddAction(100),
+        ));
+        graph.add_node(DefaultNode::with_id(
+            b_id,
+            NodeName::from("B"),
+            AddAction(10),
+        ));
+        graph.connect(a_id, b_id).unwrap();
+        graph.restore(snapshot);
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 1);
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 11);
+    }
+
+    /// Test for serializing a snapshot.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_serde() {
+        let id = NodeId(1);
+        let snapshot = Snapshot {
+            completed: HashMap::from([(id, Some(Content::new("done".to_string())))]),
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            snapshot.output(&id).unwrap().get::<String>().unwrap(),
+            "done"
+        );
+
+        let snapshot = Snapshot {
+            completed: HashMap::from([(id, Some(Content::new(HelloAction)))]),
+        };
+        assert!(serde_json::to_string(&snapshot).is_err());
+    }
+
+    /// Test for the status of nodes.
+    ///
+    /// Step 1: connect A to B, and verify A is ready while B waits for its input.
+    ///
+    /// Step 2: send a packet to B, and verify B becomes ready.
+    ///
+    /// Step 3: run the graph with a failing node C, and verify the final status of the nodes.
+    #[test]
+    fn test_node_status() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let c = DefaultNode::with_action(
+            NodeName::from("C"),
+            FlakyAction {
+                runs: Arc::default(),
+                fail_first: true,
+            },
+            &mut node_table,
+        );
+        let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_node(c);
+        graph.connect(a_id, b_id).unwrap();
+
+        assert_eq!(graph.status(&a_id), NodeStatus::Ready);
+        assert_eq!(graph.status(&b_id), NodeStatus::Pending);
+        assert!(!graph.is_ready(&b_id));
+        graph.nodes[&a_id]
+            .blocking_lock()
+            .output_channels()
+            .blocking_send_to(&b_id, Content::new(0usize))
+            .unwrap();
+        assert!(graph.is_ready(&b_id));
+
+        assert!(graph.start().is_err());
+        assert_eq!(graph.status(&a_id), NodeStatus::Done);
+        assert_eq!(graph.status(&b_id), NodeStatus::Done);
+        assert_eq!(graph.status(&c_id), NodeStatus::Failed);
+    }
+
+    /// Test for running a graph with preloaded inputs.
+    ///
+    /// Step 1: connect A to B, and preload 5 into A.
+    ///
+    /// Step 2: run the graph and verify the input flows through the chain, and the input
+    /// channel is removed afterwards.
+    #[test]
+    fn test_run_with_input() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        let unknown = HashMap::from([(NodeId(usize::MAX), vec![])]);
+        assert!(matches!(
+            graph.run_with_input(unknown),
+            Err(GraphError::UnknownNode(usize::MAX))
+        ));
+
+        graph
+            .run_with_input(HashMap::from([(a_id, vec![Content::new(5usize)])]))
+            .unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 6);
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 16);
+        assert!(graph.nodes[&a_id]
+            .blocking_lock()
+            .input_channels()
+            .is_empty());
+    }
+
+    /// Test for streaming packets into a running graph.
+    ///
+    /// Step 1: open the input of A, reading until its input is closed, and open it again,
+    /// disconnecting the first handle.
+    ///
+    /// Step 2: stream a bounded sequence through a small channel while the graph runs, then
+    /// finish the input twice, and verify the run completes with all the packets received,
+    /// and the sends after finishing fail.
+    #[test]
+    fn test_open_input() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), CollectAction, &mut node_table);
+        let a_id = a.id();
+        graph.add_node(a);
+        assert!(matches!(
+            graph.open_input(NodeId(usize::MAX), 1),
+            Err(GraphError::UnknownNode(usize::MAX))
+        ));
+
+        let replaced = graph.open_input(a_id, 2).unwrap();
+        let mut input = graph.open_input(a_id, 2).unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        assert!(matches!(
+            runtime.block_on(replaced.send(Content::new(0usize))),
+            Err(crate::SendErr::ClosedChannel(_))
+        ));
+
+        let (result, send_after_finish) = runtime.block_on(async {
+            futures::join!(graph.run_async(), async {
+                for i in 0..5usize {
+                    input.send(Content::new(i)).await.unwrap();
+                }
+                input.finish();
+                input.finish();
+                assert!(input.is_finished());
+                input.send(Content::new(5usize)).await
+            })
+        });
+        result.unwrap();
+        assert!(matches!(
+            send_after_finish,
+            Err(crate::SendErr::ClosedChannel(_))
+        ));
+        assert_eq!(
+            *graph.get_output_as::<Vec<usize>>(&a_id).unwrap(),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    /// An implementation of [`Action`] that counts up to `self.0` by looping, then broadcasts
+    /// the count.
+    struct CountUpAction(usize);
+    #[async_trait]
+    impl Action for CountUpAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            out_channels: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            let count = match in_channels.recv_any().await {
+                Ok((_, content)) => *content.get::<usize>().unwrap() + 1,
+                Err(_) => 1,
+            };
+            if count < self.0 {
+                return Output::Loop(Content::new(count));
+            }
+            out_channels.broadcast(Content::new(count)).await;
+            Output::new(count)
+        }
+    }
+
+    /// Test for a node running repeatedly with [`Output::Loop`].
+    ///
+    /// Step 1: connect A, which loops until it counts to 5, to B.
+    ///
+    /// Step 2: run the graph and verify B receives the final count, and the loop channel is
+    /// removed afterwards.
+    ///
+    /// Step 3: limit the runs to 3 and verify A fails.
+    #[test]
+    fn test_output_loop() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), CountUpAction(5), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(10), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 5);
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 15);
+        assert!(graph.nodes[&a_id]
+            .blocking_lock()
+            .input_channels()
+            .is_empty());
+
+        let mut graph = Graph::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), CountUpAction(5), &mut node_table);
+        let a_id = a.id();
+        graph.add_node(a);
+        graph.set_max_loop_iterations(3);
+        match graph.start() {
+            Err(GraphError::NodeFailed {
+                node_id,
+                error: NodeError::TooManyIterations(3),
+                ..
+            }) => assert_eq!(node_id, a_id.0),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    /// Test for describing the edges of a graph.
+    ///
+    /// Connect A to B, C and D with a default, a oneshot and a typed edge, plus a feedback edge
+    /// from D back to A, and verify the description of the first three edges.
+    #[test]
+    fn test_get_connection_info() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = ["A", "B", "C", "D"]
+            .into_iter()
+            .map(|name| {
+                let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+        graph.connect(a, b).unwrap();
+        graph.connect_once(a, c).unwrap();
+        graph.add_typed_edge::<u32>(a, d, 4).unwrap();
+        graph.add_feedback_edge(d, a, Content::new(0u32)).unwrap();
+
+        let info = |to, channel_type, capacity, type_id| ConnectionInfo {
+            from: a,
+            to,
+            channel_type,
+            capacity,
+            type_id,
+        };
+        assert_eq!(
+            graph.get_connection_info(),
+            vec![
+                info(b, ChannelType::Mpsc, 32, None),
+                info(c, ChannelType::Once, 1, None),
+                info(d, ChannelType::Mpsc, 4, Some(TypeId::of::<u32>())),
+            ]
+        );
+    }
+
+    /// Test for inspecting the nodes of a graph.
+    ///
+    /// Step 1: connect A to B, and verify the nodes found by id and by name know their
+    /// channels.
+    ///
+    /// Step 2: inside a Tokio runtime, verify the nodes are found, unless they are busy.
+    #[test]
+    fn test_get_node() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::new(NodeName::from("A"), &mut node_table);
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        assert_eq!(
+            graph
+                .get_node(b_id)
+                .unwrap()
+                .input_channels()
+                .get_sender_ids(),
+            vec![a_id]
+        );
+        let mut a = graph.get_node_by_name("A").unwrap();
+        assert_eq!(a.id(), a_id);
+        assert_eq!(a.output_channels().get_receiver_ids(), vec![b_id]);
+        drop(a);
+        assert!(graph.get_node(NodeId(0)).is_none());
+        assert!(graph.get_node_by_name("C").is_none());
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            assert!(graph.get_node(a_id).is_some());
+            assert_eq!(graph.get_connection_info().len(), 1);
+            let busy = graph.nodes[&a_id].lock().await;
+            assert!(graph.get_node(a_id).is_none());
+            assert!(graph.get_node_by_name("A").is_none());
+            assert!(graph.get_node_by_name("B").is_some());
+            assert!(graph.get_connection_info().is_empty());
+            assert!(graph.compute_critical_path().is_none());
+            drop(busy);
+            assert_eq!(graph.compute_critical_path(), Some(vec![a_id, b_id]));
+        });
+    }
+
+    /// Test for checking the nodes and edges before wiring them.
+    ///
+    /// Step 1: connect A to B, and verify the existence checks.
+    ///
+    /// Step 2: add edges from A to B and an unknown node, and verify it fails without adding
+    /// any edge.
+    #[test]
+    fn test_node_exists() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::new(NodeName::from("A"), &mut node_table);
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_edge(a_id, vec![b_id]).unwrap();
+
+        assert!(graph.node_exists(a_id));
+        assert!(!graph.node_exists(NodeId(usize::MAX)));
+        assert!(graph.node_exists_by_name("B"));
+        assert!(!graph.node_exists_by_name("C"));
+        assert!(graph.edge_exists(a_id, b_id));
+        assert!(!graph.edge_exists(b_id, a_id));
+
+        let unknown = NodeId(usize::MAX);
+        assert!(matches!(
+            graph.add_edge(b_id, vec![a_id, unknown]),
+            Err(GraphError::UnknownNode(id)) if id == unknown.0
+        ));
+        assert!(!graph.edge_exists(b_id, a_id));
+        assert!(matches!(
+            graph.connect(unknown, a_id),
+            Err(GraphError::UnknownNode(_))
+        ));
+    }
+
+    /// Test for querying the wiring of the nodes.
+    ///
+    /// A and B both feed C, which feeds back to A: the feedback edge is listed too.
+    #[test]
+    fn test_inputs_outputs_of() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = ["A", "B", "C"]
+            .into_iter()
+            .map(|name| {
+                let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        let (a_id, b_id, c_id) = (ids[0], ids[1], ids[2]);
+        graph.connect(a_id, c_id).unwrap();
+        graph.connect(b_id, c_id).unwrap();
+        graph
+            .add_feedback_edge(c_id, a_id, Content::new(0usize))
+            .unwrap();
+
+        assert_eq!(graph.inputs_of(c_id), vec![a_id, b_id]);
+        assert_eq!(graph.outputs_of(c_id), vec![a_id]);
+        assert_eq!(graph.inputs_of(a_id), vec![c_id]);
+        assert_eq!(graph.outputs_of(b_id), vec![c_id]);
+        assert!(graph.inputs_of(NodeId(usize::MAX)).is_empty());
+    }
+
+    /// Test for consuming the outputs of the nodes as they complete.
+    ///
+    /// Connect A to B, both adding to the number received, run the graph while polling the
+    /// output stream, and verify it yields the output of both nodes. A may be reported after B,
+    /// as B can complete before the task of A reports its output.
+    #[test]
+    fn test_output_stream() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(2), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let outputs = graph.output_stream();
+        let (result, outputs) = runtime
+            .block_on(async { futures::join!(graph.run_async(), outputs.collect::<Vec<_>>()) });
+        result.unwrap();
+        let mut outputs: Vec<(NodeId, usize)> = outputs
+            .into_iter()
+            .map(|(id, output)| (id, *output.get_out().unwrap().get::<usize>().unwrap()))
+            .collect();
+        outputs.sort();
+        assert_eq!(outputs, vec![(a_id, 1), (b_id, 3)]);
+    }
+
+    /// Test for the output stream of a node panicking after an await.
+    ///
+    /// Run A, panicking once polled, with B, and verify the stream yields the panic of A and
+    /// the output of B.
+    #[test]
+    fn test_output_stream_panic() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), PanicAction, &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(2), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let outputs = graph.output_stream();
+        let (result, outputs) = runtime
+            .block_on(async { futures::join!(graph.run_async(), outputs.collect::<Vec<_>>()) });
+        assert!(matches!(result, Err(GraphError::PanicOccurred { .. })));
+        let outputs: HashMap<NodeId, Output> = outputs.into_iter().collect();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[&a_id].get_err(), Some("panic occurred".to_string()));
+        assert_eq!(outputs[&b_id].get_out().unwrap().get::<usize>(), Some(&2));
+    }
+
+    /// Test for the callbacks called as the nodes finish.
+    ///
+    /// Connect A to B, add a failing node C, and register two completion callbacks and an
+    /// error callback: both completion callbacks see the 3 nodes, A before B, while the error
+    /// callback only sees C.
+    #[test]
+    fn test_node_callbacks() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(2), &mut node_table);
+        let c = DefaultNode::with_action(
+            NodeName::from("C"),
+            LogAction { fail: true },
+            &mut node_table,
+        );
+        let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_node(c);
+        graph.connect(a_id, b_id).unwrap();
+
+        let completed = Arc::new(std::sync::Mutex::new(vec![]));
+        for _ in 0..2 {
+            let completed = completed.clone();
+            graph.on_node_complete(move |id, output| {
+                completed.lock().unwrap().push((id, output.is_err()));
+            });
+        }
+        let failed = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = failed.clone();
+        graph.on_node_error(move |id, error| sink.lock().unwrap().push((id, error.to_string())));
+
+        assert!(graph.start().is_err());
+        let completed = completed.lock().unwrap();
+        assert_eq!(completed.len(), 6);
+        assert_eq!(completed.iter().filter(|(id, _)| *id == c_id).count(), 2);
+        assert!(completed
+            .iter()
+            .all(|(id, is_err)| *is_err == (*id == c_id)));
+        let position = |id| completed.iter().position(|(i, _)| *i == id).unwrap();
+        assert!(position(a_id) < position(b_id));
+        let failed = failed.lock().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, c_id);
+        assert!(failed[0].1.contains("failed"));
+    }
+
+    /// An implementation of [`Action`] that panics after its first await.
+    struct PanicAction;
+    #[async_trait]
+    impl Action for PanicAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            tokio::task::yield_now().await;
+            panic!("node panicked while polled");
+        }
+    }
+
+    /// Test for a node panicking after an await.
+    ///
+    /// Register a completion and an error callback, run a node panicking once polled, and verify
+    /// the run fails with the panic and both callbacks see it.
+    #[test]
+    fn test_panic_after_await() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let node = DefaultNode::with_action(NodeName::from("A"), PanicAction, &mut node_table);
+        let id = node.id();
+        graph.add_node(node);
+        let completed = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = completed.clone();
+        graph.on_node_complete(move |id, output| {
+            sink.lock().unwrap().push((id, output.get_err()));
+        });
+        let failed = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = failed.clone();
+        graph.on_node_error(move |id, error| sink.lock().unwrap().push((id, error.to_string())));
+
+        let error = graph.start().unwrap_err();
+        assert!(matches!(error, GraphError::PanicOccurred { node_id, .. } if node_id == id.0));
+        assert_eq!(
+            *completed.lock().unwrap(),
+            vec![(id, Some("panic occurred".to_string()))]
+        );
+        assert_eq!(failed.lock().unwrap().len(), 1);
+        assert!(graph.execute_states[&id].is_finished());
+    }
+
+    /// Test for comparing the structure of graphs.
+    ///
+    /// Step 1: build `A -> B -> C` twice, adding the nodes and edges in reverse order the second
+    /// time, so that the ids differ, and verify the graphs are equal.
+    ///
+    /// Step 2: add `A -> C` to the second graph only, and verify they are not equal anymore.
+    #[test]
+    fn test_structural_eq() {
+        let build = |names: [&str; 3], edges: &[(usize, usize)]| {
+            let mut graph = Graph::new();
+            let mut node_table = NodeTable::new();
+            let ids: Vec<NodeId> = names
+                .into_iter()
+                .map(|name| {
+                    let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                    let id = node.id();
+                    graph.add_node(node);
+                    id
+                })
+                .collect();
+            for &(from, to) in edges {
+                graph.connect(ids[from], ids[to]).unwrap();
+            }
+            (graph, ids)
+        };
+        let (first, _) = build(["A", "B", "C"], &[(0, 1), (1, 2)]);
+        let (mut second, ids) = build(["C", "B", "A"], &[(1, 0), (2, 1)]);
+        assert!(first.structural_eq(&second));
+        assert!(second.structural_eq(&first));
+
+        second.connect(ids[2], ids[0]).unwrap();
+        assert!(!first.structural_eq(&second));
+    }
+
+    /// Test for dropping the outputs of the intermediate nodes.
+    ///
+    /// Connect A to B, both adding to the number received, with A not retaining its output:
+    /// B still receives from A, and only the output of B is kept.
+    #[test]
+    fn test_retain_output() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(2), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        a.set_retain_output(false);
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect(a_id, b_id).unwrap();
+
+        graph.start().unwrap();
+        let outputs = graph.get_outputs();
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs.contains_key(&b_id));
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 3);
+        assert!(matches!(
+            graph.get_output_as::<usize>(&a_id),
+            Err(OutputError::NoOutput(_))
+        ));
+    }
+
+    /// Test for getting the outputs sorted by node id.
+    ///
+    /// 8 independent nodes, each outputting its own number, are listed in the order of their
+    /// ids, which is the order they were created in.
+    #[test]
+    fn test_get_outputs_ordered() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut ids = vec![];
+        for i in 0..8 {
+            let node = DefaultNode::with_action(format!("N{i}"), AddAction(i), &mut node_table);
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+
+        graph.start().unwrap();
+        let outputs: Vec<(NodeId, usize)> = graph
+            .get_outputs_ordered()
+            .into_iter()
+            .map(|(id, output)| (id, *output.get_out().unwrap().get::<usize>().unwrap()))
+            .collect();
+        assert_eq!(outputs, ids.into_iter().zip(0..8).collect::<Vec<_>>());
+    }
+
+    /// Test for assembling a graph from two graphs built independently.
+    ///
+    /// Step 1: build `A -> B` and `C`, merge them, connect B to C, and verify the output of C.
+    ///
+    /// Step 2: merge a graph holding another node named C, and verify it fails.
+    #[test]
+    fn test_merge() {
+        let mut node_table = NodeTable::new();
+        let mut first = Graph::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(2), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        first.add_node(a);
+        first.add_node(b);
+        first.add_edge(a_id, vec![b_id]).unwrap();
+        let mut second = Graph::new();
+        let c = DefaultNode::with_action(NodeName::from("C"), AddAction(3), &mut node_table);
+        let c_id = c.id();
+        second.add_node(c);
+
+        let mut graph = first.merge(second).unwrap();
+        graph.add_edge(b_id, vec![c_id]).unwrap();
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&c_id).unwrap(), 6);
+
+        let mut other = Graph::new();
+        other.add_node(DefaultNode::new(NodeName::from("C"), &mut node_table));
+        assert!(matches!(
+            graph.merge(other),
+            Err(GraphError::DuplicateNode(name)) if name == "C"
+        ));
+    }
+
+    /// Test for running a graph inside an existing runtime.
+    ///
+    /// Run the same graph with `run_async` and `run_on` from a task of a multi-threaded runtime,
+    /// and verify the output both times.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_in_runtime() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let node = DefaultNode::with_action(NodeName::from("Hello"), HelloAction, &mut node_table);
+        let id = node.id();
+        graph.add_node(node);
+
+        graph.run_async().await.unwrap();
+        assert_eq!(*graph.get_output_as::<String>(&id).unwrap(), "Hello world");
+        graph.reset();
+        graph.run_on(&tokio::runtime::Handle::current()).unwrap();
+        assert_eq!(*graph.get_output_as::<String>(&id).unwrap(), "Hello world");
+    }
+
+    /// Test for running a graph on the runtime of an application from outside of it.
+    ///
+    /// Run the graph with the handle of a runtime from the thread owning it, then from
+    /// another thread, as an application embedding the graph would, and verify the output
+    /// both times.
+    #[test]
+    fn test_run_on_handle() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let node = DefaultNode::with_action(NodeName::from("Hello"), HelloAction, &mut node_table);
+        let id = node.id();
+        graph.add_node(node);
+
+        graph.run_on(runtime.handle()).unwrap();
+        assert_eq!(*graph.get_output_as::<String>(&id).unwrap(), "Hello world");
+        graph.reset();
+        let handle = runtime.handle().clone();
+        let graph = std::thread::spawn(move || {
+            graph.run_on(&handle).unwrap();
+            graph
+        })
+        .join()
+        .unwrap();
+        assert_eq!(*graph.get_output_as::<String>(&id).unwrap(), "Hello world");
+    }
+
+    /// An action hanging in its first run, and outputting the number of runs afterwards.
+    struct StallOnceAction(std::sync::atomic::AtomicUsize);
+    #[async_trait]
+    impl Action for StallOnceAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let runs = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            if runs == 1 {
+                futures::future::pending::<()>().await;
+            }
+            Output::new(runs)
+        }
+    }
+
+    /// Test for restarting a stalled node.
+    ///
+    /// Step 1: create a node hanging in its first run, and set a watchdog of 100ms.
+    ///
+    /// Step 2: run the graph, and verify the node is restarted and completes in its second run.
+    #[test]
+    fn test_watchdog() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let node = DefaultNode::with_action(
+            NodeName::from("Stall"),
+            StallOnceAction(Default::default()),
+            &mut node_table,
+        );
+        let id = node.id();
+        graph.add_node(node);
+        graph.set_watchdog(Duration::from_millis(100));
+
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&id).unwrap(), 2);
+    }
+
+    /// An action hanging in every run, and counting its runs.
+    struct StallAction(Arc<std::sync::atomic::AtomicUsize>);
+    #[async_trait]
+    impl Action for StallAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            futures::future::pending().await
+        }
+    }
+
+    /// Test for giving up on a node stalling in every run.
+    ///
+    /// Create a node hanging in every run, set a watchdog of 50ms restarting it at most twice,
+    /// and verify it fails with a timeout after running 3 times.
+    #[test]
+    fn test_watchdog_max_restarts() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let node = DefaultNode::with_action(
+            NodeName::from("Stall"),
+            StallAction(runs.clone()),
+            &mut node_table,
+        );
+        graph.add_node(node);
+        graph.set_watchdog(Duration::from_millis(50));
+        graph.set_max_watchdog_restarts(2);
+
+        let result = graph.run_with_deadline(Duration::from_secs(10));
+        assert!(
+            matches!(
+                result,
+                Err(GraphError::NodeFailed {
+                    error: NodeError::Timeout(timeout),
+                    ..
+                }) if timeout == Duration::from_millis(50)
+            ),
+            "unexpected result: {:?}",
+            result
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    /// Test for aborting a run exceeding its deadline.
+    ///
+    /// Step 1: create a node hanging in its first run, and a node completing at once.
+    ///
+    /// Step 2: run the graph with a deadline of 100ms, and verify it returns promptly, with only
+    /// the hanging node unfinished.
+    #[test]
+    fn test_run_with_deadline() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let stuck = DefaultNode::with_action(
+            NodeName::from("Stuck"),
+            StallOnceAction(Default::default()),
+            &mut node_table,
+        );
+        let done = DefaultNode::new(NodeName::from("Done"), &mut node_table);
+        let stuck_id = stuck.id();
+        graph.add_node(stuck);
+        graph.add_node(done);
+
+        let started_at = Instant::now();
+        let result = graph.run_with_deadline(Duration::from_millis(100));
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+        assert!(matches!(
+            result,
+            Err(GraphError::DeadlineExceeded { unfinished }) if unfinished == vec![stuck_id]
+        ));
+    }
+
+    /// An action sending 3 packets to all its successors, without receiving.
+    struct FloodAction;
+    #[async_trait]
+    impl Action for FloodAction {
+        async fn run(&self, _: &mut InChannels, out: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            for i in 0..3usize {
+                out.broadcast(Content::new(i)).await;
+            }
+            Output::empty()
+        }
+    }
+
+    /// Test for detecting two nodes deadlocked on sending to each other.
+    ///
+    /// Step 1: connect A to B with a channel of capacity 1, and B back to A with a feedback edge,
+    /// full with its initial value. Both nodes send 3 packets without receiving.
+    ///
+    /// Step 2: set a watchdog of 100ms, and verify the run fails with the deadlock.
+    #[test]
+    fn test_watchdog_deadlock() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let [a, b] = ["A", "B"].map(|name| {
+            let node = DefaultNode::with_action(NodeName::from(name), FloodAction, &mut node_table);
+            let id = node.id();
+            graph.add_node(node);
+            id
+        });
+        graph.add_typed_edge::<usize>(a, b, 1).unwrap();
+        graph.add_feedback_edge(b, a, Content::new(0usize)).unwrap();
+        graph.set_watchdog(Duration::from_millis(100));
+
+        // Once a deadlocked node fails, its channels are closed, and the other one may complete.
+        let errors = match graph.start() {
+            Err(GraphError::MultipleErrors(errors)) => errors,
+            Err(error) => vec![error],
+            Ok(()) => panic!("the deadlocked nodes should fail"),
+        };
+        for error in errors {
+            assert!(
+                matches!(
+                    error,
+                    GraphError::NodeFailed {
+                        error: NodeError::Deadlock,
+                        ..
+                    }
+                ),
+                "unexpected error: {}",
+                error
+            );
+        }
+    }
+
+    /// An action blocked sending in its first run, hanging in its second run, and outputting
+    /// the number of runs afterwards.
+    struct DeadlockThenStallAction(std::sync::atomic::AtomicUsize);
+    #[async_trait]
+    impl Action for DeadlockThenStallAction {
+        async fn run(&self, _: &mut InChannels, out: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let runs = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            match runs {
+                1 => {
+                    for i in 0..3usize {
+                        out.broadcast(Content::new(i)).await;
+                    }
+                }
+                2 => futures::future::pending::<()>().await,
+                _ => {}
+            }
+            Output::new(runs)
+        }
+    }
+
+    /// Test for restarting a stalled node after a run where it was deadlocked.
+    ///
+    /// Step 1: connect A to B with a channel of capacity 1, and only let one node run at a time,
+    /// so that A is deadlocked on sending to B in its first run. Verify the run fails with the
+    /// deadlock.
+    ///
+    /// Step 2: run the graph again, where A hangs once, and verify it is restarted rather than
+    /// failing with a deadlock.
+    #[test]
+    fn test_watchdog_after_deadlock() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(
+            NodeName::from("A"),
+            DeadlockThenStallAction(Default::default()),
+            &mut node_table,
+        );
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_typed_edge::<usize>(a_id, b_id, 1).unwrap();
+        graph.set_parallelism(1);
+        graph.set_watchdog(Duration::from_millis(100));
+
+        assert!(matches!(
+            graph.start(),
+            Err(GraphError::NodeFailed {
+                error: NodeError::Deadlock,
+                ..
+            })
+        ));
+
+        graph.reset();
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 3);
+    }
+
+    /// Test for finding and pruning the nodes disconnected from the sources and sinks.
+    ///
+    /// Step 1: build `A -> B`, and `Q <-> T`, `Q -> S`, `Q -> P`, `P <-> R`, where P and R are
+    /// neither reachable from a source nor ancestors of a sink.
+    ///
+    /// Step 2: verify the reachable nodes of A and Q.
+    ///
+    /// Step 3: prune the graph and verify only P and R are removed, with the channel from Q.
+    #[test]
+    fn test_prune_unreachable_nodes() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let ids: Vec<NodeId> = ["A", "B", "Q", "T", "S", "P", "R"]
+            .into_iter()
+            .map(|name| {
+                let node = DefaultNode::new(NodeName::from(name), &mut node_table);
+                let id = node.id();
+                graph.add_node(node);
+                id
+            })
+            .collect();
+        let [a, b, q, t, s, p, r] = ids[..] else {
+            unreachable!()
+        };
+        for (from, to) in [(a, b), (q, t), (t, q), (q, s), (q, p), (p, r), (r, p)] {
+            graph.connect(from, to).unwrap();
+        }
+
+        assert_eq!(graph.reachable_from(a), HashSet::from([b]));
+        assert_eq!(graph.reachable_from(q), HashSet::from([q, t, s, p, r]));
+
+        let mut pruned = vec![p, r];
+        pruned.sort();
+        assert_eq!(graph.prune_unreachable_nodes(), pruned);
+        assert_eq!(graph.nodes.len(), 5);
+        assert!(!graph.has_edge(q, p));
+        assert_eq!(
+            graph.nodes[&q]
+                .blocking_lock()
+                .output_channels()
+                .get_receiver_ids()
+                .len(),
+            2
+        );
+        assert!(graph.prune_unreachable_nodes().is_empty());
+    }
+
+    /// Test for running a graph without any node, or without any node able to start.
+    ///
+    /// Step 1: run an empty graph and verify it returns `Empty`.
+    ///
+    /// Step 2: run a graph with only a loop subgraph `A <-> B`, and verify it returns
+    /// `NoSource` instead of waiting forever.
+    #[test]
+    fn test_empty_and_sourceless_graph() {
+        let mut graph = Graph::new();
+        assert!(matches!(graph.start(), Err(GraphError::Empty)));
+
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::new(NodeName::from("A"), &mut node_table);
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        let mut subgraph = LoopSubgraph::new(NodeName::from("A <-> B"), &mut node_table);
+        subgraph.add_node(a);
+        subgraph.add_node(b);
+        graph.add_node(subgraph);
+        graph.connect(a_id, b_id).unwrap();
+        graph.connect(b_id, a_id).unwrap();
+
+        assert!(matches!(graph.start(), Err(GraphError::NoSource)));
+    }
+
+    /// A [`Node`] with a given weight that does nothing.
+    struct WeightedNode {
+        id: NodeId,
+        name: NodeName,
+        in_channels: InChannels,
+        out_channels: OutChannels,
+        weight: u64,
+    }
+
+    impl WeightedNode {
+        fn new(name: &str, weight: u64, node_table: &mut NodeTable) -> Self {
+            Self {
+                id: node_table.alloc_id_for(name),
+                name: NodeName::from(name),
+                in_channels: InChannels::default(),
+                out_channels: OutChannels::default(),
+                weight,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Node for WeightedNode {
+        fn id(&self) -> NodeId {
+            self.id
+        }
+
+        fn name(&self) -> NodeName {
+            self.name.clone()
+        }
+
+        fn input_channels(&mut self) -> &mut InChannels {
+            &mut self.in_channels
+        }
+
+        fn output_channels(&mut self) -> &mut OutChannels {
+            &mut self.out_channels
+        }
+
+        async fn run(&mut self, _: Arc<EnvVar>) -> Output {
+            Output::empty()
+        }
+
+        fn weight(&self) -> u64 {
+            self.weight
+        }
+    }
+
+    /// Test for the critical path of a graph.
+    ///
+    /// Step 1: build a diamond `A -> B -> D`, `A -> C -> D` where every node weighs 1, and
+    /// verify the path through B is chosen by the smaller id.
+    ///
+    /// Step 2: make C weigh 5 and verify the path goes through C instead.
+    #[test]
+    fn test_compute_critical_path() {
+        assert_eq!(Graph::new().compute_critical_path(), Some(vec![]));
+
+        for (c_weight, through_c) in [(1, false), (5, true)] {
+            let mut graph = Graph::new();
+            let mut node_table = NodeTable::new();
+            let ids: Vec<NodeId> = [("A", 1), ("B", 1), ("C", c_weight), ("D", 1)]
+                .into_iter()
+                .map(|(name, weight)| {
+                    let node = WeightedNode::new(name, weight, &mut node_table);
+                    let id = node.id();
+                    graph.add_node(node);
+                    id
+                })
+                .collect();
+            let [a, b, c, d] = ids[..] else {
+                unreachable!()
+            };
+            for (from, to) in [(a, b), (a, c), (b, d), (c, d)] {
+                graph.connect(from, to).unwrap();
+            }
+
+            let middle = if through_c { c } else { b };
+            assert_eq!(graph.compute_critical_path(), Some(vec![a, middle, d]));
+        }
+    }
+
+    #[cfg(feature = "resource-aware")]
+    struct ResourceNode {
+        id: NodeId,
+        name: NodeName,
+        in_channels: InChannels,
+        out_channels: OutChannels,
+        requirements: ResourceRequirements,
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[cfg(feature = "resource-aware")]
+    #[async_trait]
+    impl Node for ResourceNode {
+        fn id(&self) -> NodeId {
+            self.id
+        }
+
+        fn name(&self) -> NodeName {
+            self.name.clone()
+        }
+
+        fn input_channels(&mut self) -> &mut InChannels {
+            &mut self.in_channels
+        }
+
+        fn output_channels(&mut self) -> &mut OutChannels {
+            &mut self.out_channels
+        }
+
+        async fn run(&mut self, _: Arc<EnvVar>) -> Output {
+            // Wait for the inputs, if any, before running.
+            self.in_channels.map(|content| content.unwrap()).await;
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            self.out_channels.broadcast(Content::new(())).await;
+            Output::empty()
+        }
+
+        fn resource_requirements(&self) -> ResourceRequirements {
+            self.requirements
+        }
+    }
+
+    /// Test for limiting the resources of the running nodes.
+    ///
+    /// Step 1: create 4 independent nodes requiring 0.6 CPU each, and limit the graph to 1 CPU.
+    ///
+    /// Step 2: run the graph, and verify that all the nodes run, but never two at a time.
+    #[cfg(feature = "resource-aware")]
+    #[test]
+    fn test_resource_limits() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut ids = vec![];
+        for i in 0..4 {
+            let name = format!("Node {}", i);
+            let node = ResourceNode {
+                id: node_table.alloc_id_for(&name),
+                name: NodeName::from(name.as_str()),
+                in_channels: InChannels::default(),
+                out_channels: OutChannels::default(),
+                requirements: ResourceRequirements {
+                    cpus: 0.6,
+                    memory_mb: 512,
+                },
+                running: running.clone(),
+                peak: peak.clone(),
+            };
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        graph.set_resource_limits(ResourceRequirements {
+            cpus: 1.0,
+            memory_mb: 4096,
+        });
+
+        graph.start().unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(ids.iter().all(|id| graph.execute_states[id].is_success()));
+    }
+
+    /// Test for limiting the resources of a chain spawned downstream-first.
+    ///
+    /// Step 1: create 4 nodes requiring 0.6 CPU each, chained from the last one created to the
+    /// first one, so that the downstream nodes are spawned first, and limit the graph to 1 CPU.
+    ///
+    /// Step 2: run the graph, and verify all the nodes run one at a time before the deadline, as
+    /// each node gets the resources after its predecessor.
+    #[cfg(feature = "resource-aware")]
+    #[test]
+    fn test_resource_limits_chain() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut ids = vec![];
+        for i in 0..4 {
+            let name = format!("Node {}", i);
+            let node = ResourceNode {
+                id: node_table.alloc_id_for(&name),
+                name: NodeName::from(name.as_str()),
+                in_channels: InChannels::default(),
+                out_channels: OutChannels::default(),
+                requirements: ResourceRequirements {
+                    cpus: 0.6,
+                    memory_mb: 512,
+                },
+                running: running.clone(),
+                peak: peak.clone(),
+            };
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        for pair in ids.windows(2) {
+            graph.connect(pair[1], pair[0]).unwrap();
+        }
+        graph.set_resource_limits(ResourceRequirements {
+            cpus: 1.0,
+            memory_mb: 4096,
+        });
+
+        graph.run_with_deadline(Duration::from_secs(10)).unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(ids.iter().all(|id| graph.execute_states[id].is_success()));
+    }
+
+    struct PeakAction {
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+    #[async_trait]
+    impl Action for PeakAction {
+        async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Output::empty()
+        }
+    }
+
+    /// Test for bounding the number of running nodes.
+    ///
+    /// Step 1: create 6 independent nodes, and limit the parallelism of the graph to 2.
+    ///
+    /// Step 2: run the graph, and verify that all the nodes run, but never more than 2 at a time.
+    #[test]
+    fn test_parallelism() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut ids = vec![];
+        for i in 0..6 {
+            let action = PeakAction {
+                running: running.clone(),
+                peak: peak.clone(),
+            };
+            let node = DefaultNode::with_action(
+                NodeName::from(format!("Node {}", i)),
+                action,
+                &mut node_table,
+            );
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        graph.set_parallelism(2);
+
+        graph.start().unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert!(ids.iter().all(|id| graph.execute_states[id].is_success()));
+    }
+
+    /// Test for the parallelism of a chain spawned downstream-first.
+    ///
+    /// Step 1: create 6 nodes, and chain them from the last one created to the first one, so
+    /// that the downstream nodes have the lower ids and the higher priority.
+    ///
+    /// Step 2: limit the parallelism to 1, run the graph, and verify the value flows through
+    /// the chain before the deadline, as each node gets the permit after its predecessor.
+    #[test]
+    fn test_parallelism_chain() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut ids = vec![];
+        for i in 0..6 {
+            let node = DefaultNode::with_action(
+                NodeName::from(format!("Node {}", i)),
+                AddAction(1),
+                &mut node_table,
+            );
+            ids.push(node.id());
+            graph.add_node(node);
+        }
+        for pair in ids.windows(2) {
+            graph.connect(pair[1], pair[0]).unwrap();
+        }
+        graph.set_parallelism(1);
+
+        graph.run_with_deadline(Duration::from_secs(10)).unwrap();
+        let output = graph.get_outputs()[&ids[0]].get_out().unwrap();
+        assert_eq!(output.get::<usize>(), Some(&6));
+    }
+
+    /// Test for the packet counters of the edges.
+    ///
+    /// Connect A to B and C, run the graph, and verify a packet is sent and received through
+    /// both edges, without any dropped.
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_edge_stats() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), AddAction(1), &mut node_table);
+        let b = DefaultNode::with_action(NodeName::from("B"), AddAction(1), &mut node_table);
+        let c = DefaultNode::with_action(NodeName::from("C"), AddAction(1), &mut node_table);
+        let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_node(c);
+        graph.add_edge(a_id, vec![b_id, c_id]).unwrap();
+
+        graph.start().unwrap();
+        let expected = EdgeStats {
+            sent: 1,
+            received: 1,
+            dropped: 0,
+        };
+        assert_eq!(
+            graph.edge_stats(),
+            HashMap::from([((a_id, b_id), expected), ((a_id, c_id), expected)])
+        );
+    }
+
+    /// An action sending the numbers from 0 to `self.0` to all its successors.
+    struct RepeatAction(usize);
+    #[async_trait]
+    impl Action for RepeatAction {
+        async fn run(&self, _: &mut InChannels, out: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            for i in 0..self.0 {
+                out.broadcast(Content::new(i)).await;
+            }
+            Output::empty()
+        }
+    }
+
+    /// Test for counting the packets sent through each edge.
+    ///
+    /// Connect A, sending 5 packets, to B and C, and C, sending 3 packets, to B: run the graph
+    /// and verify the counts of the 3 edges.
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_edge_message_counts() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), RepeatAction(5), &mut node_table);
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let c = DefaultNode::with_action(NodeName::from("C"), RepeatAction(3), &mut node_table);
+        let (a_id, b_id, c_id) = (a.id(), b.id(), c.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_node(c);
+        graph.add_edge(a_id, vec![b_id, c_id]).unwrap();
+        graph.add_edge(c_id, vec![b_id]).unwrap();
+
+        graph.start().unwrap();
+        assert_eq!(
+            graph.edge_message_counts(),
+            HashMap::from([((a_id, b_id), 5), ((a_id, c_id), 5), ((c_id, b_id), 3)])
+        );
+    }
+
+    /// An action receiving the numbers from node `from` after `delay`, until it receives
+    /// `last`, skipping the lagged ones. Outputs the number of packets received.
+    struct SlowConsumerAction {
+        from: NodeId,
+        last: usize,
+        delay: Duration,
+    }
+    #[async_trait]
+    impl Action for SlowConsumerAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            _: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            time::sleep(self.delay).await;
+            let mut received = 0usize;
+            loop {
+                match in_channels.recv_from(&self.from).await {
+                    Ok(content) => {
+                        received += 1;
+                        if content.get::<usize>() == Some(&self.last) {
+                            break;
+                        }
+                    }
+                    Err(crate::RecvErr::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+            Output::new(received)
+        }
+    }
+
+    /// A logger keeping the warnings, to check them in the tests.
+    struct WarningLogger(std::sync::Mutex<Vec<String>>);
+    impl log::Log for WarningLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+    static WARNINGS: WarningLogger = WarningLogger(std::sync::Mutex::new(Vec::new()));
+
+    /// Test for a broadcast edge with slow consumers.
+    ///
+    /// Step 1: connect A, sending 20 packets, to B and C with a broadcast channel of 4 packets.
+    /// B and C only start receiving once A is done.
+    ///
+    /// Step 2: run the graph, and verify that both receive the last 4 packets only, that the
+    /// 16 others are counted as dropped, and that a warning names each slow consumer.
+    #[test]
+    fn test_connect_broadcast() {
+        let _ = log::set_logger(&WARNINGS);
+        log::set_max_level(log::LevelFilter::Warn);
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), RepeatAction(20), &mut node_table);
+        let (a_id, mut consumers) = (a.id(), vec![]);
+        graph.add_node(a);
+        for name in ["B", "C"] {
+            let action = SlowConsumerAction {
+                from: a_id,
+                last: 19,
+                delay: Duration::from_millis(100),
+            };
+            let node = DefaultNode::with_action(NodeName::from(name), action, &mut node_table);
+            consumers.push(node.id());
+            graph.add_node(node);
+        }
+        graph.connect_broadcast(a_id, &consumers, 4).unwrap();
+        assert!(matches!(
+            graph.connect_broadcast(a_id, &consumers[..1], 4),
+            Err(GraphError::DuplicateEdge { .. })
+        ));
+        graph.set_lag_warning_threshold(10);
+
+        graph.start().unwrap();
+        let outputs = graph.get_outputs();
+        #[cfg(feature = "metrics")]
+        let stats = graph.edge_stats();
+        let warnings = WARNINGS.0.lock().unwrap();
+        for id in consumers {
+            assert_eq!(outputs[&id].get_out().unwrap().get::<usize>(), Some(&4));
+            let mut node = graph.nodes[&id].blocking_lock();
+            assert_eq!(node.input_channels().messages_dropped(&a_id), 16);
+            #[cfg(feature = "metrics")]
+            assert_eq!(
+                stats[&(a_id, id)],
+                EdgeStats {
+                    sent: 20,
+                    received: 4,
+                    dropped: 16,
+                }
+            );
+            let warning = format!(
+                "Slow consumer [id: {}] lagged behind node {}, 16 packets dropped",
+                id.0, a_id.0
+            );
+            assert!(warnings.iter().any(|w| w.starts_with(&warning)));
+        }
+    }
+
+    /// An action receiving once from node `self.0` after a delay, and outputting the number of
+    /// packets it lagged behind.
+    struct LagOnceAction(NodeId);
+    #[async_trait]
+    impl Action for LagOnceAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            _: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            time::sleep(Duration::from_millis(100)).await;
+            match in_channels.recv_from(&self.0).await {
+                Err(crate::RecvErr::Lagged(dropped)) => Output::new(dropped),
+                _ => Output::empty(),
+            }
+        }
+    }
+
+    /// Test for fast-forwarding a lagging broadcast edge.
+    ///
+    /// Step 1: connect A, sending 20 packets, to B with a broadcast channel of 2 packets, and
+    /// fast-forward the edge on the first lag. B only receives once A is done.
+    ///
+    /// Step 2: run the graph, and verify that B skips all the 20 packets at once, which are
+    /// counted as dropped and reported by an event.
+    ///
+    /// Step 3: verify a fast-forward is rejected for an edge which does not exist.
+    #[test]
+    fn test_set_fast_forward() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), RepeatAction(20), &mut node_table);
+        let a_id = a.id();
+        let b = DefaultNode::with_action(NodeName::from("B"), LagOnceAction(a_id), &mut node_table);
+        let b_id = b.id();
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.connect_broadcast(a_id, &[b_id], 2).unwrap();
+        graph.set_fast_forward(a_id, b_id, 1).unwrap();
+        let mut events = graph.event_stream();
+
+        graph.start().unwrap();
+        let outputs = graph.get_outputs();
+        assert_eq!(outputs[&b_id].get_out().unwrap().get::<u64>(), Some(&20));
+        let mut node = graph.nodes[&b_id].blocking_lock();
+        assert_eq!(node.input_channels().messages_dropped(&a_id), 20);
+        drop(node);
+        #[cfg(feature = "metrics")]
+        assert_eq!(graph.edge_stats()[&(a_id, b_id)].dropped, 20);
+        let mut fast_forwarded = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let GraphEvent::FastForwarded { from, to, dropped } = event {
+                fast_forwarded.push((from, to, dropped));
+            }
+        }
+        assert_eq!(fast_forwarded, vec![(a_id, b_id, 20)]);
+
+        assert!(matches!(
+            graph.set_fast_forward(b_id, a_id, 1),
+            Err(GraphError::UnknownEdge { from, to }) if from == b_id.0 && to == a_id.0
+        ));
+    }
+
+    /// An action broadcasting 0, 1 and 2, and closing its edge to node `self.0` after the
+    /// first packet, then the other edges. Outputs the number of sends which failed as the
+    /// edge is closed.
+    struct CloseOneAction(NodeId);
+    #[async_trait]
+    impl Action for CloseOneAction {
+        async fn run(&self, _: &mut InChannels, out: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+            out.broadcast(Content::new(0usize)).await;
+            out.close_to(&self.0).await;
+            let mut closed = 0usize;
+            for i in 1..3usize {
+                for result in out.broadcast(Content::new(i)).await {
+                    match result {
+                        Ok(()) => {}
+                        Err(crate::SendErr::ClosedChannel(_)) => closed += 1,
+                        Err(error) => return Output::Err(error.to_string()),
+                    }
+                }
+            }
+            if let Err(crate::SendErr::ClosedChannel(_)) =
+                out.send_to(&self.0, Content::new(3usize)).await
+            {
+                closed += 1;
+            }
+            for id in out.get_receiver_ids() {
+                out.close_to(&id).await;
+            }
+            Output::new(closed)
+        }
+    }
+
+    /// Test for closing one edge of a broadcast channel.
+    ///
+    /// Step 1: connect A to B, C and D with a single broadcast channel. A closes its edge to B
+    /// after sending a first packet, then sends two more and closes the other edges.
+    ///
+    /// Step 2: run the graph, and verify that B only receives the first packet before seeing
+    /// the channel closed, while C and D receive all three, and that the sends to B fail.
+    #[test]
+    fn test_broadcast_close_to() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let mut consumers = vec![];
+        for name in ["B", "C", "D"] {
+            let node =
+                DefaultNode::with_action(NodeName::from(name), CollectAction, &mut node_table);
+            consumers.push(node.id());
+            graph.add_node(node);
+        }
+        let a = DefaultNode::with_action(
+            NodeName::from("A"),
+            CloseOneAction(consumers[0]),
+            &mut node_table,
+        );
+        let a_id = a.id();
+        graph.add_node(a);
+        graph.connect_broadcast(a_id, &consumers, 8).unwrap();
+
+        graph.run_with_deadline(Duration::from_secs(10)).unwrap();
+        let outputs = graph.get_outputs();
+        assert_eq!(outputs[&a_id].get_out().unwrap().get::<usize>(), Some(&3));
+        let received = |id| outputs[&id].get_out().unwrap().get::<Vec<usize>>().cloned();
+        assert_eq!(received(consumers[0]), Some(vec![0]));
+        assert_eq!(received(consumers[1]), Some(vec![0, 1, 2]));
+        assert_eq!(received(consumers[2]), Some(vec![0, 1, 2]));
+    }
+
+    /// An action running `self.1` steps: each receives a number from node `self.0`, adds
+    /// `self.2` and sends the result back. Outputs the last result.
+    struct StepAction(NodeId, usize, usize);
+    #[async_trait]
+    impl Action for StepAction {
+        async fn run(
+            &self,
+            in_channels: &mut InChannels,
+            out_channels: &mut OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            let mut value = 0;
+            for _ in 0..self.1 {
+                let content = in_channels.recv_from(&self.0).await.unwrap();
+                value = *content.get::<usize>().unwrap() + self.2;
+                out_channels
+                    .send_to(&self.0, Content::new(value))
+                    .await
+                    .unwrap();
+            }
+            Output::new(value)
+        }
+    }
+
+    /// Test for iterating through a feedback edge.
+    ///
+    /// Step 1: connect A to B, and B back to A with a feedback edge holding 0. For 3 steps, A
+    /// adds 1 to the value from B, and B sends it back.
+    ///
+    /// Step 2: verify the graph is not reported as a loop and both nodes end with 3.
+    ///
+    /// Step 3: verify a duplicate or dangling feedback edge is rejected.
+    #[test]
+    fn test_add_feedback_edge() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a_id = node_table.alloc_id_for("A");
+        let b_id = node_table.alloc_id_for("B");
+        graph.add_node(DefaultNode::with_id(
+            a_id,
+            NodeName::from("A"),
+            StepAction(b_id, 3, 1),
+        ));
+        graph.add_node(DefaultNode::with_id(
+            b_id,
+            NodeName::from("B"),
+            StepAction(a_id, 3, 0),
+        ));
+        graph.connect(a_id, b_id).unwrap();
+        graph
+            .add_feedback_edge(b_id, a_id, Content::new(0usize))
+            .unwrap();
+
+        graph.start().unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&a_id).unwrap(), 3);
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 3);
+
+        assert!(matches!(
+            graph.add_feedback_edge(b_id, a_id, Content::new(0usize)),
+            Err(GraphError::DuplicateEdge { .. })
+        ));
+        assert!(matches!(
+            graph.add_feedback_edge(b_id, NodeId(usize::MAX), Content::new(0usize)),
+            Err(GraphError::UnknownNode(_))
+        ));
+    }
+
+    /// Test for preloading the input of a node.
+    ///
+    /// The node receives the 3 seeded packets in order, then sees its input closed.
+    #[test]
+    fn test_add_source_with_data() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let node = DefaultNode::with_action(NodeName::from("A"), CollectAction, &mut node_table);
+        let data = (1..=3usize).map(Content::new).collect();
+        let id = graph.add_source_with_data(node, data);
+        assert!(graph.node_exists(id));
+
+        graph.start().unwrap();
+        assert_eq!(
+            *graph.get_output_as::<Vec<usize>>(&id).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    /// Test for observing the events of a run.
+    ///
+    /// Step 1: connect A, sending 2 packets, to B, and subscribe to the events twice.
+    ///
+    /// Step 2: run the graph, and verify both subscribers observe the same events: the graph
+    /// starts first and completes last, each node starts before completing, and the 2 packets
+    /// sent from A to B.
+    #[test]
+    fn test_event_stream() {
+        let mut graph = Graph::new();
+        let mut node_table = NodeTable::new();
+        let a = DefaultNode::with_action(NodeName::from("A"), RepeatAction(2), &mut node_table);
+        let b = DefaultNode::new(NodeName::from("B"), &mut node_table);
+        let (a_id, b_id) = (a.id(), b.id());
+        graph.add_node(a);
+        graph.add_node(b);
+        graph.add_edge(a_id, vec![b_id]).unwrap();
+        let mut logging = graph.event_stream();
+        let mut metrics = graph.event_stream();
+
+        graph.start().unwrap();
+        for events in [&mut logging, &mut metrics] {
+            let mut received = vec![];
+            while let Ok(event) = events.try_recv() {
+                received.push(event);
+            }
+            assert!(matches!(received.first(), Some(GraphEvent::GraphStarted)));
+            assert!(matches!(received.last(), Some(GraphEvent::GraphCompleted)));
+            for id in [a_id, b_id] {
+                let started = received
+                    .iter()
+                    .position(|event| matches!(event, GraphEvent::NodeStarted(i) if *i == id));
+                let completed = received
+                    .iter()
+                    .position(|event| matches!(event, GraphEvent::NodeCompleted(i, _) if *i == id));
+                assert!(started.unwrap() < completed.unwrap());
+            }
+            let sent = received
+                .iter()
+                .filter(|event| {
+                    matches!(event, GraphEvent::MessageSent { from, to }
+                        if *from == a_id && *to == b_id)
+                })
+                .count();
+            assert_eq!(sent, 2);
+            assert_eq!(received.len(), 8);
+        }
+    }
+
+    /// Test for building a graph from a spec and a registry of node types.
+    ///
+    /// Step 1: register a type adding 1 and a type adding 10, and build `A -> B` from a spec.
+    ///
+    /// Step 2: run the graph and verify the value flows from A to B.
+    ///
+    /// Step 3: verify specs with an unknown type or node name are rejected.
+    #[test]
+    fn test_build_from_spec() {
+        let mut registry = NodeRegistry::new();
+        for (type_name, value) in [("add_one", 1), ("add_ten", 10)] {
+            registry.register(type_name, move |id, env| {
+                let name = env.get_node_name(&id).unwrap();
+                Box::new(DefaultNode::with_id(id, name, AddAction(value)))
+            });
+        }
+        let node = |name: &str, type_name: &str| NodeSpec {
+            name: NodeName::from(name),
+            type_name: type_name.to_string(),
+        };
+        let mut spec = GraphSpec {
+            nodes: vec![node("A", "add_one"), node("B", "add_ten")],
+            edges: vec![EdgeSpec {
+                from: NodeName::from("A"),
+                to: NodeName::from("B"),
+            }],
+        };
+
+        let mut graph = Graph::build_from_spec(&spec, &registry).unwrap();
+        graph.start().unwrap();
+        let b_id = *graph.env.get_node_id("B").unwrap();
+        assert_eq!(*graph.get_output_as::<usize>(&b_id).unwrap(), 11);
+
+        spec.edges[0].to = NodeName::from("C");
+        assert!(matches!(
+            Graph::build_from_spec(&spec, &registry),
+            Err(GraphError::InvalidSpec(_))
+        ));
+        spec.edges.clear();
+        spec.nodes.push(node("C", "add_hundred"));
+        assert!(matches!(
+            Graph::build_from_spec(&spec, &registry),
+            Err(GraphError::InvalidSpec(_))
+        ));
+    }
+
+    /// Test for reading a spec from JSON.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_graph_spec_serde() {
+        let json = r#"{
+            "nodes": [{ "name": "A", "type_name": "source" }, { "name": "B", "type_name": "sink" }],
+            "edges": [{ "from": "A", "to": "B" }]
+        }"#;
+        let spec: GraphSpec = serde_json::from_str(json).unwrap();
+        assert_eq!(spec.nodes[1].type_name, "sink");
+        assert_eq!(spec.edges[0].from, "A");
+        let round_trip: GraphSpec =
+            serde_json::from_str(&serde_json::to_string(&spec).unwrap()).unwrap();
+        assert_eq!(round_trip, spec);
+    }
 }
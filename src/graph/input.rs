@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+
+use crate::{
+    connection::{information_packet::Content, out_channel::SendErr},
+    utils::in_flight::InFlight,
+};
+
+/// # External input of a graph
+///
+/// A handle streaming packets into a node of a running graph from outside of it, returned by
+/// [`Graph::open_input`](crate::Graph::open_input). The node receives them from its input
+/// channel keyed by [`NodeId::INPUT`](crate::NodeId::INPUT), like packets from a predecessor.
+///
+/// The channel stays open until [`GraphInput::finish`] is called or the handle is dropped, so
+/// a node reading its input until the channel is closed completes only then: a streaming
+/// graph whose producer never finishes never terminates.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use async_trait::async_trait;
+/// use dagrs::{
+///     Action, Content, DefaultNode, EnvVar, Graph, InChannels, Node, NodeId, NodeName,
+///     NodeTable, OutChannels, Output,
+/// };
+///
+/// struct Sum;
+///
+/// #[async_trait]
+/// impl Action for Sum {
+///     async fn run(&self, input: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+///         let mut sum = 0;
+///         while let Ok(content) = input.recv_from(&NodeId::INPUT).await {
+///             sum += content.get::<i32>().unwrap();
+///         }
+///         Output::new(sum)
+///     }
+/// }
+///
+/// let mut node_table = NodeTable::new();
+/// let node = DefaultNode::with_action(NodeName::from("sum"), Sum, &mut node_table);
+/// let id = node.id();
+/// let mut graph = Graph::new();
+/// graph.add_node(node);
+///
+/// let mut input = graph.open_input(id, 4).unwrap();
+/// let runtime = tokio::runtime::Runtime::new().unwrap();
+/// let (result, _) = runtime.block_on(async {
+///     futures::join!(graph.run_async(), async move {
+///         for i in 1..=3 {
+///             input.send(Content::new(i)).await.unwrap();
+///         }
+///         input.finish();
+///     })
+/// });
+/// result.unwrap();
+/// assert_eq!(*graph.get_output_as::<i32>(&id).unwrap(), 6);
+/// ```
+pub struct GraphInput {
+    sender: Option<mpsc::Sender<Content>>,
+    in_flight: Arc<InFlight>,
+}
+
+impl GraphInput {
+    pub(crate) fn new(sender: mpsc::Sender<Content>, in_flight: Arc<InFlight>) -> Self {
+        Self {
+            sender: Some(sender),
+            in_flight,
+        }
+    }
+
+    /// Send `content` to the node, waiting while its input channel is full.
+    ///
+    /// Returns [`SendErr::ClosedChannel`] with the `content` once the input is finished, or if
+    /// the node closed the channel, e.g. when it completed, or the channel was replaced by
+    /// another call to [`Graph::open_input`](crate::Graph::open_input).
+    pub async fn send(&self, mut content: Content) -> Result<(), SendErr> {
+        let Some(sender) = &self.sender else {
+            return Err(SendErr::ClosedChannel(content));
+        };
+        content.mark_sent();
+        self.in_flight.add(1);
+        sender.send(content).await.map_err(|error| {
+            self.in_flight.sub(1);
+            SendErr::ClosedChannel(error.0)
+        })
+    }
+
+    /// Close the input channel: the node receives the packets sent so far, then sees the
+    /// channel closed. Finishing an input finished already does nothing.
+    pub fn finish(&mut self) {
+        self.sender = None;
+    }
+
+    /// Returns true if [`GraphInput::finish`] was called.
+    pub fn is_finished(&self) -> bool {
+        self.sender.is_none()
+    }
+}
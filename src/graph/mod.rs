@@ -1,4 +1,10 @@
 mod abstract_graph;
 pub mod error;
+pub mod event;
+#[allow(clippy::module_inception)]
 pub mod graph;
+pub mod input;
 pub mod loop_subgraph;
+pub mod scheduler;
+pub mod snapshot;
+pub mod spec;
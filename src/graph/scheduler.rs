@@ -0,0 +1,60 @@
+use crate::node::node::NodeId;
+
+/// # Spawn order
+///
+/// The graph runs its nodes by blocks, delimited by the conditional nodes and the loops. All
+/// the nodes of a block are spawned together, without waiting for their predecessors to
+/// complete: they exchange packets through their channels while they run, so a node is
+/// typically spawned before its inputs are available, and waits for them in its `run`. A
+/// [`Scheduler`] only decides the order in which the nodes of a block are spawned, see
+/// [`Graph::run_with`](crate::Graph::run_with).
+///
+/// This is not a ready queue: the scheduler is asked for the next node to spawn among the ones
+/// not spawned yet whose predecessors are spawned already, until all of them are, and it is
+/// never asked again once a node completes.
+/// Spawning a node hands its task to the runtime, so on a runtime with several workers, the
+/// order is the one in which the tasks are handed over rather than a strict order of execution,
+/// and all the nodes of a block may run concurrently whatever the order. The order matters most
+/// on a single-threaded runtime, or when the nodes compete for a bounded resource, such as the
+/// parallelism of the graph, see [`Graph::set_parallelism`](crate::Graph::set_parallelism).
+///
+/// # Fairness
+/// Since every call to [`Scheduler::next_to_spawn`] spawns a node, and the next block is only
+/// started once all the nodes of the current one are spawned, a node with a low priority is
+/// spawned after the nodes of its block with higher priority at most, and can never be starved
+/// by them. With the `resource-aware` feature, the resources of each node are taken right
+/// before spawning it, so they are given out in the spawn order too. Hence the spawn order
+/// needs no priority aging.
+///
+/// # Example
+/// A scheduler spawning the nodes with the lowest priority first.
+/// ```rust
+/// use dagrs::{NodeId, Scheduler};
+///
+/// struct LowestPriorityFirst;
+///
+/// impl Scheduler for LowestPriorityFirst {
+///     fn next_to_spawn(&self, pending: &[NodeId]) -> Option<NodeId> {
+///         pending.last().copied()
+///     }
+/// }
+/// ```
+pub trait Scheduler: Send + Sync {
+    /// Picks the next node to spawn among the `pending` nodes of a block, which are sorted by
+    /// decreasing [priority](crate::Node::priority), then by increasing id. Only the nodes
+    /// whose predecessors are all spawned already are pending, or all the nodes left if they
+    /// form a cycle, so a node is never spawned before its predecessors.
+    ///
+    /// Returning `None`, or a node not in `pending`, spawns the first node of `pending`.
+    fn next_to_spawn(&self, pending: &[NodeId]) -> Option<NodeId>;
+}
+
+/// The [`Scheduler`] of a graph by default, spawning the nodes with higher priority first.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultScheduler;
+
+impl Scheduler for DefaultScheduler {
+    fn next_to_spawn(&self, pending: &[NodeId]) -> Option<NodeId> {
+        pending.first().copied()
+    }
+}
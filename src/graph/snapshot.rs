@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::{connection::information_packet::Content, node::node::NodeId};
+
+/// A checkpoint of the nodes completed in a run of a `Graph`, and their outputs.
+///
+/// Take one with [`Graph::snapshot`](crate::Graph::snapshot), and hand it to
+/// [`Graph::restore`](crate::Graph::restore) so that the next run skips the completed nodes.
+/// With the `serde` feature, it can be serialized as long as the outputs can, see [`Content`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    /// Maps the completed nodes to their outputs, `None` if a node has no output.
+    pub(crate) completed: HashMap<NodeId, Option<Content>>,
+}
+
+impl Snapshot {
+    /// Returns true if the node `id` was completed.
+    pub fn is_completed(&self, id: &NodeId) -> bool {
+        self.completed.contains_key(id)
+    }
+
+    /// Returns the output of the completed node `id`, if any.
+    pub fn output(&self, id: &NodeId) -> Option<&Content> {
+        self.completed.get(id).and_then(Option::as_ref)
+    }
+
+    /// Returns the ids of the completed nodes.
+    pub fn completed_nodes(&self) -> Vec<NodeId> {
+        self.completed.keys().copied().collect()
+    }
+}
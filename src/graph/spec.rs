@@ -0,0 +1,35 @@
+use crate::node::node::NodeName;
+
+/// A declarative description of a graph: its nodes by name and type, and the edges between
+/// them. The graph is built from it by [`Graph::build_from_spec`](crate::Graph::build_from_spec),
+/// with the types resolved by a [`NodeRegistry`](crate::NodeRegistry).
+///
+/// With the `serde` feature, it can be read from any format supported by serde, e.g. JSON:
+/// ```json
+/// {
+///     "nodes": [{ "name": "A", "type_name": "source" }, { "name": "B", "type_name": "sink" }],
+///     "edges": [{ "from": "A", "to": "B" }]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphSpec {
+    pub nodes: Vec<NodeSpec>,
+    pub edges: Vec<EdgeSpec>,
+}
+
+/// A node of a [`GraphSpec`]. The name must be unique in the graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeSpec {
+    pub name: NodeName,
+    pub type_name: String,
+}
+
+/// An edge of a [`GraphSpec`], from the node named `from` to the node named `to`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeSpec {
+    pub from: NodeName,
+    pub to: NodeName,
+}
@@ -1,23 +1,47 @@
+// `NodeId::new_uuid` folds a UUID into a `usize`, which must hold enough random bits.
+#[cfg(all(feature = "uuid", not(target_pointer_width = "64")))]
+compile_error!("the `uuid` feature requires a 64-bit target");
+
 pub mod connection;
 pub mod graph;
 pub mod node;
 pub mod utils;
 
 pub use connection::{
-    in_channel::{InChannels, RecvErr},
-    information_packet::Content,
-    out_channel::{OutChannels, SendErr},
+    in_channel::{ChannelType, InChannels, RecvErr},
+    information_packet::{Content, MessageMeta},
+    out_channel::{AckError, BalancePolicy, OutChannels, SendErr},
 };
 pub use node::{
-    action::{Action, EmptyAction},
-    default_node::DefaultNode,
+    action::{Action, BlockingAction, EmptyAction, SyncAction},
+    collect_node::CollectNode,
+    dedup_node::DedupNode,
+    default_node::{DefaultNode, DefaultNodeBuilder},
+    error::NodeError,
     node::*,
+    node_context::NodeContext,
+    registry::NodeRegistry,
+    task_scope::TaskScope,
 };
 
 pub use async_trait;
-pub use graph::graph::*;
+pub use graph::{
+    event::GraphEvent,
+    graph::*,
+    input::GraphInput,
+    scheduler::{DefaultScheduler, Scheduler},
+    snapshot::Snapshot,
+    spec::{EdgeSpec, GraphSpec, NodeSpec},
+};
 pub use tokio;
-pub use utils::{env::EnvVar, output::Output};
+pub use utils::{
+    env::{EnvVar, EnvVarBuilder, EnvVarError, EnvVarSnapshot},
+    output::{Output, OutputError},
+    yielding::{yielding, Yielding},
+};
+
+#[cfg(feature = "config")]
+pub use utils::config::ConfigError;
 
 #[cfg(feature = "derive")]
 pub use dagrs_derive::*;
@@ -1,9 +1,12 @@
-use std::sync::Arc;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 
 use crate::{
-    connection::{in_channel::InChannels, out_channel::OutChannels},
+    connection::{in_channel::InChannels, information_packet::Content, out_channel::OutChannels},
     utils::{env::EnvVar, output::Output},
 };
 
@@ -49,6 +52,18 @@ pub trait Action: Send + Sync {
         out_channels: &mut OutChannels,
         env: Arc<EnvVar>,
     ) -> Output;
+
+    /// The scheduling priority of this action, higher values are spawned first, see
+    /// [`Node::priority`](crate::Node::priority). By default, it returns 0.
+    ///
+    /// The graph reads it again before every run of the graph, so an action may raise its
+    /// priority for the next run based on its internal state, e.g. after detecting that it has
+    /// fallen behind. It only orders the spawns of the nodes of a block, see
+    /// [`Scheduler`](crate::Scheduler): it does not change the order in which the running
+    /// nodes are served.
+    fn priority(&self) -> u8 {
+        0
+    }
 }
 
 /// An empty implementaion of [`Action`].
@@ -61,3 +76,250 @@ impl Action for EmptyAction {
         Output::Out(None)
     }
 }
+
+/// An implementation of [`Action`] running a synchronous closure, for CPU-bound work that
+/// would otherwise block the async scheduler and stall the other nodes.
+///
+/// The closure runs on the blocking thread pool of tokio, see [`tokio::task::spawn_blocking`].
+/// It is given a packet from every input channel, received before it starts, and the content
+/// of its [`Output`] is broadcast to all the successors after it returns. The inputs and the
+/// output are moved across threads, so the closure must be `Send + Sync + 'static`, and
+/// the values it produces must be `Send + Sync`, like any [`Content`].
+///
+/// If the closure panics, the action returns an [`Output::Err`].
+///
+/// # Example
+/// ```rust
+/// use dagrs::{BlockingAction, DefaultNode, NodeName, NodeTable, Output};
+///
+/// let mut node_table = NodeTable::new();
+/// let sum = BlockingAction::new(|inputs, _env| {
+///     let sum: usize = inputs.iter().filter_map(|c| c.get::<usize>()).sum();
+///     Output::new(sum)
+/// });
+/// let node = DefaultNode::with_action(NodeName::from("sum"), sum, &mut node_table);
+/// ```
+pub struct BlockingAction<F>(Arc<F>);
+
+impl<F> BlockingAction<F>
+where
+    F: Fn(Vec<Content>, Arc<EnvVar>) -> Output + Send + Sync + 'static,
+{
+    pub fn new(f: F) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+#[async_trait]
+impl<F> Action for BlockingAction<F>
+where
+    F: Fn(Vec<Content>, Arc<EnvVar>) -> Output + Send + Sync + 'static,
+{
+    async fn run(
+        &self,
+        in_channels: &mut InChannels,
+        out_channels: &mut OutChannels,
+        env: Arc<EnvVar>,
+    ) -> Output {
+        let inputs = in_channels.recv_values().await;
+        let f = self.0.clone();
+        let out = match tokio::task::spawn_blocking(move || f(inputs, env)).await {
+            Ok(out) => out,
+            Err(_) => return Output::error("blocking action panicked".to_string()),
+        };
+        if let Some(content) = out.get_out() {
+            out_channels.broadcast(content).await;
+        }
+        out
+    }
+}
+
+/// Synchronous node specific behavior, for CPU-bound work such as FFT, compression or linear
+/// algebra, that would otherwise block the async scheduler and stall the other nodes.
+///
+/// A node created with [`DefaultNode::with_sync_action`](crate::DefaultNode::with_sync_action)
+/// runs its [`SyncAction`] on the blocking thread pool of tokio, see
+/// [`tokio::task::spawn_blocking`]. The channels are used through their blocking methods, e.g.
+/// [`InChannels::blocking_recv_from`] and [`OutChannels::blocking_send_to`].
+///
+/// If `run` panics, the node outputs an [`Output::Err`]. A node cancelled by a timeout stops
+/// waiting for `run`, but cannot interrupt it: `run` goes on until it returns.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use dagrs::{DefaultNode, EnvVar, InChannels, NodeName, NodeTable, OutChannels, Output, SyncAction};
+///
+/// struct SumAction;
+///
+/// impl SyncAction for SumAction {
+///     fn run(&self, in_channels: &mut InChannels, _: &OutChannels, _: Arc<EnvVar>) -> Output {
+///         let sum: usize = in_channels
+///             .blocking_map(|content| content.ok().and_then(|c| c.get::<usize>().copied()))
+///             .into_iter()
+///             .flatten()
+///             .sum();
+///         Output::new(sum)
+///     }
+/// }
+///
+/// let mut node_table = NodeTable::new();
+/// let node = DefaultNode::with_sync_action(NodeName::from("sum"), SumAction, &mut node_table);
+/// ```
+pub trait SyncAction: Send + Sync {
+    fn run(
+        &self,
+        in_channels: &mut InChannels,
+        out_channels: &OutChannels,
+        env: Arc<EnvVar>,
+    ) -> Output;
+}
+
+/// Runs a [`SyncAction`] as an [`Action`], on the blocking thread pool.
+pub(crate) struct SyncActionAdapter<A>(pub(crate) Arc<A>);
+
+#[async_trait]
+impl<A: SyncAction + 'static> Action for SyncActionAdapter<A> {
+    async fn run(
+        &self,
+        in_channels: &mut InChannels,
+        out_channels: &mut OutChannels,
+        env: Arc<EnvVar>,
+    ) -> Output {
+        // Move the channels to the blocking thread, and back once the action returns.
+        let action = self.0.clone();
+        let mut inputs = std::mem::take(in_channels);
+        let outputs = std::mem::take(out_channels);
+        let task = tokio::task::spawn_blocking(move || {
+            let out =
+                panic::catch_unwind(AssertUnwindSafe(|| action.run(&mut inputs, &outputs, env)))
+                    .unwrap_or_else(|_| Output::error("sync action panicked".to_string()));
+            (inputs, outputs, out)
+        });
+        match task.await {
+            Ok((inputs, outputs, out)) => {
+                *in_channels = inputs;
+                *out_channels = outputs;
+                out
+            }
+            Err(_) => Output::error("sync action panicked".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::{mpsc, Mutex};
+
+    use crate::{
+        connection::{in_channel::InChannel, out_channel::OutChannel},
+        Content, DefaultNode, EnvVar, InChannels, Node, NodeId, NodeName, NodeTable, OutChannels,
+    };
+
+    use super::{Action, BlockingAction, Output, SyncAction};
+
+    /// Test for running a synchronous closure as an action.
+    ///
+    /// The closure sums the inputs: the sum is both the output and sent to the successor, and
+    /// a panicking closure yields an error.
+    #[test]
+    fn test_blocking_action() {
+        let (in_tx, in_rx) = mpsc::channel(8);
+        let (out_tx, mut out_rx) = mpsc::channel(8);
+        let mut in_channels = InChannels::default();
+        in_channels.insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(in_rx))));
+        let mut out_channels = OutChannels::default();
+        out_channels.insert(NodeId(2), Arc::new(Mutex::new(OutChannel::Mpsc(out_tx))));
+        in_tx.blocking_send(Content::new(3usize)).unwrap();
+        drop(in_tx);
+        let env = Arc::new(EnvVar::new(NodeTable::default()));
+
+        let sum = BlockingAction::new(|inputs: Vec<Content>, _| {
+            Output::new(
+                inputs
+                    .iter()
+                    .filter_map(|c| c.get::<usize>())
+                    .sum::<usize>(),
+            )
+        });
+        let panicking = BlockingAction::new(|_, _| -> Output { panic!("heavy work failed") });
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (out, err) = runtime.block_on(async {
+            let out = sum
+                .run(&mut in_channels, &mut out_channels, env.clone())
+                .await;
+            let err = panicking
+                .run(&mut in_channels, &mut out_channels, env)
+                .await;
+            (out, err)
+        });
+
+        assert_eq!(out.get_out().unwrap().get::<usize>(), Some(&3));
+        assert_eq!(out_rx.blocking_recv().unwrap().get::<usize>(), Some(&3));
+        assert!(err.get_err().is_some());
+    }
+
+    /// An implementation of [`SyncAction`] forwarding the double of each number received
+    /// from node 1 to node 2, and outputting their count. It panics on a negative number.
+    struct DoubleAction;
+    impl SyncAction for DoubleAction {
+        fn run(
+            &self,
+            in_channels: &mut InChannels,
+            out_channels: &OutChannels,
+            _: Arc<EnvVar>,
+        ) -> Output {
+            let mut count = 0;
+            while let Ok(content) = in_channels.blocking_recv_from(&NodeId(1)) {
+                let value = *content.get::<i32>().unwrap();
+                assert!(value >= 0, "negative input");
+                out_channels
+                    .blocking_send_to(&NodeId(2), Content::new(value * 2))
+                    .unwrap();
+                count += 1;
+            }
+            Output::new(count)
+        }
+    }
+
+    /// Test for running a synchronous action on the blocking thread pool.
+    ///
+    /// Step 1: run a node with [`DoubleAction`], which could not use the blocking methods of
+    /// the channels on an async worker, and verify its output and the packets it sent.
+    ///
+    /// Step 2: run it again with a negative input, verify it yields an error and keeps its
+    /// channels.
+    #[test]
+    fn test_sync_action() {
+        let mut node_table = NodeTable::new();
+        let mut node =
+            DefaultNode::with_sync_action(NodeName::from("Double"), DoubleAction, &mut node_table);
+        let (in_tx, in_rx) = mpsc::channel(8);
+        let (out_tx, mut out_rx) = mpsc::channel(8);
+        node.input_channels()
+            .insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(in_rx))));
+        node.output_channels()
+            .insert(NodeId(2), Arc::new(Mutex::new(OutChannel::Mpsc(out_tx))));
+        let env = Arc::new(EnvVar::new(NodeTable::default()));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        in_tx.blocking_send(Content::new(1)).unwrap();
+        in_tx.blocking_send(Content::new(2)).unwrap();
+        drop(in_tx);
+        let out = runtime.block_on(node.run(env.clone()));
+        assert_eq!(out.get_out().unwrap().get::<i32>(), Some(&2));
+        assert_eq!(out_rx.blocking_recv().unwrap().get::<i32>(), Some(&2));
+        assert_eq!(out_rx.blocking_recv().unwrap().get::<i32>(), Some(&4));
+
+        let (in_tx, in_rx) = mpsc::channel(8);
+        node.input_channels()
+            .insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(in_rx))));
+        in_tx.blocking_send(Content::new(-1)).unwrap();
+        let err = runtime.block_on(node.run(env));
+        assert!(err.get_err().is_some());
+        assert_eq!(node.input_channels().get_sender_ids(), vec![NodeId(1)]);
+        assert_eq!(node.output_channels().get_receiver_ids(), vec![NodeId(2)]);
+    }
+}
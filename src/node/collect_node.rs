@@ -0,0 +1,106 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{EnvVar, InChannels, Node, NodeId, NodeName, NodeTable, OutChannels, Output, RecvErr};
+
+/// # Collecting node
+///
+/// [`CollectNode`] is a sink: it receives the packets from all its predecessors, whether
+/// connected through `mpsc` or `broadcast` channels, until all its input channels are closed, and
+/// outputs them as a `Vec<Arc<T>>`, in the order they were received. The collected packets are
+/// then retrieved from the graph once it has run, with
+/// [`Graph::get_outputs`](crate::Graph::get_outputs) or
+/// [`Graph::get_output_as`](crate::Graph::get_output_as).
+///
+/// The packets of another type than `T`, as well as the lagged or expired packets, are skipped.
+pub struct CollectNode<T> {
+    id: NodeId,
+    name: NodeName,
+    in_channels: InChannels,
+    out_channels: OutChannels,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> CollectNode<T> {
+    /// Creates a new [`CollectNode`] collecting the packets of type `T`.
+    pub fn new(name: NodeName, node_table: &mut NodeTable) -> Self {
+        Self {
+            id: node_table.alloc_id_for(&name),
+            name,
+            in_channels: InChannels::default(),
+            out_channels: OutChannels::default(),
+            _type: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> Node for CollectNode<T> {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn name(&self) -> NodeName {
+        self.name.clone()
+    }
+
+    fn input_channels(&mut self) -> &mut InChannels {
+        &mut self.in_channels
+    }
+
+    fn output_channels(&mut self) -> &mut OutChannels {
+        &mut self.out_channels
+    }
+
+    async fn run(&mut self, _: Arc<EnvVar>) -> Output {
+        let mut collected = Vec::new();
+        loop {
+            match self.in_channels.recv_any().await {
+                Ok((_, content)) => collected.extend(content.into_inner::<T>()),
+                Err(RecvErr::Closed | RecvErr::NoSuchChannel) => return Output::new(collected),
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::{broadcast, mpsc, Mutex};
+
+    use super::*;
+    use crate::{connection::in_channel::InChannel, Content};
+
+    /// Test for collecting the packets received through a `mpsc` and a `broadcast` channel.
+    ///
+    /// The packet of another type is skipped.
+    #[test]
+    fn test_collect_node() {
+        let mut node_table = NodeTable::new();
+        let mut node = CollectNode::<u32>::new(NodeName::from("Sink"), &mut node_table);
+        let (mpsc_tx, mpsc_rx) = mpsc::channel(8);
+        let (bcst_tx, bcst_rx) = broadcast::channel(8);
+        node.input_channels()
+            .insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(mpsc_rx))));
+        node.input_channels()
+            .insert(NodeId(2), Arc::new(Mutex::new(InChannel::Bcst(bcst_rx))));
+
+        mpsc_tx.blocking_send(Content::new(1u32)).unwrap();
+        mpsc_tx.blocking_send(Content::new("skipped")).unwrap();
+        bcst_tx.send(Content::new(2u32)).unwrap();
+        drop((mpsc_tx, bcst_tx));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let env = Arc::new(EnvVar::new(NodeTable::default()));
+        let output = runtime.block_on(node.run(env));
+        let collected = output
+            .get_out()
+            .unwrap()
+            .into_inner::<Vec<Arc<u32>>>()
+            .unwrap();
+        let mut collected: Vec<u32> = collected.iter().map(|v| **v).collect();
+        collected.sort_unstable();
+        assert_eq!(collected, vec![1, 2]);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+
+use crate::{EnvVar, InChannels, Node, NodeId, NodeName, NodeTable, OutChannels, Output, RecvErr};
+
+/// # Deduplicating node
+///
+/// [`DedupNode`] forwards the packets received from all its predecessors to all its successors,
+/// except the packets whose trace id, see [`Content::with_trace_id`](crate::Content), it has
+/// seen already. Placed in front of a node reached by the same packet through several paths,
+/// e.g. the join of a diamond, it makes the node process each packet once.
+///
+/// The seen trace ids are kept in a LRU set of bounded `capacity`: once full, the id seen least
+/// recently is evicted, and a packet with this id would be forwarded again. Seeing a duplicate
+/// counts as using the id. Packets without trace id are always forwarded.
+///
+/// The node runs until all its input channels are closed.
+pub struct DedupNode {
+    id: NodeId,
+    name: NodeName,
+    seen: LruSet,
+    in_channels: InChannels,
+    out_channels: OutChannels,
+}
+
+impl DedupNode {
+    /// Creates a new [`DedupNode`] remembering the last `capacity` trace ids.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn new(name: NodeName, capacity: usize, node_table: &mut NodeTable) -> Self {
+        assert!(capacity > 0, "dedup capacity must be positive");
+        Self {
+            id: node_table.alloc_id_for(&name),
+            name,
+            seen: LruSet::new(capacity),
+            in_channels: InChannels::default(),
+            out_channels: OutChannels::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Node for DedupNode {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn name(&self) -> NodeName {
+        self.name.clone()
+    }
+
+    fn input_channels(&mut self) -> &mut InChannels {
+        &mut self.in_channels
+    }
+
+    fn output_channels(&mut self) -> &mut OutChannels {
+        &mut self.out_channels
+    }
+
+    async fn run(&mut self, _: Arc<EnvVar>) -> Output {
+        loop {
+            match self.in_channels.recv_any().await {
+                Ok((_, content)) => {
+                    if content.trace_id().is_none_or(|id| self.seen.insert(id)) {
+                        self.out_channels.broadcast(content).await;
+                    }
+                }
+                Err(RecvErr::Closed | RecvErr::NoSuchChannel) => return Output::empty(),
+                // Skip the lagged or expired packets.
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// A set of at most `capacity` ids, evicting the least recently used one.
+struct LruSet {
+    capacity: usize,
+    /// The last use of each id.
+    uses: HashMap<u64, u64>,
+    /// The ids by their last use, the least recent first.
+    order: BTreeMap<u64, u64>,
+    clock: u64,
+}
+
+impl LruSet {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            uses: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Use `id`, and returns true if it was not in the set.
+    fn insert(&mut self, id: u64) -> bool {
+        self.clock += 1;
+        let last_use = self.uses.insert(id, self.clock);
+        self.order.insert(self.clock, id);
+        match last_use {
+            Some(last_use) => {
+                self.order.remove(&last_use);
+                false
+            }
+            None => {
+                if self.uses.len() > self.capacity {
+                    let (_, evicted) = self.order.pop_first().unwrap();
+                    self.uses.remove(&evicted);
+                }
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::{mpsc, Mutex};
+
+    use super::*;
+    use crate::{
+        connection::{in_channel::InChannel, out_channel::OutChannel},
+        Content,
+    };
+
+    /// Test for the eviction of the LRU set.
+    ///
+    /// With a capacity of 2, using 1 again keeps it in the set, so 2 is evicted by 3.
+    #[test]
+    fn test_lru_set() {
+        let mut seen = LruSet::new(2);
+        assert!(seen.insert(1));
+        assert!(seen.insert(2));
+        assert!(!seen.insert(1));
+        assert!(seen.insert(3));
+        assert!(!seen.insert(1));
+        assert!(seen.insert(2));
+    }
+
+    /// Test for deduplicating the packets reaching a node through two paths.
+    ///
+    /// Both predecessors send the packets with trace ids 1 and 2, and a packet without trace id:
+    /// each traced packet is forwarded once, and both untraced packets are forwarded.
+    #[test]
+    fn test_dedup_node() {
+        let mut node_table = NodeTable::new();
+        let mut node = DedupNode::new(NodeName::from("Dedup"), 8, &mut node_table);
+        let mut senders = vec![];
+        for i in 1..=2 {
+            let (tx, rx) = mpsc::channel(8);
+            node.input_channels()
+                .insert(NodeId(i), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+            senders.push(tx);
+        }
+        let (out_tx, mut out_rx) = mpsc::channel(8);
+        node.output_channels()
+            .insert(NodeId(3), Arc::new(Mutex::new(OutChannel::Mpsc(out_tx))));
+
+        for tx in senders {
+            for id in 1..=2 {
+                tx.blocking_send(Content::new(id).with_trace_id(id))
+                    .unwrap();
+            }
+            tx.blocking_send(Content::new(0u64)).unwrap();
+        }
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let env = Arc::new(EnvVar::new(NodeTable::default()));
+        assert!(!runtime.block_on(node.run(env)).is_err());
+        drop(node);
+
+        let mut received = vec![];
+        while let Some(content) = out_rx.blocking_recv() {
+            received.push(*content.get::<u64>().unwrap());
+        }
+        received.sort_unstable();
+        assert_eq!(received, vec![0, 0, 1, 2]);
+    }
+}
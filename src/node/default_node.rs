@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 
@@ -8,8 +8,8 @@ use crate::{
 };
 
 use super::{
-    action::{Action, EmptyAction},
-    node::{Node, NodeId, NodeName, NodeTable},
+    action::{Action, EmptyAction, SyncAction, SyncActionAdapter},
+    node::{Node, NodeDescriptor, NodeId, NodeName, NodeTable},
 };
 
 /// # Default node type
@@ -32,7 +32,7 @@ use super::{
 /// ```
 ///
 /// - use the method `with_action`. Required attributes: node's name; [`NodeTable`](for id allocation);
-/// execution logic [`Action`].
+///   execution logic [`Action`].
 ///
 /// ```rust
 /// use dagrs::{NodeName, NodeTable, DefaultNode, EmptyAction};
@@ -45,12 +45,31 @@ use super::{
 ///     &mut node_table,
 /// );
 /// ```
+///
+/// - use the method `builder`, to set the optional attributes as well, see [`DefaultNodeBuilder`].
+///
+/// ```rust
+/// use std::time::Duration;
+/// use dagrs::{NodeTable, DefaultNode, EmptyAction};
+///
+/// let mut node_table = NodeTable::new();
+/// let mut node = DefaultNode::builder("Node X", &mut node_table)
+///     .action(EmptyAction)
+///     .timeout(Duration::from_secs(1))
+///     .priority(2)
+///     .tags(vec!["io".to_string()])
+///     .build();
+/// ```
 pub struct DefaultNode {
     id: NodeId,
     name: NodeName,
     action: Box<dyn Action>,
     in_channels: InChannels,
     out_channels: OutChannels,
+    retain_output: bool,
+    priority: Option<u8>,
+    timeout: Option<Duration>,
+    tags: Vec<String>,
 }
 #[async_trait]
 impl Node for DefaultNode {
@@ -75,17 +94,32 @@ impl Node for DefaultNode {
             .run(&mut self.in_channels, &mut self.out_channels, env)
             .await
     }
+
+    fn priority(&self) -> u8 {
+        self.priority.unwrap_or_else(|| self.action.priority())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn describe(&self) -> NodeDescriptor {
+        NodeDescriptor {
+            in_channel_ids: self.in_channels.get_sender_ids(),
+            out_channel_ids: self.out_channels.get_receiver_ids(),
+            tags: self.tags.clone(),
+            ..NodeDescriptor::new(self.id, self.name.clone())
+        }
+    }
+
+    fn retain_output(&self) -> bool {
+        self.retain_output
+    }
 }
 
 impl DefaultNode {
     pub fn new(name: NodeName, node_table: &mut NodeTable) -> Self {
-        Self {
-            id: node_table.alloc_id_for(&name),
-            name,
-            action: Box::new(EmptyAction),
-            in_channels: InChannels::default(),
-            out_channels: OutChannels::default(),
-        }
+        Self::with_action(name, EmptyAction, node_table)
     }
 
     pub fn with_action(
@@ -93,26 +127,147 @@ impl DefaultNode {
         action: impl Action + 'static,
         node_table: &mut NodeTable,
     ) -> Self {
+        Self::with_id(node_table.alloc_id_for(&name), name, action)
+    }
+
+    /// Create a node with the action returned by `make_action`, or return its error, e.g. when
+    /// the action opens a file or connects a client it owns. The id is only allocated from
+    /// `node_table` once the action is created.
+    ///
+    /// The construction errors surface this way while the graph is assembled. The setup that
+    /// needs the environment, or an async runtime, rather belongs to a node implementing
+    /// [`Node::warm_up`], whose error prevents the graph from running any node.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::fs::File;
+    ///
+    /// use dagrs::{DefaultNode, EmptyAction, NodeName, NodeTable};
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let node = DefaultNode::try_with_action(
+    ///     NodeName::from("reader"),
+    ///     || File::open("missing.csv").map(|_file| EmptyAction),
+    ///     &mut node_table,
+    /// );
+    /// assert!(node.is_err());
+    /// assert!(node_table.get("reader").is_none());
+    /// ```
+    pub fn try_with_action<A: Action + 'static, E>(
+        name: NodeName,
+        make_action: impl FnOnce() -> Result<A, E>,
+        node_table: &mut NodeTable,
+    ) -> Result<Self, E> {
+        let action = make_action()?;
+        Ok(Self::with_action(name, action, node_table))
+    }
+
+    /// Start building a node named `name`, see [`DefaultNodeBuilder`]. Its id is allocated
+    /// from `node_table` right away.
+    pub fn builder(name: &str, node_table: &mut NodeTable) -> DefaultNodeBuilder {
+        DefaultNodeBuilder {
+            node: Self::new(NodeName::from(name), node_table),
+        }
+    }
+
+    /// Create a node running a synchronous `action` on the blocking thread pool, see
+    /// [`SyncAction`].
+    pub fn with_sync_action(
+        name: NodeName,
+        action: impl SyncAction + 'static,
+        node_table: &mut NodeTable,
+    ) -> Self {
+        Self::with_action(name, SyncActionAdapter(Arc::new(action)), node_table)
+    }
+
+    /// Create a node with an `id` allocated already, e.g. by a factory of a
+    /// [`NodeRegistry`](crate::NodeRegistry).
+    pub fn with_id(id: NodeId, name: NodeName, action: impl Action + 'static) -> Self {
         Self {
-            id: node_table.alloc_id_for(&name),
+            id,
             name,
             action: Box::new(action),
             in_channels: InChannels::default(),
             out_channels: OutChannels::default(),
+            retain_output: true,
+            priority: None,
+            timeout: None,
+            tags: Vec::new(),
         }
     }
 
     pub fn set_action(&mut self, action: impl Action + 'static) {
         self.action = Box::new(action)
     }
+
+    /// Set whether the graph keeps the output of this node, see [`Node::retain_output`].
+    pub fn set_retain_output(&mut self, retain: bool) {
+        self.retain_output = retain
+    }
+}
+
+/// # Builder of [`DefaultNode`]
+///
+/// Created by [`DefaultNode::builder`]. Every attribute is optional: a node built without
+/// action runs [`EmptyAction`].
+pub struct DefaultNodeBuilder {
+    node: DefaultNode,
+}
+
+impl DefaultNodeBuilder {
+    /// Set the execution logic of the node.
+    pub fn action(mut self, action: impl Action + 'static) -> Self {
+        self.node.set_action(action);
+        self
+    }
+
+    /// Set a synchronous execution logic, run on the blocking thread pool, see [`SyncAction`].
+    pub fn sync_action(self, action: impl SyncAction + 'static) -> Self {
+        self.action(SyncActionAdapter(Arc::new(action)))
+    }
+
+    /// Set the timeout of every run of the node, see [`Node::timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.node.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the scheduling priority of the node, instead of the one of its action, see
+    /// [`Node::priority`].
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.node.priority = Some(priority);
+        self
+    }
+
+    /// Set the tags reported by [`Node::describe`].
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.node.tags = tags;
+        self
+    }
+
+    /// Set whether the graph keeps the output of the node, see [`Node::retain_output`].
+    pub fn retain_output(mut self, retain: bool) -> Self {
+        self.node.set_retain_output(retain);
+        self
+    }
+
+    /// Returns the node.
+    pub fn build(self) -> DefaultNode {
+        self.node
+    }
 }
 
 #[cfg(test)]
 mod test_default_node {
 
-    use std::sync::Arc;
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::sync::{mpsc, Mutex};
 
-    use crate::{Content, EnvVar, InChannels, Node, NodeName, NodeTable, OutChannels, Output};
+    use crate::{
+        connection::{in_channel::InChannel, out_channel::OutChannel},
+        Content, EnvVar, InChannels, Node, NodeId, NodeName, NodeTable, OutChannels, Output,
+    };
 
     use super::{Action, DefaultNode};
 
@@ -130,7 +285,7 @@ mod test_default_node {
 
     impl HelloAction {
         pub fn new() -> Self {
-            Self::default()
+            Self
         }
     }
 
@@ -160,4 +315,78 @@ mod test_default_node {
         let out: &String = out.get().unwrap();
         assert_eq!(out, "Hello world");
     }
+
+    /// Test for describing a default node.
+    ///
+    /// Step 1: connect a [`DefaultNode`] to a sender and a receiver.
+    ///
+    /// Step 2: verify its descriptor reports them, without tags or metadata.
+    #[test]
+    fn test_describe() {
+        let mut node_table = NodeTable::new();
+        let mut node = DefaultNode::new(NodeName::from("Node"), &mut node_table);
+        let (tx, rx) = mpsc::channel(1);
+        node.input_channels()
+            .insert(NodeId(1), Arc::new(Mutex::new(InChannel::Mpsc(rx))));
+        node.output_channels()
+            .insert(NodeId(2), Arc::new(Mutex::new(OutChannel::Mpsc(tx))));
+
+        let descriptor = node.describe();
+        assert_eq!(descriptor.id, node.id());
+        assert_eq!(descriptor.name, "Node");
+        assert_eq!(descriptor.in_channel_ids, vec![NodeId(1)]);
+        assert_eq!(descriptor.out_channel_ids, vec![NodeId(2)]);
+        assert!(descriptor.tags.is_empty() && descriptor.metadata.is_empty());
+    }
+
+    /// Test for building a default node.
+    ///
+    /// The optional attributes are set, the priority overriding the one of the action, and the
+    /// node runs its action.
+    #[test]
+    fn test_builder() {
+        let mut node_table = NodeTable::new();
+        let mut node = DefaultNode::builder("Built", &mut node_table)
+            .action(HelloAction::new())
+            .timeout(Duration::from_secs(1))
+            .priority(3)
+            .tags(vec!["io".to_string()])
+            .retain_output(false)
+            .build();
+
+        assert_eq!(node_table.get("Built").unwrap(), &node.id());
+        assert_eq!(node.timeout(), Some(Duration::from_secs(1)));
+        assert_eq!(node.priority(), 3);
+        assert_eq!(node.describe().tags, vec!["io".to_string()]);
+        assert!(!node.retain_output());
+
+        let env = Arc::new(EnvVar::new(node_table));
+        let out = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async { node.run(env).await.get_out().unwrap() });
+        assert_eq!(out.get::<String>().unwrap(), "Hello world");
+    }
+
+    /// Test for creating a node with an action which may fail to be created.
+    ///
+    /// The error is returned without allocating an id, while a created action gets one.
+    #[test]
+    fn test_try_with_action() {
+        let mut node_table = NodeTable::new();
+        let failed = DefaultNode::try_with_action(
+            NodeName::from("Failed"),
+            || Err::<HelloAction, _>("connection refused"),
+            &mut node_table,
+        );
+        assert_eq!(failed.err(), Some("connection refused"));
+        assert!(node_table.get("Failed").is_none());
+
+        let node = DefaultNode::try_with_action(
+            NodeName::from("Created"),
+            || Ok::<_, &str>(HelloAction::new()),
+            &mut node_table,
+        )
+        .unwrap();
+        assert_eq!(node_table.get("Created").unwrap(), &node.id());
+    }
 }
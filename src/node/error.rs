@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+/// Errors raised by a [`Node`](crate::Node) outside of the [`Output`](crate::Output) of its run.
+#[derive(Clone, Debug)]
+pub enum NodeError {
+    /// A failure reported by the node itself, carrying a human-readable message.
+    Custom(String),
+    /// The node's run did not finish within the given time limit.
+    Timeout(Duration),
+    /// The node was still returning [`Output::Loop`](crate::Output::Loop) after running the
+    /// given number of times.
+    TooManyIterations(usize),
+    /// The node was blocked on sending, while all the other running nodes were blocked on
+    /// sending as well, waiting for each other to receive. See
+    /// [`Graph::set_watchdog`](crate::Graph::set_watchdog).
+    Deadlock,
+}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NodeError::Custom(msg) => write!(f, "{}", msg),
+            NodeError::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            NodeError::TooManyIterations(n) => write!(f, "did not stop looping after {} runs", n),
+            NodeError::Deadlock => write!(f, "deadlocked sending to the other running nodes"),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
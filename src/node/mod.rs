@@ -1,6 +1,13 @@
 pub mod action;
+pub mod collect_node;
 pub mod conditional_node;
+pub mod dedup_node;
 pub mod default_node;
+pub mod error;
 pub mod id_allocate;
+#[allow(clippy::module_inception)]
 pub mod node;
+pub mod node_context;
+pub mod registry;
+pub mod task_scope;
 pub mod typed_action;
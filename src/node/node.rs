@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use tokio::sync::Mutex;
@@ -8,7 +8,7 @@ use crate::{
     utils::{env::EnvVar, output::Output},
 };
 
-use super::id_allocate::alloc_id;
+use super::{error::NodeError, id_allocate::alloc_id};
 
 ///# The [`Node`] trait
 ///
@@ -31,6 +31,54 @@ pub trait Node: Send + Sync {
     fn output_channels(&mut self) -> &mut OutChannels;
     /// Execute a run of this node.
     async fn run(&mut self, env: Arc<EnvVar>) -> Output;
+    /// Prepare this node for execution, e.g. open connections or load models.
+    ///
+    /// The graph calls `warm_up` on all the nodes concurrently before any node starts
+    /// its `run`, so initialization costs are kept out of the execution. If any node
+    /// returns an error, the graph will not run. By default, it does nothing.
+    async fn warm_up(&mut self, _env: Arc<EnvVar>) -> Result<(), NodeError> {
+        Ok(())
+    }
+    /// The scheduling priority of this node. Among the nodes of a block, which are all spawned
+    /// together, the ones with higher priority are spawned first, see
+    /// [`Scheduler`](crate::Scheduler). By default, it returns 0.
+    fn priority(&self) -> u8 {
+        0
+    }
+    /// The estimated cost of running this node, used by
+    /// [`Graph::compute_critical_path`](crate::Graph::compute_critical_path). By default, it
+    /// returns 1, so that the critical path is the one with the most nodes.
+    fn weight(&self) -> u64 {
+        1
+    }
+    /// The resources this node uses while running. With the `resource-aware` feature, the graph
+    /// delays starting the node until they fit in its limits, see
+    /// [`Graph::set_resource_limits`](crate::Graph). By default, it requires nothing.
+    fn resource_requirements(&self) -> ResourceRequirements {
+        ResourceRequirements::default()
+    }
+    /// The timeout of every run of this node, unless one is set with
+    /// [`Graph::set_node_timeout`](crate::Graph::set_node_timeout). By default, it returns
+    /// `None`, and the default timeout of the graph applies, if any.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    /// Describes this node without running it, for tools inspecting a graph, e.g. visualizers
+    /// and validators. By default, it returns the id and the name of the node only, as the
+    /// channels are not accessible from `&self`: override it to report them, along with tags
+    /// and metadata.
+    fn describe(&self) -> NodeDescriptor {
+        NodeDescriptor::new(self.id(), self.name())
+    }
+    /// Whether the graph keeps the [`Output`] of this node once it completes successfully. By
+    /// default, it returns true.
+    ///
+    /// Return false for the intermediate nodes of long runs, whose outputs no one reads, so that
+    /// they are dropped rather than held in memory until the next run: see
+    /// [`Graph::get_outputs`](crate::Graph::get_outputs).
+    fn retain_output(&self) -> bool {
+        true
+    }
     /// Return true if this node is conditional node. By default, it returns false.
     fn is_condition(&self) -> bool {
         false
@@ -58,16 +106,155 @@ pub trait Node: Send + Sync {
     }
 }
 
+/// Forwards every method to the boxed node, so that a node created at runtime, e.g. by a
+/// [`NodeRegistry`](crate::NodeRegistry), can be added to a graph.
+#[async_trait]
+impl Node for Box<dyn Node> {
+    fn id(&self) -> NodeId {
+        (**self).id()
+    }
+    fn name(&self) -> NodeName {
+        (**self).name()
+    }
+    fn input_channels(&mut self) -> &mut InChannels {
+        (**self).input_channels()
+    }
+    fn output_channels(&mut self) -> &mut OutChannels {
+        (**self).output_channels()
+    }
+    async fn run(&mut self, env: Arc<EnvVar>) -> Output {
+        (**self).run(env).await
+    }
+    async fn warm_up(&mut self, env: Arc<EnvVar>) -> Result<(), NodeError> {
+        (**self).warm_up(env).await
+    }
+    fn priority(&self) -> u8 {
+        (**self).priority()
+    }
+    fn weight(&self) -> u64 {
+        (**self).weight()
+    }
+    fn resource_requirements(&self) -> ResourceRequirements {
+        (**self).resource_requirements()
+    }
+    fn timeout(&self) -> Option<Duration> {
+        (**self).timeout()
+    }
+    fn describe(&self) -> NodeDescriptor {
+        (**self).describe()
+    }
+    fn retain_output(&self) -> bool {
+        (**self).retain_output()
+    }
+    fn is_condition(&self) -> bool {
+        (**self).is_condition()
+    }
+    fn loop_structure(&self) -> Option<Vec<Arc<Mutex<dyn Node>>>> {
+        (**self).loop_structure()
+    }
+    fn has_typed_input(&self) -> bool {
+        (**self).has_typed_input()
+    }
+    fn has_typed_output(&self) -> bool {
+        (**self).has_typed_output()
+    }
+}
+
+/// The resources a [`Node`] uses while running, see [`Node::resource_requirements`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ResourceRequirements {
+    /// Number of CPUs, which can be fractional.
+    pub cpus: f32,
+    /// Memory in megabytes.
+    pub memory_mb: u64,
+}
+
+/// The metadata of a [`Node`], see [`Node::describe`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDescriptor {
+    pub id: NodeId,
+    pub name: NodeName,
+    /// The ids of the nodes sending to this node.
+    pub in_channel_ids: Vec<NodeId>,
+    /// The ids of the nodes this node sends to.
+    pub out_channel_ids: Vec<NodeId>,
+    pub tags: Vec<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl NodeDescriptor {
+    /// Create a [`NodeDescriptor`] of the node `id` named `name`, without channels, tags or
+    /// metadata.
+    pub fn new(id: NodeId, name: NodeName) -> Self {
+        Self {
+            id,
+            name,
+            in_channel_ids: Vec::new(),
+            out_channel_ids: Vec::new(),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeId(pub(crate) usize);
 
 impl NodeId {
+    /// The key of the input channel holding the packets preloaded by
+    /// [`Graph::run_with_input`](crate::Graph::run_with_input). It's never allocated to a node.
+    pub const INPUT: NodeId = NodeId(0);
+
+    /// Create a `NodeId` from a random (v4) UUID, which stays unique across process launches and
+    /// machines, unlike the ids allocated by [`NodeTable::alloc_id_for`].
+    ///
+    /// The 128 bits of the UUID are folded into the 64 bits of `usize`, with the highest bit
+    /// always set so that it never collides with a sequentially allocated id. This keeps 63
+    /// random bits, so the probability of any collision among `n` such ids is about
+    /// `n² / 2⁶⁴`: less than one in ten million for a million ids, but about 5% for a billion
+    /// ids. The `uuid` feature requires a 64-bit target, since a 32-bit `usize` would leave
+    /// too few random bits for global uniqueness.
+    #[cfg(feature = "uuid")]
+    pub fn new_uuid() -> NodeId {
+        let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+        let folded = (high ^ low) as usize;
+        NodeId(folded | !(usize::MAX >> 1))
+    }
+
+    /// Create a `NodeId` wrapping `id`, e.g. to wire channels with known ids in tests, or to
+    /// map the ids of an external scheme.
+    ///
+    /// This is an advanced API: a graph assumes its nodes have unique ids, and nothing prevents
+    /// `id` from colliding with an id allocated by [`NodeTable::alloc_id_for`], which remains
+    /// the normal way to get a `NodeId`. Ids allocated by the table start at 1, and 0 is
+    /// [`NodeId::INPUT`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::NodeId;
+    ///
+    /// let id = NodeId::new(42);
+    /// assert_eq!(id.as_usize(), 42);
+    /// assert_eq!(NodeId::from(42), id);
+    /// ```
+    pub fn new(id: usize) -> NodeId {
+        NodeId(id)
+    }
+
     /// Return the numeric identifier wrapped by this `NodeId`.
     pub fn as_usize(&self) -> usize {
         self.0
     }
 }
 
+/// Same as [`NodeId::new`], with the same caveats.
+impl From<usize> for NodeId {
+    fn from(value: usize) -> Self {
+        NodeId(value)
+    }
+}
+
 impl From<NodeId> for usize {
     fn from(value: NodeId) -> Self {
         value.0
@@ -78,6 +265,7 @@ pub type NodeName = String;
 
 /// [NodeTable]: a mapping from [Node]'s name to [NodeId].
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeTable(pub(crate) HashMap<NodeName, NodeId>);
 
 /// [NodeTable]'s name in [`EnvVar`].
@@ -92,7 +280,7 @@ impl NodeTable {
         let id = alloc_id();
         log::debug!("alloc id {:?} for {:?}", id, name);
 
-        if let Some(v) = self.0.insert(name.to_string(), id.clone()) {
+        if let Some(v) = self.0.insert(name.to_string(), id) {
             log::warn!("Node {} is already allocated with id {:?}.", name, v);
         };
         id
@@ -107,6 +295,45 @@ impl NodeTable {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// An iterator visiting all the registered names and their [`NodeId`]s in arbitrary order.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::NodeTable;
+    ///
+    /// let mut node_table = NodeTable::new();
+    /// let a = node_table.alloc_id_for("A");
+    /// let b = node_table.alloc_id_for("B");
+    ///
+    /// let mut entries: Vec<_> = node_table.iter().collect();
+    /// entries.sort();
+    /// assert_eq!(entries, vec![(&"A".to_string(), &a), (&"B".to_string(), &b)]);
+    /// assert_eq!(node_table.len(), 2);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&NodeName, &NodeId)> {
+        self.0.iter()
+    }
+
+    /// Returns the [`NodeId`]s of all the registered nodes in arbitrary order.
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.0.values().copied().collect()
+    }
+
+    /// Returns the names of all the registered nodes in arbitrary order.
+    pub fn node_names(&self) -> Vec<&NodeName> {
+        self.0.keys().collect()
+    }
+
+    /// Returns the number of registered nodes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no node is registered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl EnvVar {
@@ -115,4 +342,31 @@ impl EnvVar {
         let node_table: &NodeTable = self.get_ref(NODE_TABLE_STR).unwrap();
         node_table.get(node_name)
     }
+
+    /// Get a [`Node`]'s name by providing its [`NodeId`].
+    pub fn get_node_name(&self, id: &NodeId) -> Option<NodeName> {
+        let node_table: &NodeTable = self.get_ref(NODE_TABLE_STR).unwrap();
+        node_table
+            .iter()
+            .find(|(_, node_id)| *node_id == id)
+            .map(|(name, _)| name.clone())
+    }
+}
+
+#[cfg(all(test, feature = "uuid"))]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{NodeId, NodeTable};
+
+    #[test]
+    fn test_new_uuid() {
+        let mut node_table = NodeTable::new();
+        let sequential = node_table.alloc_id_for("a");
+
+        let ids: HashSet<NodeId> = (0..1000).map(|_| NodeId::new_uuid()).collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(!ids.contains(&sequential));
+        assert!(!ids.contains(&NodeId::INPUT));
+    }
 }
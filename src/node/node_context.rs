@@ -0,0 +1,71 @@
+//! The identity of the node currently running.
+//!
+//! # Example
+//! An action logging the name of the node it runs in, without the node being passed to it.
+//! ```rust
+//! use std::sync::Arc;
+//! use dagrs::{Action, EnvVar, InChannels, NodeContext, OutChannels, Output};
+//! use async_trait::async_trait;
+//!
+//! struct LogAction;
+//!
+//! #[async_trait]
+//! impl Action for LogAction {
+//!     async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+//!         let ctx = NodeContext::current();
+//!         println!("running node {} ({:?})", ctx.name(), ctx.id());
+//!         Output::empty()
+//!     }
+//! }
+//! ```
+
+use std::future::Future;
+
+use super::node::{NodeId, NodeName};
+
+tokio::task_local! {
+    static CURRENT: NodeContext;
+}
+
+/// The id and the name of a node, available to its [`Action`](crate::Action) during its run.
+///
+/// The `Graph` sets the context for every run of a node, see [`NodeContext::current`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeContext {
+    id: NodeId,
+    name: NodeName,
+}
+
+impl NodeContext {
+    pub(crate) fn new(id: NodeId, name: NodeName) -> Self {
+        Self { id, name }
+    }
+
+    /// Returns the context of the node currently running.
+    ///
+    /// # Panics
+    /// Panics if called outside of a node's `run`, e.g. in a task spawned with `tokio::spawn`.
+    pub fn current() -> NodeContext {
+        Self::try_current().expect("NodeContext::current() called outside of a node run")
+    }
+
+    /// Returns the context of the node currently running, or `None` outside of a node's `run`.
+    pub fn try_current() -> Option<NodeContext> {
+        CURRENT.try_with(NodeContext::clone).ok()
+    }
+
+    /// The id of the node.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The name of the node.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Runs `fut` with this context as the current one.
+    pub(crate) async fn enter<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+}
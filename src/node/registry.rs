@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::utils::env::EnvVar;
+
+use super::node::{Node, NodeId};
+
+type Factory = Box<dyn Fn(NodeId, Arc<EnvVar>) -> Box<dyn Node> + Send + Sync>;
+
+/// A mapping from type names to the factories of nodes, used to build a graph from a
+/// [`GraphSpec`](crate::GraphSpec) with [`Graph::build_from_spec`](crate::Graph::build_from_spec).
+///
+/// A factory is given the [`NodeId`] allocated for the node, which the node must return as its
+/// id, and the environment of the graph, where the name of the node can be looked up with
+/// [`EnvVar::get_node_name`].
+///
+/// # Example
+/// ```rust
+/// use dagrs::{DefaultNode, EmptyAction, NodeRegistry};
+///
+/// let mut registry = NodeRegistry::new();
+/// registry.register("empty", |id, env| {
+///     let name = env.get_node_name(&id).unwrap();
+///     Box::new(DefaultNode::with_id(id, name, EmptyAction))
+/// });
+/// assert!(registry.contains("empty"));
+/// ```
+#[derive(Default)]
+pub struct NodeRegistry {
+    factories: HashMap<String, Factory>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the `factory` of the nodes of type `type_name`, replacing the factory
+    /// registered before, if any.
+    pub fn register<F>(&mut self, type_name: &str, factory: F)
+    where
+        F: Fn(NodeId, Arc<EnvVar>) -> Box<dyn Node> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(type_name.to_string(), Box::new(factory));
+    }
+
+    /// Returns true if a factory is registered for `type_name`.
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.factories.contains_key(type_name)
+    }
+
+    /// Creates a node of type `type_name`, or returns `None` if the type is not registered.
+    pub(crate) fn create(
+        &self,
+        type_name: &str,
+        id: NodeId,
+        env: Arc<EnvVar>,
+    ) -> Option<Box<dyn Node>> {
+        self.factories
+            .get(type_name)
+            .map(|factory| factory(id, env))
+    }
+}
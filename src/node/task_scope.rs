@@ -0,0 +1,89 @@
+//! Background tasks tied to the lifetime of a node run.
+//!
+//! # Example
+//! A node keeps sending heartbeats while it works. The heartbeat task is aborted as soon as
+//! the node returns, so it never outlives the node.
+//! ```rust
+//! use std::{sync::Arc, time::Duration};
+//! use dagrs::{Action, EnvVar, InChannels, OutChannels, Output, TaskScope};
+//! use async_trait::async_trait;
+//!
+//! struct WorkAction;
+//!
+//! #[async_trait]
+//! impl Action for WorkAction {
+//!     async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+//!         TaskScope::current().spawn(async {
+//!             loop {
+//!                 println!("still working");
+//!                 tokio::time::sleep(Duration::from_millis(100)).await;
+//!             }
+//!         });
+//!         tokio::time::sleep(Duration::from_secs(1)).await;
+//!         Output::empty()
+//!     }
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::task::{AbortHandle, JoinSet};
+
+tokio::task_local! {
+    static CURRENT: TaskScope;
+}
+
+/// A set of background tasks spawned by a node, built on [`JoinSet`].
+///
+/// The `Graph` creates a scope for every run of a node. All the tasks spawned in it are
+/// **aborted, not awaited**, when the node returns, fails, panics or times out. To wait for a
+/// task, await it inside `run` through other means, e.g. a channel.
+#[derive(Clone, Default)]
+pub struct TaskScope(Arc<Mutex<JoinSet<()>>>);
+
+impl TaskScope {
+    /// Returns the scope of the node currently running.
+    ///
+    /// # Panics
+    /// Panics if called outside of a node's `run`, e.g. in a task spawned with `tokio::spawn`.
+    pub fn current() -> TaskScope {
+        CURRENT
+            .try_with(TaskScope::clone)
+            .expect("TaskScope::current() called outside of a node run")
+    }
+
+    /// Spawns `task` in this scope. Returns a handle that can abort it earlier.
+    pub fn spawn<F>(&self, task: F) -> AbortHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut tasks = self.0.lock().unwrap();
+        // Drop the finished tasks, so that they do not pile up in a long run.
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(task)
+    }
+
+    /// Runs `fut` with this scope as the current one.
+    pub(crate) async fn enter<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+
+    /// Aborts all the tasks in this scope when the returned guard is dropped.
+    pub(crate) fn abort_on_drop(&self) -> AbortOnDrop {
+        AbortOnDrop(self.clone())
+    }
+}
+
+/// See [`TaskScope::abort_on_drop`].
+pub(crate) struct AbortOnDrop(TaskScope);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Ok(mut tasks) = self.0 .0.lock() {
+            tasks.abort_all();
+        }
+    }
+}
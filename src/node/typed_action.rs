@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
@@ -35,7 +35,7 @@ pub trait TypedAction: Send + Sync {
     ///
     /// Returns a typed input channel with the type specified by the associated type `I`
     fn make_typed_in_channels(&self, in_channels: &InChannels) -> TypedInChannels<Self::I> {
-        TypedInChannels(in_channels.0.clone(), PhantomData::default())
+        TypedInChannels::new(in_channels.receivers.clone())
     }
 
     /// Converts raw output channels to typed output channels
@@ -48,7 +48,7 @@ pub trait TypedAction: Send + Sync {
     ///
     /// Returns a typed output channel with the type specified by the associated type `O`
     fn make_typed_out_channels(&self, out_channels: &OutChannels) -> TypedOutChannels<Self::O> {
-        TypedOutChannels(out_channels.0.clone(), PhantomData::default())
+        TypedOutChannels::new(out_channels.senders.clone())
     }
 
     /// The method that users need to implement to define their action logic
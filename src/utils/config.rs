@@ -0,0 +1,166 @@
+use std::{fs, path::Path};
+
+use crate::node::node::NodeTable;
+
+use super::env::EnvVar;
+
+/// Errors of loading an [`EnvVar`] from a configuration file, see [`EnvVar::from_toml`] and
+/// [`EnvVar::from_json`].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file cannot be read.
+    Io(std::io::Error),
+    /// The file is not valid TOML or JSON, with the reason.
+    Parse(String),
+    /// The value of the key is neither a scalar nor a table, e.g. an array.
+    UnsupportedValue(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read the configuration: {}", e),
+            ConfigError::Parse(reason) => write!(f, "invalid configuration: {}", reason),
+            ConfigError::UnsupportedValue(key) => {
+                write!(f, "unsupported configuration value for key {}", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl EnvVar {
+    /// Load the variables of a TOML file into a new [`EnvVar`] with an empty [`NodeTable`].
+    ///
+    /// Scalar values are loaded as `String`, `i64`, `f64` or `bool`. The values of a nested
+    /// table are loaded under the keys of the table and the value joined by `.`, e.g.
+    /// `db.port` for the key `port` of the table `[db]`. Any other value, e.g. an array or a
+    /// datetime, fails with [`ConfigError::UnsupportedValue`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::path::Path;
+    /// use dagrs::EnvVar;
+    ///
+    /// let env = EnvVar::from_toml(Path::new("pipeline.toml")).unwrap();
+    /// let port: Option<i64> = env.get("db.port");
+    /// ```
+    pub fn from_toml(path: &Path) -> Result<EnvVar, ConfigError> {
+        let table: toml::Table = fs::read_to_string(path)?
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+        let mut env = EnvVar::new(NodeTable::default());
+        for (key, value) in table {
+            env.set_toml(key, value)?;
+        }
+        Ok(env)
+    }
+
+    /// Load the variables of a JSON file into a new [`EnvVar`] with an empty [`NodeTable`].
+    ///
+    /// The file must hold an object. The values are loaded like [`EnvVar::from_toml`], with
+    /// the integers that do not fit in an `i64` loaded as `f64`. Any other value, e.g. an array
+    /// or `null`, fails with [`ConfigError::UnsupportedValue`].
+    pub fn from_json(path: &Path) -> Result<EnvVar, ConfigError> {
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| ConfigError::Parse(e.to_string()))?;
+        let serde_json::Value::Object(object) = value else {
+            return Err(ConfigError::Parse("the root is not an object".to_string()));
+        };
+        let mut env = EnvVar::new(NodeTable::default());
+        for (key, value) in object {
+            env.set_json(key, value)?;
+        }
+        Ok(env)
+    }
+
+    fn set_toml(&mut self, key: String, value: toml::Value) -> Result<(), ConfigError> {
+        match value {
+            toml::Value::String(v) => self.set(&key, v),
+            toml::Value::Integer(v) => self.set(&key, v),
+            toml::Value::Float(v) => self.set(&key, v),
+            toml::Value::Boolean(v) => self.set(&key, v),
+            toml::Value::Table(table) => {
+                for (name, value) in table {
+                    self.set_toml(format!("{}.{}", key, name), value)?;
+                }
+            }
+            toml::Value::Array(_) | toml::Value::Datetime(_) => {
+                return Err(ConfigError::UnsupportedValue(key))
+            }
+        }
+        Ok(())
+    }
+
+    fn set_json(&mut self, key: String, value: serde_json::Value) -> Result<(), ConfigError> {
+        match value {
+            serde_json::Value::String(v) => self.set(&key, v),
+            serde_json::Value::Number(v) => match (v.as_i64(), v.as_f64()) {
+                (Some(v), _) => self.set(&key, v),
+                (None, Some(v)) => self.set(&key, v),
+                (None, None) => return Err(ConfigError::UnsupportedValue(key)),
+            },
+            serde_json::Value::Bool(v) => self.set(&key, v),
+            serde_json::Value::Object(object) => {
+                for (name, value) in object {
+                    self.set_json(format!("{}.{}", key, name), value)?;
+                }
+            }
+            serde_json::Value::Array(_) | serde_json::Value::Null => {
+                return Err(ConfigError::UnsupportedValue(key))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test for loading the same configuration from TOML and JSON.
+    ///
+    /// Both files give scalars of every type and a nested table, loaded under namespaced keys,
+    /// and an array is rejected.
+    #[test]
+    fn test_load_config() {
+        let dir = std::env::temp_dir().join(format!("dagrs-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let toml_path = dir.join("config.toml");
+        let json_path = dir.join("config.json");
+        fs::write(
+            &toml_path,
+            "name = \"dagrs\"\nworkers = 4\n[db]\nratio = 0.5\n[db.pool]\nenabled = true\n",
+        )
+        .unwrap();
+        fs::write(
+            &json_path,
+            r#"{"name": "dagrs", "workers": 4, "db": {"ratio": 0.5, "pool": {"enabled": true}}}"#,
+        )
+        .unwrap();
+
+        for env in [
+            EnvVar::from_toml(&toml_path).unwrap(),
+            EnvVar::from_json(&json_path).unwrap(),
+        ] {
+            assert_eq!(env.get::<String>("name").unwrap(), "dagrs");
+            assert_eq!(env.get::<i64>("workers"), Some(4));
+            assert_eq!(env.get::<f64>("db.ratio"), Some(0.5));
+            assert_eq!(env.get::<bool>("db.pool.enabled"), Some(true));
+        }
+
+        fs::write(&toml_path, "[db]\nhosts = [\"a\", \"b\"]\n").unwrap();
+        assert!(matches!(
+            EnvVar::from_toml(&toml_path),
+            Err(ConfigError::UnsupportedValue(key)) if key == "db.hosts"
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,11 @@
-use std::collections::HashMap;
+use std::{
+    any,
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use crate::{
     connection::information_packet::Content,
@@ -17,11 +24,33 @@ pub type Variable = Content;
 ///
 /// Variables that [`EnvVar`] should have:
 /// - [NodeTable] : a mapping from node's name to `NodeId`.
-/// During the runtime of a `Graph`, [`NodeTable`] allows
-/// each `Node` to look up the id of a specific node by its name.
+///   During the runtime of a `Graph`, [`NodeTable`] allows
+///   each `Node` to look up the id of a specific node by its name.
 #[derive(Debug, Clone)]
 pub struct EnvVar {
     variables: HashMap<String, Variable>,
+    /// The progress of the current run, shared with the clones.
+    progress: Arc<Progress>,
+}
+
+/// The number of nodes completed and to run in the current run, see [`EnvVar::graph_progress`].
+#[derive(Debug, Default)]
+pub(crate) struct Progress {
+    completed: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl Progress {
+    /// Start a run of `total` nodes.
+    pub(crate) fn start(&self, total: usize) {
+        self.completed.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Count a node completing.
+    pub(crate) fn complete(&self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl EnvVar {
@@ -29,6 +58,7 @@ impl EnvVar {
     pub fn new(node_table: NodeTable) -> Self {
         let mut env = Self {
             variables: HashMap::default(),
+            progress: Arc::default(),
         };
         env.set(NODE_TABLE_STR, node_table);
         env
@@ -64,4 +94,272 @@ impl EnvVar {
             None
         }
     }
+
+    /// Returns true if the variable `name` is set.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    /// Remove the variable `name`, and returns true if it was set, e.g. to clean up the
+    /// variables of a subgraph before the next run.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.variables.remove(name).is_some()
+    }
+
+    /// Returns the names of all the variables, sorted, including the [`NodeTable`] set by
+    /// [`EnvVar::new`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{EnvVar, NodeTable};
+    ///
+    /// let mut env = EnvVar::new(NodeTable::default());
+    /// env.set("b", 1);
+    /// env.set("a", 2);
+    /// assert!(env.remove("b"));
+    /// assert!(!env.contains_key("b"));
+    /// assert!(env.keys().contains(&"a".to_string()));
+    /// ```
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.variables.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns the number of nodes completed in the current run of the graph, and the number of
+    /// nodes to run, e.g. for a node rendering a progress bar. Reading it is cheap, and never
+    /// waits for the graph.
+    ///
+    /// Only the nodes known when the run starts are counted, except the ones skipped, e.g.
+    /// restored from a snapshot. A node counts as completed once its task ends, whether it
+    /// succeeds or fails, and the nodes after a conditional node returning false never
+    /// complete. Both numbers are 0 before the first run.
+    pub fn graph_progress(&self) -> (usize, usize) {
+        (
+            self.progress.completed.load(Ordering::Relaxed),
+            self.progress.total.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Captures all the variables as they are now, see [`EnvVarSnapshot`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{EnvVar, NodeTable};
+    ///
+    /// let mut env = EnvVar::new(NodeTable::default());
+    /// env.set("threshold", 0.5f64);
+    /// let snapshot = env.snapshot();
+    /// env.set("threshold", 0.9f64);
+    /// env.remove("threshold");
+    /// assert_eq!(snapshot.get::<f64>("threshold"), Some(0.5));
+    /// assert!(!env.contains_key("threshold"));
+    /// ```
+    pub fn snapshot(&self) -> EnvVarSnapshot {
+        EnvVarSnapshot {
+            variables: self
+                .variables
+                .iter()
+                .map(|(name, var)| (name.clone(), var.clone()))
+                .collect(),
+        }
+    }
+
+    /// The progress of the runs of the graph using this environment.
+    pub(crate) fn progress(&self) -> Arc<Progress> {
+        self.progress.clone()
+    }
+
+    /// Returns true if the running node has used up its cooperative budget, and should yield
+    /// to the runtime, e.g. with [`tokio::task::yield_now`], to let the other nodes progress.
+    ///
+    /// The budget is consumed by the operations on tokio resources, such as receiving from
+    /// the input channels, and refilled when the node yields. Pure computation does not consume
+    /// it: a node computing for long between two awaits should rather yield at intervals, see
+    /// [`yielding`](crate::yielding).
+    pub fn should_yield(&self) -> bool {
+        !tokio::task::coop::has_budget_remaining()
+    }
+}
+
+/// # Snapshot of an [`EnvVar`]
+///
+/// The variables of an [`EnvVar`] at a point in time, taken with [`EnvVar::snapshot`], e.g.
+/// to record the environment of a run for auditing, or as a fixture of a test. It cannot be
+/// modified, and is not affected by the later changes of the [`EnvVar`]: setting or removing
+/// a variable never changes a value, it only replaces it.
+///
+/// The values are shared with the [`EnvVar`] rather than copied, since their type is erased:
+/// only a value with interior mutability, e.g. a `Mutex`, can still change through it.
+///
+/// With the `serde` feature, it can be serialized as long as the values can, see
+/// [`Content`], into a map of the variables sorted by name.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct EnvVarSnapshot {
+    variables: BTreeMap<String, Variable>,
+}
+
+impl EnvVarSnapshot {
+    /// Get a variable of the snapshot, see [`EnvVar::get`].
+    pub fn get<H: Send + Sync + Clone + 'static>(&self, name: &str) -> Option<H> {
+        self.get_ref(name).cloned()
+    }
+
+    /// Get a variable of the snapshot, see [`EnvVar::get_ref`].
+    pub fn get_ref<H: Send + Sync + 'static>(&self, name: &str) -> Option<&H> {
+        self.variables.get(name).and_then(Variable::get)
+    }
+
+    /// Returns true if the variable `name` was set.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    /// Returns the names of all the variables, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        self.variables.keys().cloned().collect()
+    }
+}
+
+/// # Builder of [`EnvVar`]
+///
+/// Sets the variables like [`EnvVar::set`], and checks that the variables the nodes rely on
+/// are set with the right type before the graph runs, rather than failing in the middle of
+/// the run.
+///
+/// # Example
+/// ```rust
+/// use dagrs::{EnvVarBuilder, NodeTable};
+///
+/// let env = EnvVarBuilder::new(NodeTable::default())
+///     .set("threshold", 0.5f64)
+///     .require::<f64>("threshold")
+///     .build()
+///     .unwrap();
+/// assert_eq!(env.get::<f64>("threshold"), Some(0.5));
+///
+/// let error = EnvVarBuilder::new(NodeTable::default())
+///     .set("threshold", "0.5")
+///     .require::<f64>("threshold")
+///     .require::<String>("model")
+///     .build()
+///     .unwrap_err();
+/// assert_eq!(error.missing, vec!["model".to_string()]);
+/// assert_eq!(error.mismatched.len(), 1);
+/// ```
+pub struct EnvVarBuilder {
+    env: EnvVar,
+    required: Vec<Requirement>,
+}
+
+/// A variable required by [`EnvVarBuilder::require`].
+struct Requirement {
+    name: String,
+    /// Checks the type of the value.
+    is_type: fn(&Variable) -> bool,
+    type_name: &'static str,
+}
+
+impl EnvVarBuilder {
+    /// Start building an [`EnvVar`] with `node_table`, see [`EnvVar::new`].
+    pub fn new(node_table: NodeTable) -> Self {
+        Self {
+            env: EnvVar::new(node_table),
+            required: Vec::new(),
+        }
+    }
+
+    /// Set the variable `name` to `var`, see [`EnvVar::set`].
+    pub fn set<T: Send + Sync + 'static>(mut self, name: &str, var: T) -> Self {
+        self.env.set(name, var);
+        self
+    }
+
+    /// Require the variable `name` to be set with a value of type `T` when building.
+    pub fn require<T: Send + Sync + 'static>(mut self, name: &str) -> Self {
+        self.required.push(Requirement {
+            name: name.to_owned(),
+            is_type: Variable::is::<T>,
+            type_name: any::type_name::<T>(),
+        });
+        self
+    }
+
+    /// Returns the [`EnvVar`], or an [`EnvVarError`] listing the required variables missing
+    /// or set with another type. More variables can still be set on the [`EnvVar`], e.g.
+    /// under keys only known at runtime.
+    pub fn build(self) -> Result<EnvVar, EnvVarError> {
+        let mut error = EnvVarError::default();
+        for required in &self.required {
+            match self.env.variables.get(&required.name) {
+                None => error.missing.push(required.name.clone()),
+                Some(var) if !(required.is_type)(var) => error.mismatched.push((
+                    required.name.clone(),
+                    required.type_name,
+                    var.type_name(),
+                )),
+                Some(_) => {}
+            }
+        }
+        if error.missing.is_empty() && error.mismatched.is_empty() {
+            Ok(self.env)
+        } else {
+            Err(error)
+        }
+    }
+}
+
+/// The required variables which are not set properly, see [`EnvVarBuilder::build`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvVarError {
+    /// The required variables which are not set.
+    pub missing: Vec<String>,
+    /// The required variables set with another type: the name of the variable, the required
+    /// type and the type of the value.
+    pub mismatched: Vec<(String, &'static str, &'static str)>,
+}
+
+impl std::fmt::Display for EnvVarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid environment variables:")?;
+        for name in &self.missing {
+            write!(f, "\n- {} is missing", name)?;
+        }
+        for (name, expected, found) in &self.mismatched {
+            write!(
+                f,
+                "\n- {} is a {}, but {} is required",
+                name, found, expected
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for EnvVarError {}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    /// Test for serializing a snapshot of the environment.
+    ///
+    /// The variables are serialized sorted by name, with the node table, while a snapshot
+    /// holding a value which cannot be serialized fails.
+    #[test]
+    fn test_serialize_snapshot() {
+        let mut env = EnvVar::new(NodeTable::default());
+        env.set("retries", 3u32);
+        env.set("model", "small".to_string());
+        let json = serde_json::to_string(&env.snapshot()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"model":{"String":"small"},"node_table":{"NodeTable":{}},"retries":{"U32":3}}"#
+        );
+
+        env.set("pool", std::sync::Mutex::new(0));
+        assert!(serde_json::to_string(&env.snapshot()).is_err());
+    }
 }
@@ -10,8 +10,14 @@ use crate::connection::information_packet::Content;
 pub(crate) struct ExecState {
     /// The execution succeed or not.
     success: AtomicBool,
+    /// The execution is finished or not.
+    finished: AtomicBool,
     /// Output produced by a task.
     output: Arc<Mutex<Output>>,
+    /// The output was dropped, see [`Node::retain_output`](crate::Node::retain_output).
+    discarded: AtomicBool,
+    /// Logs produced by the task, see [`Output::with_logs`].
+    logs: Mutex<Vec<String>>,
     /*/// The semaphore is used to control the synchronous blocking of subsequent tasks to obtain the
     /// execution results of this task.
     /// When a task is successfully executed, the permits inside the semaphore will be increased to
@@ -28,7 +34,10 @@ impl ExecState {
         // initialize the task to failure without output.
         Self {
             success: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
             output: Arc::new(Mutex::new(Output::empty())),
+            discarded: AtomicBool::new(false),
+            logs: Mutex::new(Vec::new()),
             //semaphore: Semaphore::new(0),
         }
     }
@@ -48,12 +57,41 @@ impl ExecState {
         self.output.lock().unwrap().clone()
     }
 
+    /// Drop the output, which is not retained by the graph.
+    pub(crate) fn discard_output(&self) {
+        *self.output.lock().unwrap() = Output::empty();
+        self.discarded.store(true, Ordering::Relaxed);
+    }
+
+    /// Append the logs of a run of the task.
+    pub(crate) fn add_logs(&self, logs: Vec<String>) {
+        self.logs.lock().unwrap().extend(logs);
+    }
+
+    pub(crate) fn get_logs(&self) -> Vec<String> {
+        self.logs.lock().unwrap().clone()
+    }
+
+    pub(crate) fn is_discarded(&self) -> bool {
+        self.discarded.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_success(&self) -> bool {
+        self.success.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn exe_success(&self) {
-        self.success.store(true, Ordering::Relaxed)
+        self.success.store(true, Ordering::Relaxed);
+        self.finished.store(true, Ordering::Relaxed);
     }
 
     pub(crate) fn exe_fail(&self) {
-        self.success.store(false, Ordering::Relaxed)
+        self.success.store(false, Ordering::Relaxed);
+        self.finished.store(true, Ordering::Relaxed);
     }
 
     /*/// The semaphore is used to control the synchronous acquisition of task output results.
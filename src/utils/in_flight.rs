@@ -0,0 +1,63 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use tokio::sync::Notify;
+
+/// Counter of the work in flight in a `Graph`: the running nodes and the packets sent but
+/// not yet received.
+///
+/// The graph is quiescent when the counter drops to 0, and [`InFlight::wait_idle`] wakes up.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    count: AtomicUsize,
+    notify: Notify,
+}
+
+impl InFlight {
+    pub(crate) fn add(&self, n: usize) {
+        self.count.fetch_add(n, Ordering::AcqRel);
+    }
+
+    /// Saturates at 0, in case a packet sent before tracking started is received.
+    pub(crate) fn sub(&self, n: usize) {
+        let prev = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                Some(count.saturating_sub(n))
+            })
+            .unwrap();
+        if prev > 0 && prev <= n {
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Count a running node until the returned guard is dropped, which also covers a node task
+    /// that panics or is aborted.
+    pub(crate) fn enter(self: &Arc<Self>) -> InFlightGuard {
+        self.add(1);
+        InFlightGuard(self.clone())
+    }
+
+    /// Wait until the counter drops to 0.
+    pub(crate) async fn wait_idle(&self) {
+        loop {
+            // Register before checking, so that a notification in between is not missed.
+            let notified = self.notify.notified();
+            if self.count.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// See [`InFlight::enter`].
+pub(crate) struct InFlightGuard(Arc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.sub(1);
+    }
+}
@@ -1,3 +1,10 @@
+#[cfg(feature = "config")]
+pub mod config;
 pub mod env;
 pub mod execstate;
+pub(crate) mod in_flight;
 pub mod output;
+#[cfg(feature = "resource-aware")]
+pub(crate) mod resource_pool;
+pub(crate) mod watchdog;
+pub mod yielding;
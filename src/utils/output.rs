@@ -23,7 +23,7 @@
 //! use dagrs::Content;
 //! let err_out = Output::Err("some error messages!".to_string());
 
-use crate::connection::information_packet::Content;
+use crate::{connection::information_packet::Content, node::node::NodeId};
 
 /// [`Output`] represents the output of a node. Different from information packet (`Content`,
 /// used to communicate with other Nodes), `Output` carries the information that `Node`
@@ -35,6 +35,12 @@ pub enum Output {
     ErrWithExitCode(Option<i32>, Option<Content>),
     /// ...
     ConditionResult(bool),
+    /// Asks the graph to run the node again, with the content put into a new input channel
+    /// keyed by the node's own [`NodeId`]. The number of runs is limited by
+    /// [`Graph::set_max_loop_iterations`](crate::Graph::set_max_loop_iterations).
+    Loop(Content),
+    /// The output of the node along with the logs it produced, see [`Output::with_logs`].
+    WithLogs(Box<Output>, Vec<String>),
 }
 
 impl Output {
@@ -61,11 +67,72 @@ impl Output {
         Self::ErrWithExitCode(code, msg)
     }
 
+    /// Attach `logs` to the output, e.g. warnings or the paths of the files written by the
+    /// node, which are collected by the graph rather than sent to the successors, see
+    /// [`Graph::get_logs`](crate::Graph::get_logs). The output itself is unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::Output;
+    ///
+    /// let out = Output::new(1).with_logs(vec!["3 rows skipped".to_string()]);
+    /// assert_eq!(out.get_out().unwrap().get::<i32>(), Some(&1));
+    /// ```
+    pub fn with_logs(self, logs: Vec<String>) -> Output {
+        match self {
+            Self::WithLogs(out, mut previous) => {
+                previous.extend(logs);
+                Self::WithLogs(out, previous)
+            }
+            out => Self::WithLogs(Box::new(out), logs),
+        }
+    }
+
+    /// Split the output from its logs, if any.
+    pub(crate) fn take_logs(self) -> (Output, Vec<String>) {
+        match self {
+            Self::WithLogs(out, logs) => (*out, logs),
+            out => (out, Vec::new()),
+        }
+    }
+
+    /// Applies `f` to the content of [`Output::Out`], leaving the other variants, as well as
+    /// an empty output, unchanged.
+    pub fn map_content<F: FnOnce(Content) -> Content>(self, f: F) -> Output {
+        match self {
+            Self::Out(Some(content)) => Self::Out(Some(f(content))),
+            Self::WithLogs(out, logs) => Self::WithLogs(Box::new(out.map_content(f)), logs),
+            other => other,
+        }
+    }
+
+    /// Replaces an [`Output::Out`] with the output returned by `f` on its content, leaving the
+    /// other variants, as well as an empty output, unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use dagrs::{Content, Output};
+    ///
+    /// let out = Output::new(-1).and_then(|content| match content.get::<i32>() {
+    ///     Some(v) if *v >= 0 => Output::Out(Some(content)),
+    ///     _ => Output::error("negative value".to_string()),
+    /// });
+    /// assert_eq!(out.get_err(), Some("negative value".to_string()));
+    /// ```
+    pub fn and_then<F: FnOnce(Content) -> Output>(self, f: F) -> Output {
+        match self {
+            Self::Out(Some(content)) => f(content),
+            Self::WithLogs(out, logs) => out.and_then(f).with_logs(logs),
+            other => other,
+        }
+    }
+
     /// Determine whether [`Output`] stores error information.
     pub(crate) fn is_err(&self) -> bool {
         match self {
             Self::Err(_) | Self::ErrWithExitCode(_, _) => true,
-            Self::Out(_) | Self::ConditionResult(_) => false,
+            Self::Out(_) | Self::ConditionResult(_) | Self::Loop(_) => false,
+            Self::WithLogs(out, _) => out.is_err(),
         }
     }
 
@@ -73,14 +140,19 @@ impl Output {
     pub fn get_out(&self) -> Option<Content> {
         match self {
             Self::Out(ref out) => out.clone(),
-            Self::Err(_) | Self::ErrWithExitCode(_, _) | Self::ConditionResult(_) => None,
+            Self::WithLogs(out, _) => out.get_out(),
+            Self::Err(_)
+            | Self::ErrWithExitCode(_, _)
+            | Self::ConditionResult(_)
+            | Self::Loop(_) => None,
         }
     }
 
     /// Get error information stored in [`Output`].
     pub fn get_err(&self) -> Option<String> {
         match self {
-            Self::Out(_) | Self::ConditionResult(_) => None,
+            Self::Out(_) | Self::ConditionResult(_) | Self::Loop(_) => None,
+            Self::WithLogs(out, _) => out.get_err(),
             Self::Err(err) => Some(err.to_string()),
             Self::ErrWithExitCode(code, _) => {
                 let error_code = code.map_or("".to_string(), |v| v.to_string());
@@ -96,7 +168,39 @@ impl Output {
     pub(crate) fn conditional_result(&self) -> Option<bool> {
         match self {
             Self::ConditionResult(b) => Some(*b),
+            Self::WithLogs(out, _) => out.conditional_result(),
             _ => None,
         }
     }
 }
+
+/// Errors of getting the typed output of a node from the `Graph`.
+/// - NoSuchNode: the graph has no execution record of the node, e.g. it does not exist or
+///   has not run yet.
+/// - NoOutput: the node failed, or returned no output.
+/// - TypeMismatch: the output of the node is not of the expected type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputError {
+    NoSuchNode(NodeId),
+    NoOutput(NodeId),
+    TypeMismatch {
+        node_id: NodeId,
+        expected: &'static str,
+    },
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OutputError::NoSuchNode(id) => write!(f, "node {} has no execution record", id.0),
+            OutputError::NoOutput(id) => write!(f, "node {} produced no output", id.0),
+            OutputError::TypeMismatch { node_id, expected } => write!(
+                f,
+                "output of node {} is not of type {}",
+                node_id.0, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
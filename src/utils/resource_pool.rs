@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::node::node::ResourceRequirements;
+
+/// # Resource Pool
+/// Limits the total resources required by the running nodes, see
+/// [`Graph::set_resource_limits`](crate::Graph::set_resource_limits).
+///
+/// Each resource is a [`Semaphore`] weighted by resource units: CPUs are counted in thousandths,
+/// and memory in megabytes. A node requiring more than a limit is given the whole of it, so that
+/// it runs alone rather than never.
+pub(crate) struct ResourcePool {
+    cpus: Arc<Semaphore>,
+    memory: Arc<Semaphore>,
+    cpu_limit: u32,
+    memory_limit: u32,
+}
+
+/// The resources held by a running node, released on drop.
+pub(crate) struct ResourcePermit {
+    _cpus: OwnedSemaphorePermit,
+    _memory: OwnedSemaphorePermit,
+}
+
+impl ResourcePool {
+    pub(crate) fn new(limits: ResourceRequirements) -> Self {
+        let cpu_limit = cpu_units(limits.cpus);
+        let memory_limit = memory_units(limits.memory_mb);
+        Self {
+            cpus: Arc::new(Semaphore::new(cpu_limit as usize)),
+            memory: Arc::new(Semaphore::new(memory_limit as usize)),
+            cpu_limit,
+            memory_limit,
+        }
+    }
+
+    /// Wait until the `requirements` fit in the resources left, and take them.
+    ///
+    /// The CPUs are always taken before the memory, so that two nodes never wait for each other.
+    pub(crate) async fn acquire(&self, requirements: ResourceRequirements) -> ResourcePermit {
+        let cpus = cpu_units(requirements.cpus).min(self.cpu_limit);
+        let memory = memory_units(requirements.memory_mb).min(self.memory_limit);
+        // The semaphores are never closed.
+        let cpus = self.cpus.clone().acquire_many_owned(cpus).await.unwrap();
+        let memory = self
+            .memory
+            .clone()
+            .acquire_many_owned(memory)
+            .await
+            .unwrap();
+        ResourcePermit {
+            _cpus: cpus,
+            _memory: memory,
+        }
+    }
+}
+
+fn cpu_units(cpus: f32) -> u32 {
+    (cpus.max(0.0) * 1000.0).round() as u32
+}
+
+fn memory_units(memory_mb: u64) -> u32 {
+    memory_mb.min(u32::MAX as u64) as u32
+}
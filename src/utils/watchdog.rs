@@ -0,0 +1,109 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use tokio::sync::Notify;
+
+use crate::node::node::NodeId;
+
+/// The activity of a node watched by the watchdog, see
+/// [`Graph::set_watchdog`](crate::Graph::set_watchdog).
+#[derive(Debug, Default)]
+pub(crate) struct WatchState {
+    /// Unix timestamp in milliseconds of the last send or receive of the node.
+    pub(crate) last_activity: Arc<AtomicU64>,
+    /// Number of sends of the node in progress, which are blocked if they last.
+    pub(crate) pending_sends: Arc<AtomicUsize>,
+    running: AtomicBool,
+    /// Set when the watchdog finds the node in a deadlock rather than stalled.
+    deadlocked: AtomicBool,
+    restart: Notify,
+}
+
+impl WatchState {
+    /// Mark the node as running, which counts as an activity. A deadlock found in a previous
+    /// run is forgotten, as the states are kept across the runs of the graph.
+    pub(crate) fn start(&self) {
+        touch(&self.last_activity);
+        self.deadlocked.store(false, Ordering::Relaxed);
+        self.running.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    /// Wait until the watchdog finds the running node stalled, or deadlocked, see
+    /// [`WatchState::is_deadlocked`].
+    pub(crate) async fn stalled(&self) {
+        self.restart.notified().await
+    }
+
+    /// Returns true if the node is stalled in a deadlock, and must not be restarted.
+    pub(crate) fn is_deadlocked(&self) -> bool {
+        self.deadlocked.load(Ordering::Relaxed)
+    }
+
+    fn is_stalled(&self, now: u64, stall_millis: u64) -> bool {
+        let last_activity = self.last_activity.load(Ordering::Relaxed);
+        self.running.load(Ordering::Relaxed) && now.saturating_sub(last_activity) >= stall_millis
+    }
+}
+
+/// Record an activity now.
+pub(crate) fn touch(activity: &AtomicU64) {
+    activity.store(now_millis(), Ordering::Relaxed);
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Every `stall_timeout / 2`, signal the running nodes without activity for `stall_timeout` to
+/// restart. Runs until aborted.
+///
+/// If all the running nodes are stalled while sending, none of them receives, so they wait
+/// for each other forever: they are signaled as deadlocked instead.
+pub(crate) async fn watch(nodes: Vec<(NodeId, Arc<WatchState>)>, stall_timeout: Duration) {
+    let stall_millis = stall_timeout.as_millis() as u64;
+    let mut interval = tokio::time::interval((stall_timeout / 2).max(Duration::from_millis(1)));
+    loop {
+        interval.tick().await;
+        let now = now_millis();
+        let running: Vec<_> = nodes
+            .iter()
+            .filter(|(_, state)| state.running.load(Ordering::Relaxed))
+            .collect();
+        if !running.is_empty()
+            && running.iter().all(|(_, state)| {
+                state.is_stalled(now, stall_millis)
+                    && state.pending_sends.load(Ordering::Relaxed) > 0
+            })
+        {
+            let ids: Vec<usize> = running.iter().map(|(id, _)| id.0).collect();
+            warn!("Nodes {:?} are deadlocked, all blocked on sending", ids);
+            for (_, state) in running {
+                state.deadlocked.store(true, Ordering::Relaxed);
+                state.restart.notify_waiters();
+            }
+            continue;
+        }
+        for (id, state) in &nodes {
+            if state.is_stalled(now, stall_millis) {
+                warn!(
+                    "Node [id: {}] has no activity for {:?}, restarting",
+                    id.0, stall_timeout
+                );
+                touch(&state.last_activity);
+                state.restart.notify_waiters();
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Cooperative yielding for long-running nodes.
+//!
+//! Nodes run as tasks on the tokio runtime, which is scheduled cooperatively: a node that
+//! computes for a long time without awaiting keeps its worker thread, and the other nodes
+//! scheduled on it make no progress. Moderately heavy nodes can stay fair without
+//! `spawn_blocking` by yielding to the runtime at intervals, either with
+//! [`tokio::task::yield_now`], or by iterating over their work with [`yielding`].
+//!
+//! Each yield point is also where a node can be cancelled: a node that times out, see
+//! [`Graph::set_node_timeout`](crate::Graph::set_node_timeout), or that the watchdog
+//! restarts, see [`Graph::set_watchdog`](crate::Graph::set_watchdog), is only stopped at its
+//! next await.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//! use dagrs::{yielding, Action, EnvVar, InChannels, OutChannels, Output};
+//! use async_trait::async_trait;
+//!
+//! struct SumAction;
+//!
+//! #[async_trait]
+//! impl Action for SumAction {
+//!     async fn run(&self, _: &mut InChannels, _: &mut OutChannels, _: Arc<EnvVar>) -> Output {
+//!         let mut sum = 0u64;
+//!         let mut items = yielding(0..1_000_000u64, 1024);
+//!         while let Some(i) = items.next().await {
+//!             sum += i;
+//!         }
+//!         Output::new(sum)
+//!     }
+//! }
+//! ```
+
+/// An iterator adapter yielding to the runtime every `every` items, see [`yielding`].
+pub struct Yielding<I> {
+    iter: I,
+    every: usize,
+    count: usize,
+}
+
+/// Iterates over `iter`, yielding to the runtime before every `every` items.
+///
+/// # Panics
+/// Panics if `every` is 0.
+pub fn yielding<I: IntoIterator>(iter: I, every: usize) -> Yielding<I::IntoIter> {
+    assert!(every > 0, "yield interval must be positive");
+    Yielding {
+        iter: iter.into_iter(),
+        every,
+        count: 0,
+    }
+}
+
+impl<I: Iterator> Yielding<I> {
+    /// Returns the next item, after yielding to the runtime if `every` items were returned
+    /// since the last yield.
+    pub async fn next(&mut self) -> Option<I::Item> {
+        if self.count == self.every {
+            self.count = 0;
+            tokio::task::yield_now().await;
+        }
+        self.count += 1;
+        self.iter.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    /// Test for letting another task run while iterating.
+    ///
+    /// On a single thread, the spawned task only runs once the iteration yields, after the
+    /// first 10 items.
+    #[test]
+    fn test_yielding() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let flag = Arc::new(AtomicBool::new(false));
+            let set = flag.clone();
+            tokio::spawn(async move { set.store(true, Ordering::SeqCst) });
+
+            let mut first_seen = None;
+            let mut items = yielding(0..100, 10);
+            while let Some(i) = items.next().await {
+                if first_seen.is_none() && flag.load(Ordering::SeqCst) {
+                    first_seen = Some(i);
+                }
+            }
+            assert_eq!(first_seen, Some(10));
+        });
+    }
+}